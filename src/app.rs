@@ -1,15 +1,41 @@
-use crate::file_dialog::{scan_folder_async, select_files, select_image_async};
+use crate::file_dialog::{
+    find_directory_covers, scan_folder_async, select_files, select_image_async,
+};
 use crate::message::Message;
-use crate::metadata::{process_files, read_file_metadata};
-use crate::model::{AppState, Screen};
-use crate::settings::AppSettings;
+use crate::metadata::{
+    process_files, read_file_metadata, read_lyrics, update_files, ReplayGain, TagUpdate,
+};
+use crate::model::{AppState, EditorPanel, FileMetadata, Screen, SpectrumView};
+use crate::settings::{AppSettings, TagPreset};
 use crate::theme::{cosmic_theme, ThemeMode};
-use crate::utils::audio_player;
-use crate::utils::scraper::SongHubScraper;
+use crate::utils::audio_player::{self, PlayerEvent, PlayerSource};
+use crate::utils::download_queue::{
+    retry_delay, DownloadItem, DownloadProgress, DownloadQueue, DownloadState, MAX_ATTEMPTS,
+};
+use crate::utils::folder_watcher::{self, WatchEvent};
+use crate::utils::genres::genre_key;
+use crate::utils::integrity::{check_file, FileCheck, IntegrityIssue};
+use crate::utils::loudness::{analyze_file, AlbumLoudness, TrackLoudness};
+use crate::utils::lyrics::{self, lines_from_text, lrc_path, LyricLine, Lyrics};
+use crate::utils::music_downloader::{sanitize_filename, tag_download};
+use crate::utils::quality::analyze_quality;
+use crate::utils::scraper::{part_path, Song, SongHubScraper};
+use crate::utils::spectrum::{self, spectrogram_svg, waveform_svg};
+use crate::utils::text_rules::TagField;
+use crate::utils::track_numbering::{album_dir, album_files, assign_track_numbers};
 use crate::view::build_view;
+use futures::future::{AbortHandle, AbortRegistration, Abortable};
 use iced::time;
-use iced::{Application, Command, Subscription, Theme};
-use std::time::Duration;
+use iced::widget::{svg, text_editor};
+use iced::{event, keyboard, Application, Command, Event, Subscription, Theme};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// How often a running download reports its byte count
+const DOWNLOAD_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+/// Downloads running at once
+const MAX_DOWNLOADS: usize = 5;
 
 pub struct MusicToolsApp {
     state: AppState,
@@ -33,14 +59,32 @@ impl Application for MusicToolsApp {
         if let Some(path) = settings.get_metadata_folder() {
             state.last_metadata_folder = Some(path);
         }
+        if let Some(path) = settings.get_watch_folder() {
+            state.watch_status = format!("Ready to watch {}", path.display());
+            state.watch_folder = Some(path);
+        }
+        state.watch_rules = settings.watch_rules;
+        state.presets = settings.presets;
+        state.custom_genres = settings.custom_genres;
+        state.crossfade_secs = settings.crossfade_secs;
+        state.play_through = settings.play_through;
+        if state.crossfade_secs > 0.0 {
+            audio_player::set_crossfade(Duration::from_secs_f32(state.crossfade_secs));
+        }
 
-        (
-            Self {
-                state,
-                theme_mode: ThemeMode::Dark,
-            },
-            Command::none(),
-        )
+        // Pick up downloads left unfinished by the last session
+        state.downloader_state.downloads = DownloadQueue::load();
+
+        let mut app = Self {
+            state,
+            theme_mode: ThemeMode::Dark,
+        };
+        let command = if app.state.downloader_state.downloads.items.is_empty() {
+            Command::none()
+        } else {
+            app.start_queued_downloads()
+        };
+        (app, command)
     }
 
     fn title(&self) -> String {
@@ -49,6 +93,7 @@ impl Application for MusicToolsApp {
             Screen::MetadataEditor => "Metadata Editor - Music Tools".to_string(),
             Screen::MusicDownloader => "Music Downloader - Music Tools".to_string(),
             Screen::AudioConverter => "Audio Converter - Music Tools".to_string(),
+            Screen::FolderWatcher => "Folder Watcher - Music Tools".to_string(),
         }
     }
 
@@ -65,6 +110,11 @@ impl Application for MusicToolsApp {
                     // Clear download logs when leaving downloader
                     self.state.error_logs.clear();
                 }
+                if self.state.current_screen == Screen::MetadataEditor
+                    && screen != Screen::MetadataEditor
+                {
+                    self.stop_editor_playback();
+                }
                 // Clear error logs when entering metadata editor (they're for metadata processing only)
                 if screen == Screen::MetadataEditor {
                     self.state.error_logs.clear();
@@ -88,6 +138,9 @@ impl Application for MusicToolsApp {
                 Command::none()
             }
             Message::GoHome => {
+                if self.state.current_screen == Screen::MetadataEditor {
+                    self.stop_editor_playback();
+                }
                 // Stop any playing audio when going home
                 if self.state.current_screen == Screen::MusicDownloader {
                     audio_player::stop_audio();
@@ -152,16 +205,45 @@ impl Application for MusicToolsApp {
                 self.state.loading_files = false;
                 self.state.files = paths;
                 self.state.selected_file_index = None;
+                self.state.checked_files.clear();
+                self.state.last_checked_index = None;
                 self.state.file_metadata.clear();
+                self.state.directory_covers.clear();
+                self.state.album_groups.clear();
+                self.state.selected_group = None;
+                self.state.rebuild_album_groups();
                 self.state.status = format!("{} file(s) loaded", self.state.files.len());
-                Command::none()
+                let files = self.state.files.clone();
+                Command::batch([
+                    load_all_metadata(files.clone()),
+                    Command::perform(
+                        async move {
+                            tokio::task::spawn_blocking(move || find_directory_covers(&files))
+                                .await
+                                .unwrap_or_default()
+                        },
+                        Message::DirectoryCoversFound,
+                    ),
+                ])
             }
             Message::ArtistChanged(value) => {
-                self.state.artist = value;
+                if let Some(group) = self.state.editing_group_mut() {
+                    // Clearing the field hands it back to the shared value
+                    group.artist = Some(value.clone()).filter(|v| !v.trim().is_empty());
+                } else {
+                    self.state.artist = value.clone();
+                }
+                self.state.update_artist_suggestions(&value);
                 Command::none()
             }
             Message::AlbumChanged(value) => {
-                self.state.album = value;
+                if let Some(group) = self.state.editing_group_mut() {
+                    // Clearing the field hands it back to the shared value
+                    group.album = Some(value.clone()).filter(|v| !v.trim().is_empty());
+                } else {
+                    self.state.album = value.clone();
+                }
+                self.state.update_album_suggestions(&value);
                 Command::none()
             }
             Message::GenreChanged(value) => {
@@ -169,17 +251,72 @@ impl Application for MusicToolsApp {
                 Command::none()
             }
             Message::YearChanged(value) => {
-                self.state.year = value;
+                if let Some(group) = self.state.editing_group_mut() {
+                    // Clearing the field hands it back to the shared value
+                    group.year = Some(value).filter(|v| !v.trim().is_empty());
+                } else {
+                    self.state.year = value;
+                }
                 Command::none()
             }
             Message::SelectImage => {
                 Command::perform(async { select_image_async().await }, Message::ImageSelected)
             }
             Message::ImageSelected(path) => {
-                self.state.album_art_path = path;
-                if self.state.album_art_path.is_some() {
+                if path.is_some() {
                     self.state.status = "Album art selected".to_string();
                 }
+                if let Some(group) = self.state.editing_group_mut() {
+                    group.album_art_path = path;
+                } else {
+                    self.state.album_art_path = path;
+                }
+                Command::none()
+            }
+            Message::GroupByAlbumToggled(enabled) => {
+                self.state.group_by_album = enabled;
+                self.state.selected_group = None;
+                if enabled {
+                    self.state.status =
+                        format!("{} album folder(s)", self.state.album_groups.len());
+                }
+                Command::none()
+            }
+            Message::SelectAlbumGroup(index) => {
+                if self.state.selected_group == Some(index) {
+                    self.state.selected_group = None;
+                } else if index < self.state.album_groups.len() {
+                    self.state.selected_group = Some(index);
+                }
+                Command::none()
+            }
+            Message::ToggleGroupChecked(index) => {
+                if let Some(indices) = self.state.group_file_indices().get(index) {
+                    let all_checked = indices.iter().all(|i| self.state.checked_files.contains(i));
+                    for i in indices {
+                        if all_checked {
+                            self.state.checked_files.remove(i);
+                        } else {
+                            self.state.checked_files.insert(*i);
+                        }
+                    }
+                    self.state.last_checked_index = None;
+                }
+                Command::none()
+            }
+            Message::DirectoryCoversFound(covers) => {
+                if !covers.is_empty() {
+                    self.state.status = format!(
+                        "{} file(s) loaded, cover images found in {} folder(s)",
+                        self.state.files.len(),
+                        covers.len()
+                    );
+                }
+                self.state.directory_covers = covers;
+                Command::none()
+            }
+            Message::UseDirectoryCoversToggled(enabled) => {
+                self.state.use_directory_covers = enabled;
                 Command::none()
             }
             Message::ProcessFiles => {
@@ -187,33 +324,831 @@ impl Application for MusicToolsApp {
                     self.state.status = "No files selected".to_string();
                     return Command::none();
                 }
-                if self.state.artist.trim().is_empty()
-                    && self.state.album.trim().is_empty()
-                    && self.state.genre.trim().is_empty()
-                    && self.state.year.trim().is_empty()
-                    && self.state.album_art_path.is_none()
-                {
+                let jobs: Vec<(PathBuf, TagUpdate)> = self
+                    .state
+                    .target_files()
+                    .into_iter()
+                    .map(|file| {
+                        let update = self.state.tag_update_for(&file);
+                        (file, update)
+                    })
+                    .collect();
+                if jobs.iter().all(|(_, update)| update.is_empty()) {
                     self.state.status = "Please fill in at least one metadata field".to_string();
                     return Command::none();
                 }
 
                 self.state.processing = true;
+                self.state.processing_count = jobs.len();
                 self.state.error_logs.clear();
-                self.state.status = format!("Processing {} files...", self.state.files.len());
+                self.state.status = format!("Processing {} files...", jobs.len());
 
-                let files = self.state.files.clone();
-                let artist = self.state.artist.clone();
-                let album = self.state.album.clone();
-                let genre = if self.state.genre.trim().is_empty() {
-                    None
+                Command::perform(
+                    async move { process_files(jobs).await },
+                    Message::ProcessingComplete,
+                )
+            }
+            Message::TrackOrderChanged(order) => {
+                self.state.track_order = order;
+                Command::none()
+            }
+            Message::NumberTracks => {
+                if self.state.files.is_empty() {
+                    self.state.status = "No files selected".to_string();
+                    return Command::none();
+                }
+                // Only the numbering fields are written; other tags stay as they are
+                let jobs: Vec<(PathBuf, TagUpdate)> =
+                    assign_track_numbers(&self.state.target_files(), self.state.track_order)
+                        .into_iter()
+                        .map(|(file, number)| {
+                            let update = TagUpdate {
+                                track: Some(number.track),
+                                track_total: Some(number.track_total),
+                                disc: number.disc,
+                                disc_total: number.disc_total,
+                                ..TagUpdate::default()
+                            };
+                            (file, update)
+                        })
+                        .collect();
+
+                self.state.processing = true;
+                self.state.processing_count = jobs.len();
+                self.state.error_logs.clear();
+                self.state.status = format!("Numbering {} files...", jobs.len());
+
+                Command::perform(
+                    async move { update_files(jobs).await },
+                    Message::ProcessingComplete,
+                )
+            }
+            Message::ShowEditorPanel(panel) => {
+                self.state.editor_panel = panel;
+                match panel {
+                    EditorPanel::FileDetails => self.load_spectrum(),
+                    EditorPanel::Lyrics => self.load_lyrics(),
+                    _ => Command::none(),
+                }
+            }
+            Message::TextRuleToggled(rule, enabled) => {
+                self.state.text_rules.set_enabled(rule, enabled);
+                Command::none()
+            }
+            Message::TextCaseChanged(case) => {
+                self.state.text_rules.case = case;
+                Command::none()
+            }
+            Message::TextFindChanged(find) => {
+                self.state.text_rules.find = find;
+                Command::none()
+            }
+            Message::TextReplaceChanged(replace) => {
+                self.state.text_rules.replace = replace;
+                Command::none()
+            }
+            Message::ApplyTextRules => {
+                let changes = match self.state.text_rule_changes() {
+                    Ok(changes) => changes,
+                    Err(e) => {
+                        self.state.status = format!("Error: {}", e);
+                        return Command::none();
+                    }
+                };
+                if changes.is_empty() {
+                    self.state.status = "Nothing to clean up".to_string();
+                    return Command::none();
+                }
+                // Only the changed fields are written; other tags stay as they are
+                let jobs: Vec<(PathBuf, TagUpdate)> = changes
+                    .into_iter()
+                    .map(|(index, fields)| {
+                        let mut update = TagUpdate::default();
+                        for change in fields {
+                            let value = Some(change.after);
+                            match change.field {
+                                TagField::Title => update.title = value,
+                                TagField::Artist => update.artist = value,
+                                TagField::Album => update.album = value,
+                                TagField::Genre => update.genre = value,
+                            }
+                        }
+                        (self.state.files[index].clone(), update)
+                    })
+                    .collect();
+
+                self.state.processing = true;
+                self.state.processing_count = jobs.len();
+                self.state.error_logs.clear();
+                self.state.editor_panel = EditorPanel::FileDetails;
+                self.state.status = format!("Cleaning up {} files...", jobs.len());
+
+                Command::perform(
+                    async move { update_files(jobs).await },
+                    Message::ProcessingComplete,
+                )
+            }
+            Message::PresetNameChanged(name) => {
+                self.state.preset_name = name;
+                Command::none()
+            }
+            Message::SavePreset => {
+                let name = self.state.preset_name.trim().to_string();
+                if name.is_empty() {
+                    self.state.status = "Enter a name for the preset".to_string();
+                    return Command::none();
+                }
+                // Save what the edit panel currently shows, including a selected album group
+                let (artist, album, year, cover) = match self.state.editing_group() {
+                    Some(g) => (
+                        g.artist.as_ref().unwrap_or(&self.state.artist),
+                        g.album.as_ref().unwrap_or(&self.state.album),
+                        g.year.as_ref().unwrap_or(&self.state.year),
+                        &g.album_art_path,
+                    ),
+                    None => (
+                        &self.state.artist,
+                        &self.state.album,
+                        &self.state.year,
+                        &self.state.album_art_path,
+                    ),
+                };
+                let preset = TagPreset {
+                    name: name.clone(),
+                    artist: artist.clone(),
+                    album: album.clone(),
+                    genre: self.state.genre.clone(),
+                    year: year.clone(),
+                    cover_path: cover.as_ref().map(|p| p.to_string_lossy().to_string()),
+                    text_rules: self.state.text_rules.clone(),
+                };
+                match self.state.presets.iter_mut().find(|p| p.name == name) {
+                    Some(existing) => {
+                        *existing = preset;
+                        self.state.status = format!("Updated preset '{}'", name);
+                    }
+                    None => {
+                        self.state.presets.push(preset);
+                        self.state.status = format!("Saved preset '{}'", name);
+                    }
+                }
+                self.save_presets();
+                Command::none()
+            }
+            Message::LoadPreset(index) => {
+                if let Some(preset) = self.state.presets.get(index).cloned() {
+                    let cover = preset.get_cover_path();
+                    match self.state.editing_group_mut() {
+                        Some(g) => {
+                            let edited = |v: String| Some(v).filter(|v| !v.trim().is_empty());
+                            g.artist = edited(preset.artist);
+                            g.album = edited(preset.album);
+                            g.year = edited(preset.year);
+                            g.album_art_path = cover;
+                        }
+                        None => {
+                            self.state.artist = preset.artist;
+                            self.state.album = preset.album;
+                            self.state.year = preset.year;
+                            self.state.album_art_path = cover;
+                        }
+                    }
+                    self.state.genre = preset.genre;
+                    self.state.text_rules = preset.text_rules;
+                    self.state.preset_name = preset.name.clone();
+                    self.state.status = format!("Loaded preset '{}'", preset.name);
+                }
+                Command::none()
+            }
+            Message::RenamePreset(index) => {
+                let name = self.state.preset_name.trim().to_string();
+                if name.is_empty() {
+                    self.state.status = "Enter the new name for the preset".to_string();
+                    return Command::none();
+                }
+                if self
+                    .state
+                    .presets
+                    .iter()
+                    .enumerate()
+                    .any(|(i, p)| i != index && p.name == name)
+                {
+                    self.state.status = format!("A preset named '{}' already exists", name);
+                    return Command::none();
+                }
+                if let Some(preset) = self.state.presets.get_mut(index) {
+                    self.state.status = format!("Renamed preset '{}' to '{}'", preset.name, name);
+                    preset.name = name;
+                    self.save_presets();
+                }
+                Command::none()
+            }
+            Message::DeletePreset(index) => {
+                if index < self.state.presets.len() {
+                    let preset = self.state.presets.remove(index);
+                    self.state.status = format!("Deleted preset '{}'", preset.name);
+                    self.save_presets();
+                }
+                Command::none()
+            }
+            Message::CustomGenreInputChanged(value) => {
+                self.state.custom_genre_input = value;
+                Command::none()
+            }
+            Message::AddCustomGenre => {
+                let genre = self.state.custom_genre_input.trim().to_string();
+                if genre.is_empty() {
+                    return Command::none();
+                }
+                let key = genre_key(&genre);
+                if self.state.custom_genres.iter().any(|g| genre_key(g) == key) {
+                    self.state.status = format!("'{}' is already in the custom genres", genre);
+                    return Command::none();
+                }
+                self.state.custom_genres.push(genre);
+                self.state.custom_genres.sort();
+                self.state.custom_genre_input.clear();
+                self.save_custom_genres();
+                Command::none()
+            }
+            Message::RemoveCustomGenre(index) => {
+                if index < self.state.custom_genres.len() {
+                    self.state.custom_genres.remove(index);
+                    self.save_custom_genres();
+                }
+                Command::none()
+            }
+            Message::MergeSourceSelected(genre) => {
+                self.state.merge_source = Some(genre);
+                Command::none()
+            }
+            Message::MergeTargetChanged(genre) => {
+                self.state.merge_target = genre;
+                Command::none()
+            }
+            Message::MergeGenres(variants, canonical) => {
+                let canonical = canonical.trim().to_string();
+                if canonical.is_empty() {
+                    self.state.status = "Enter the genre to merge into".to_string();
+                    return Command::none();
+                }
+                let jobs: Vec<(PathBuf, TagUpdate)> = self
+                    .state
+                    .file_metadata
+                    .iter()
+                    .filter(|(_, m)| {
+                        let genre = m.genre.trim();
+                        genre != canonical && variants.iter().any(|v| v == genre)
+                    })
+                    .filter_map(|(index, _)| self.state.files.get(*index))
+                    .map(|file| {
+                        let update = TagUpdate {
+                            genre: Some(canonical.clone()),
+                            ..TagUpdate::default()
+                        };
+                        (file.clone(), update)
+                    })
+                    .collect();
+                if jobs.is_empty() {
+                    self.state.status = "No files use those genres".to_string();
+                    return Command::none();
+                }
+
+                self.state.merge_source = None;
+                self.state.processing = true;
+                self.state.processing_count = jobs.len();
+                self.state.error_logs.clear();
+                self.state.status = format!(
+                    "Merging genres into '{}' in {} files...",
+                    canonical,
+                    jobs.len()
+                );
+
+                Command::perform(
+                    async move { update_files(jobs).await },
+                    Message::ProcessingComplete,
+                )
+            }
+            Message::AnalyzeLoudness => {
+                if self.state.files.is_empty() {
+                    self.state.status = "No files selected".to_string();
+                    return Command::none();
+                }
+                self.state.loudness_queue = self.state.target_files();
+                self.state.loudness_queue.reverse();
+                self.state.loudness_total = self.state.loudness_queue.len();
+                self.state.loudness_results.clear();
+                self.state.analyzing_loudness = true;
+                self.analyze_next_loudness()
+            }
+            Message::StopLoudnessAnalysis => {
+                // The file being decoded finishes; nothing after it is started
+                self.state.loudness_queue.clear();
+                Command::none()
+            }
+            Message::LoudnessAnalyzed(file, result) => {
+                self.state.loudness_results.push((file, result));
+                self.analyze_next_loudness()
+            }
+            Message::WriteReplayGain => {
+                let measured: Vec<(&PathBuf, &TrackLoudness)> = self
+                    .state
+                    .loudness_results
+                    .iter()
+                    .filter_map(|(file, result)| result.as_ref().ok().map(|l| (file, l)))
+                    .collect();
+
+                // Album values only make sense for folders the user treats as albums, and
+                // only when every track of the album was measured
+                let mut albums: HashMap<PathBuf, AlbumLoudness> = HashMap::new();
+                let mut partial_albums = 0;
+                if self.state.group_by_album {
+                    let mut by_dir: HashMap<PathBuf, Vec<&TrackLoudness>> = HashMap::new();
+                    for (file, loudness) in &measured {
+                        if let Some(dir) = album_dir(file) {
+                            by_dir.entry(dir.to_path_buf()).or_default().push(loudness);
+                        }
+                    }
+                    let measured_files: HashSet<&PathBuf> =
+                        measured.iter().map(|(file, _)| *file).collect();
+                    for (dir, tracks) in by_dir {
+                        if album_files(&dir)
+                            .iter()
+                            .all(|file| measured_files.contains(file))
+                        {
+                            albums.insert(dir, AlbumLoudness::from_tracks(tracks));
+                        } else {
+                            partial_albums += 1;
+                        }
+                    }
+                }
+
+                let jobs: Vec<(PathBuf, TagUpdate)> = measured
+                    .iter()
+                    .filter_map(|(file, loudness)| {
+                        let album = album_dir(file).and_then(|dir| albums.get(dir));
+                        let replay_gain = ReplayGain {
+                            track_gain: loudness.gain_db()?,
+                            track_peak: loudness.true_peak,
+                            album_gain: album.and_then(|a| a.gain_db()),
+                            album_peak: album.map(|a| a.true_peak),
+                        };
+                        let update = TagUpdate {
+                            replay_gain: Some(replay_gain),
+                            ..TagUpdate::default()
+                        };
+                        Some(((*file).clone(), update))
+                    })
+                    .collect();
+                if jobs.is_empty() {
+                    self.state.status = "No loudness results to write".to_string();
+                    return Command::none();
+                }
+
+                self.state.processing = true;
+                self.state.processing_count = jobs.len();
+                self.state.error_logs.clear();
+                self.state.status = if partial_albums > 0 {
+                    format!(
+                        "Writing ReplayGain to {} files (no album gain for {} folder(s) \
+                         with unanalyzed files)...",
+                        jobs.len(),
+                        partial_albums
+                    )
+                } else {
+                    format!("Writing ReplayGain to {} files...", jobs.len())
+                };
+
+                Command::perform(
+                    async move { update_files(jobs).await },
+                    Message::ProcessingComplete,
+                )
+            }
+            Message::VerifyFiles => {
+                if self.state.files.is_empty() {
+                    self.state.status = "No files selected".to_string();
+                    return Command::none();
+                }
+                self.state.verify_queue = self.state.target_files();
+                self.state.verify_queue.reverse();
+                self.state.verify_total = self.state.verify_queue.len();
+                self.state.verify_results.clear();
+                self.state.verifying = true;
+                self.verify_next_file()
+            }
+            Message::StopVerification => {
+                self.state.verify_queue.clear();
+                Command::none()
+            }
+            Message::FileVerified(file, check) => {
+                self.state.verify_results.push((file, check));
+                self.verify_next_file()
+            }
+            Message::CheckProblemFiles => {
+                let problems: Vec<&PathBuf> = self
+                    .state
+                    .verify_results
+                    .iter()
+                    .filter(|(_, check)| !check.is_ok())
+                    .map(|(file, _)| file)
+                    .collect();
+                self.state.checked_files = self
+                    .state
+                    .files
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, f)| problems.contains(f))
+                    .map(|(i, _)| i)
+                    .collect();
+                self.state.last_checked_index = None;
+                self.state.status = format!(
+                    "{} file(s) with problems selected",
+                    self.state.checked_files.len()
+                );
+                Command::none()
+            }
+            Message::AnalyzeQuality => {
+                if self.state.files.is_empty() {
+                    self.state.status = "No files selected".to_string();
+                    return Command::none();
+                }
+                self.state.quality_queue = self.state.target_files();
+                self.state.quality_queue.reverse();
+                self.state.quality_total = self.state.quality_queue.len();
+                self.state.quality_results.clear();
+                self.state.analyzing_quality = true;
+                self.analyze_next_quality()
+            }
+            Message::StopQualityAnalysis => {
+                self.state.quality_queue.clear();
+                Command::none()
+            }
+            Message::QualityAnalyzed(file, result) => {
+                self.state.quality_results.insert(file, result);
+                self.analyze_next_quality()
+            }
+            Message::CheckSuspectFiles => {
+                let results = &self.state.quality_results;
+                self.state.checked_files = self
+                    .state
+                    .files
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, f)| matches!(results.get(*f), Some(Ok(r)) if r.is_suspect()))
+                    .map(|(i, _)| i)
+                    .collect();
+                self.state.last_checked_index = None;
+                self.state.status = format!(
+                    "{} suspect file(s) selected",
+                    self.state.checked_files.len()
+                );
+                Command::none()
+            }
+            Message::SpectrumReady(file, result) => {
+                if self.state.spectrum_file.as_ref() == Some(&file) {
+                    self.state.spectrum = Some(result);
+                }
+                Command::none()
+            }
+            Message::LyricsLoaded(file, result) => {
+                // Ignore a slow load for a file that is no longer selected
+                if self.state.lyrics_file.as_ref() != Some(&file) {
+                    return Command::none();
+                }
+                match result {
+                    Ok(lyrics) => {
+                        self.state.lyrics_text = text_editor::Content::with_text(&lyrics.unsynced);
+                        self.state.lyrics_lines = lyrics.synced;
+                        self.state.status = "Lyrics loaded".to_string();
+                    }
+                    Err(e) => self.state.status = format!("Error reading lyrics: {}", e),
+                }
+                Command::none()
+            }
+            Message::LyricsTextEdited(action) => {
+                self.state.lyrics_text.perform(action);
+                Command::none()
+            }
+            Message::LyricLineTextChanged(index, value) => {
+                if let Some(line) = self.state.lyrics_lines.get_mut(index) {
+                    line.text = value;
+                }
+                Command::none()
+            }
+            Message::SelectLyricLine(index) => {
+                self.state.lyrics_cursor = index;
+                Command::none()
+            }
+            Message::StampLyricLine => {
+                let position =
+                    audio_player::playback_position().filter(|_| self.state.lyrics_playing());
+                let Some(position) = position else {
+                    self.state.status = "Start playback to stamp timestamps".to_string();
+                    return Command::none();
+                };
+                let cursor = self.state.lyrics_cursor;
+                if let Some(line) = self.state.lyrics_lines.get_mut(cursor) {
+                    line.time = position;
+                    self.state.lyrics_cursor = (cursor + 1).min(self.state.lyrics_lines.len());
+                }
+                Command::none()
+            }
+            Message::NudgeLyricLine(index, millis) => {
+                if let Some(line) = self.state.lyrics_lines.get_mut(index) {
+                    let time = line.time.as_millis() as i64 + millis;
+                    line.time = Duration::from_millis(time.max(0) as u64);
+                }
+                Command::none()
+            }
+            Message::AddLyricLine => {
+                // New lines start where the current line is, so they sort into place
+                let time = self
+                    .state
+                    .lyrics_lines
+                    .get(self.state.lyrics_cursor)
+                    .map(|l| l.time)
+                    .unwrap_or(self.state.lyrics_position());
+                let index = (self.state.lyrics_cursor + 1).min(self.state.lyrics_lines.len());
+                self.state.lyrics_lines.insert(
+                    index,
+                    LyricLine {
+                        time,
+                        text: String::new(),
+                    },
+                );
+                self.state.lyrics_cursor = index;
+                Command::none()
+            }
+            Message::RemoveLyricLine(index) => {
+                if index < self.state.lyrics_lines.len() {
+                    self.state.lyrics_lines.remove(index);
+                    if self.state.lyrics_cursor > index {
+                        self.state.lyrics_cursor -= 1;
+                    }
+                }
+                Command::none()
+            }
+            Message::LyricLinesFromText => {
+                self.state.lyrics_lines = lines_from_text(&self.state.lyrics_text.text());
+                self.state.lyrics_cursor = 0;
+                self.state.status = format!(
+                    "{} line(s) ready: play the track and stamp each line",
+                    self.state.lyrics_lines.len()
+                );
+                Command::none()
+            }
+            Message::PreviewFile(index) => self.preview_file(index, Duration::ZERO),
+            Message::StopPreview | Message::StopLyricsPlayback => {
+                audio_player::stop_audio();
+                Command::none()
+            }
+            Message::PreviewNext | Message::PreviewPrevious => {
+                // Step from the playing file, or from the selection when nothing plays
+                let current = self
+                    .state
+                    .playing_file()
+                    .and_then(|file| self.state.files.iter().position(|f| f == file))
+                    .or(self.state.selected_file_index);
+                let target = match (current, &message) {
+                    (Some(i), Message::PreviewNext) => i + 1,
+                    (Some(i), _) => i.saturating_sub(1),
+                    (None, _) => 0,
+                };
+                if target < self.state.files.len() {
+                    self.preview_file(target, Duration::ZERO)
                 } else {
-                    Some(self.state.genre.clone())
+                    Command::none()
+                }
+            }
+            Message::TogglePlayThrough(play_through) => {
+                self.state.play_through = play_through;
+                let mut settings = AppSettings::load();
+                settings.play_through = play_through;
+                let _ = settings.save();
+                self.queue_next_file();
+                Command::none()
+            }
+            Message::PlayLyricsFrom(start) => {
+                let Some(file) = self.state.lyrics_file.clone() else {
+                    return Command::none();
+                };
+                self.state.preview_follow = false;
+                audio_player::play_file(file, start);
+                Command::none()
+            }
+            Message::PlayerPause => {
+                audio_player::pause();
+                Command::none()
+            }
+            Message::PlayerResume => {
+                audio_player::resume();
+                Command::none()
+            }
+            Message::PlayerSeek(seconds) => {
+                self.state.player_seek = Some(seconds);
+                Command::none()
+            }
+            Message::PlayerSeekReleased => {
+                let Some(seconds) = self.state.player_seek.take() else {
+                    return Command::none();
                 };
-                let year = self.state.year.parse::<u32>().ok();
-                let album_art = self.state.album_art_path.clone();
+                let position = Duration::from_secs_f32(seconds.max(0.0));
+                let selected = self
+                    .state
+                    .selected_file_index
+                    .filter(|_| self.state.current_screen == Screen::MetadataEditor);
+                match selected {
+                    // The editor's slider starts the selected file when something else plays
+                    Some(index)
+                        if self.state.playing_file()
+                            != self.state.files.get(index).map(|f| f.as_path()) =>
+                    {
+                        self.preview_file(index, position)
+                    }
+                    _ => {
+                        audio_player::seek(position);
+                        self.state.player.position = position;
+                        Command::none()
+                    }
+                }
+            }
+            Message::PlayerVolume(volume) => {
+                audio_player::set_volume(volume);
+                self.state.player.volume = volume;
+                Command::none()
+            }
+            Message::CrossfadeChanged(seconds) => {
+                self.state.crossfade_secs = seconds;
+                audio_player::set_crossfade(Duration::from_secs_f32(seconds));
+                Command::none()
+            }
+            Message::CrossfadeReleased => {
+                let mut settings = AppSettings::load();
+                settings.crossfade_secs = self.state.crossfade_secs;
+                let _ = settings.save();
+                Command::none()
+            }
+            Message::PlayerStatusChanged(status) => {
+                self.state.player = status;
+                Command::none()
+            }
+            Message::PlaybackStarted(source) => {
+                let downloader = &mut self.state.downloader_state;
+                // The player moved on to the queued song it was handed
+                let handed_over = downloader
+                    .next_stream
+                    .take_if(|(_, url)| matches!(&source, PlayerSource::Url(u) if u == url));
+                if let Some((index, url)) = handed_over {
+                    downloader.playing_song_index = Some(index);
+                    downloader.streaming_url = Some(url);
+                    downloader.queue_position =
+                        downloader.play_queue.iter().position(|&i| i == index);
+                }
+                if let Some(song) = self.streamed_song(&source) {
+                    self.state.downloader_state.status = format!("Playing: {}", song.title);
+                    return self.queue_next_song();
+                }
+                match source {
+                    PlayerSource::File(file) if self.state.preview_follow => {
+                        self.queue_next_file();
+                        // Follow a play-through onto the next file
+                        let index = self.state.files.iter().position(|f| *f == file);
+                        match index {
+                            Some(index)
+                                if self.state.current_screen == Screen::MetadataEditor
+                                    && self.state.selected_file_index != Some(index) =>
+                            {
+                                self.update(Message::FileSelected(index))
+                            }
+                            _ => Command::none(),
+                        }
+                    }
+                    _ => Command::none(),
+                }
+            }
+            Message::PlaybackFinished(source) => {
+                // Without a handed-over next song, start it here
+                if self.streamed_song(&source).is_none()
+                    || self.state.downloader_state.next_stream.is_some()
+                {
+                    return Command::none();
+                }
+                // Carry on with the queue when the song was played from it
+                let next = self
+                    .state
+                    .downloader_state
+                    .queue_position
+                    .and_then(|_| self.state.downloader_state.next_queued());
+                match next {
+                    Some(index) => self.update(Message::PlaySong(index)),
+                    None => {
+                        self.clear_playing_song();
+                        Command::none()
+                    }
+                }
+            }
+            Message::PlaybackFailed(source, reason) => {
+                match &source {
+                    PlayerSource::Url(url) => {
+                        let downloader = &mut self.state.downloader_state;
+                        if downloader
+                            .next_stream
+                            .as_ref()
+                            .is_some_and(|(_, u)| u == url)
+                        {
+                            downloader.next_stream = None;
+                        }
+                        if self.streamed_song(&source).is_some() {
+                            self.clear_playing_song();
+                            self.state.downloader_state.status =
+                                format!("Playback failed: {}", reason);
+                        }
+                    }
+                    PlayerSource::File(file) => {
+                        self.state.status = format!(
+                            "Cannot play {}: {}",
+                            file.file_name().unwrap_or_default().to_string_lossy(),
+                            reason
+                        );
+                    }
+                }
+                Command::none()
+            }
+            Message::ImportLrc => {
+                let Some(file) = self.state.lyrics_file.clone() else {
+                    return Command::none();
+                };
+                let path = lrc_path(&file);
+                match std::fs::read_to_string(&path) {
+                    Ok(content) => {
+                        self.state.lyrics_lines = lyrics::parse_lrc(&content);
+                        self.state.lyrics_cursor = 0;
+                        self.state.status = format!(
+                            "Imported {} line(s) from {}",
+                            self.state.lyrics_lines.len(),
+                            path.file_name().unwrap_or_default().to_string_lossy()
+                        );
+                    }
+                    Err(e) => {
+                        self.state.status = format!("Error reading {}: {}", path.display(), e)
+                    }
+                }
+                Command::none()
+            }
+            Message::ExportLrc => {
+                let Some(file) = self.state.lyrics_file.clone() else {
+                    return Command::none();
+                };
+                let mut lines = self.state.lyrics_lines.clone();
+                lines.sort_by_key(|l| l.time);
+                let metadata = self
+                    .state
+                    .selected_file_index
+                    .and_then(|i| self.state.file_metadata.get(&i))
+                    .cloned()
+                    .unwrap_or_default();
+                let path = lrc_path(&file);
+                match std::fs::write(
+                    &path,
+                    lyrics::to_lrc(&lines, &metadata.artist, &metadata.title),
+                ) {
+                    Ok(()) => {
+                        self.state.status = format!(
+                            "✓ Exported {}",
+                            path.file_name().unwrap_or_default().to_string_lossy()
+                        )
+                    }
+                    Err(e) => {
+                        self.state.status = format!("Error writing {}: {}", path.display(), e)
+                    }
+                }
+                Command::none()
+            }
+            Message::SaveLyrics => {
+                let Some(file) = self.state.lyrics_file.clone() else {
+                    return Command::none();
+                };
+                let mut synced = self.state.lyrics_lines.clone();
+                synced.sort_by_key(|l| l.time);
+                let lyrics = Lyrics {
+                    unsynced: self.state.lyrics_text.text().trim_end().to_string(),
+                    synced,
+                };
+                let jobs = vec![(
+                    file,
+                    TagUpdate {
+                        lyrics: Some(lyrics),
+                        ..TagUpdate::default()
+                    },
+                )];
+
+                self.state.processing = true;
+                self.state.processing_count = jobs.len();
+                self.state.error_logs.clear();
+                self.state.status = "Writing lyrics...".to_string();
 
                 Command::perform(
-                    async move { process_files(files, artist, album, genre, year, album_art).await },
+                    async move { update_files(jobs).await },
                     Message::ProcessingComplete,
                 )
             }
@@ -224,7 +1159,7 @@ impl Application for MusicToolsApp {
                         if errors.is_empty() {
                             self.state.status = format!(
                                 "✓ Successfully updated {} file(s)",
-                                self.state.files.len()
+                                self.state.processing_count
                             );
                         } else {
                             self.state.status = format!("Completed with {} error(s)", errors.len());
@@ -237,14 +1172,18 @@ impl Application for MusicToolsApp {
                     }
                 }
                 self.state.file_metadata.clear();
+                let reload_all = load_all_metadata(self.state.files.clone());
                 if let Some(idx) = self.state.selected_file_index {
                     let file_path = self.state.files[idx].clone();
-                    return Command::perform(
-                        async move { (idx, read_file_metadata(file_path)) },
-                        |(idx, result)| Message::MetadataLoaded(idx, result),
-                    );
+                    return Command::batch([
+                        Command::perform(
+                            async move { (idx, read_file_metadata(file_path)) },
+                            |(idx, result)| Message::MetadataLoaded(idx, result),
+                        ),
+                        reload_all,
+                    ]);
                 }
-                Command::none()
+                reload_all
             }
             Message::RemoveFile(index) => {
                 if index < self.state.files.len() {
@@ -271,6 +1210,15 @@ impl Application for MusicToolsApp {
                     for (i, meta) in old_metadata {
                         self.state.file_metadata.insert(i, meta);
                     }
+                    self.state.checked_files = self
+                        .state
+                        .checked_files
+                        .iter()
+                        .filter(|&&i| i != index)
+                        .map(|&i| if i > index { i - 1 } else { i })
+                        .collect();
+                    self.state.last_checked_index = None;
+                    self.state.rebuild_album_groups();
                     self.state.status = format!("{} file(s) loaded", self.state.files.len());
                 }
                 Command::none()
@@ -279,6 +1227,11 @@ impl Application for MusicToolsApp {
                 self.state.files.clear();
                 self.state.file_metadata.clear();
                 self.state.selected_file_index = None;
+                self.state.checked_files.clear();
+                self.state.last_checked_index = None;
+                self.state.directory_covers.clear();
+                self.state.album_groups.clear();
+                self.state.selected_group = None;
                 self.state.status = "All files cleared".to_string();
                 Command::none()
             }
@@ -286,10 +1239,16 @@ impl Application for MusicToolsApp {
                 if index < self.state.files.len() {
                     self.state.selected_file_index = Some(index);
                     let file_path = self.state.files[index].clone();
-                    Command::perform(
+                    let load_metadata = Command::perform(
                         async move { (index, read_file_metadata(file_path)) },
                         |(idx, result)| Message::MetadataLoaded(idx, result),
-                    )
+                    );
+                    let load_panel = match self.state.editor_panel {
+                        EditorPanel::FileDetails => self.load_spectrum(),
+                        EditorPanel::Lyrics => self.load_lyrics(),
+                        _ => Command::none(),
+                    };
+                    Command::batch([load_metadata, load_panel])
                 } else {
                     Command::none()
                 }
@@ -305,6 +1264,77 @@ impl Application for MusicToolsApp {
                 }
                 Command::none()
             }
+            Message::AllMetadataLoaded(results) => {
+                for (path, result) in results {
+                    // The file list may have changed while metadata was loading
+                    if let Some(index) = self.state.files.iter().position(|f| *f == path) {
+                        if let Ok(metadata) = result {
+                            self.state.file_metadata.insert(index, metadata);
+                        }
+                    }
+                }
+                Command::none()
+            }
+            Message::ToggleFileChecked(index) => {
+                if index >= self.state.files.len() {
+                    return Command::none();
+                }
+                let check = !self.state.checked_files.contains(&index);
+                match self.state.last_checked_index {
+                    // Shift-click applies the new state to the whole range from the last click
+                    Some(anchor) if self.state.shift_held => {
+                        for i in anchor.min(index)..=anchor.max(index) {
+                            if check {
+                                self.state.checked_files.insert(i);
+                            } else {
+                                self.state.checked_files.remove(&i);
+                            }
+                        }
+                    }
+                    _ => {
+                        if check {
+                            self.state.checked_files.insert(index);
+                        } else {
+                            self.state.checked_files.remove(&index);
+                        }
+                    }
+                }
+                self.state.last_checked_index = Some(index);
+                self.state.status = format!(
+                    "{} of {} file(s) selected",
+                    self.state.checked_files.len(),
+                    self.state.files.len()
+                );
+                Command::none()
+            }
+            Message::CheckAllFiles => {
+                self.state.checked_files = (0..self.state.files.len()).collect();
+                self.state.last_checked_index = None;
+                self.state.status = format!("{} file(s) selected", self.state.files.len());
+                Command::none()
+            }
+            Message::UncheckAllFiles => {
+                self.state.checked_files.clear();
+                self.state.last_checked_index = None;
+                self.state.status = format!("{} file(s) loaded", self.state.files.len());
+                Command::none()
+            }
+            Message::CheckFilesByAlbum(album) => {
+                self.state.checked_files = self
+                    .state
+                    .file_metadata
+                    .iter()
+                    .filter(|(_, m)| m.album.trim() == album)
+                    .map(|(&i, _)| i)
+                    .collect();
+                self.state.last_checked_index = None;
+                self.state.status = format!(
+                    "{} file(s) selected from album '{}'",
+                    self.state.checked_files.len(),
+                    album
+                );
+                Command::none()
+            }
 
             Message::LoadArtists => {
                 self.state.downloader_state.loading_artists = true;
@@ -397,12 +1427,14 @@ impl Application for MusicToolsApp {
                     self.state.downloader_state.selected_artist = None;
                     self.state.downloader_state.search_results.clear();
                     self.state.downloader_state.selected_songs.clear();
+                    self.state.downloader_state.clear_queue();
                     self.state.downloader_state.status = "Select an artist".to_string();
                 } else if index < self.state.downloader_state.filtered_artists.len() {
                     let artist = self.state.downloader_state.filtered_artists[index].clone();
                     self.state.downloader_state.selected_artist = Some(artist.clone());
                     self.state.downloader_state.search_results.clear();
                     self.state.downloader_state.selected_songs.clear();
+                    self.state.downloader_state.clear_queue();
                     self.state.downloader_state.loading_songs = true;
                     self.state.downloader_state.status =
                         format!("Loading songs for {}...", artist.name);
@@ -435,6 +1467,7 @@ impl Application for MusicToolsApp {
                 match result {
                     Ok(songs) => {
                         self.state.downloader_state.search_results = songs;
+                        self.state.downloader_state.clear_queue();
                         if let Some(ref artist) = self.state.downloader_state.selected_artist {
                             let status = format!(
                                 "Found {} song(s) for {}",
@@ -504,6 +1537,13 @@ impl Application for MusicToolsApp {
                 let song_url = song.url.clone();
 
                 self.state.downloader_state.playing_song_index = Some(index);
+                self.state.downloader_state.next_stream = None;
+                self.state.downloader_state.queue_position = self
+                    .state
+                    .downloader_state
+                    .play_queue
+                    .iter()
+                    .position(|&i| i == index);
                 self.state.downloader_state.status = format!("Loading: {}...", song.title);
 
                 Command::perform(
@@ -522,16 +1562,14 @@ impl Application for MusicToolsApp {
                         // Ensure the playing index is set correctly
                         self.state.downloader_state.playing_song_index = Some(index);
                         self.state.downloader_state.streaming_url = Some(url.clone());
-                        if let Some(ref song) =
-                            self.state.downloader_state.search_results.get(index)
-                        {
+                        if let Some(song) = self.state.downloader_state.search_results.get(index) {
                             self.state.downloader_state.status =
-                                format!("Playing: {}...", song.title);
+                                format!("Buffering: {}...", song.title);
                         }
 
                         // Start audio playback (non-blocking - allows app to close)
                         // Spawn directly without waiting
-                        audio_player::play_streaming_url_async(url);
+                        audio_player::play_url(url);
                         Command::none()
                     }
                     Ok(None) => {
@@ -546,19 +1584,55 @@ impl Application for MusicToolsApp {
                     }
                 }
             }
+            Message::NextStreamingUrlLoaded(index, result) => {
+                let downloader = &mut self.state.downloader_state;
+                // Only if it is still the song to come; otherwise it starts when its turn comes
+                let wanted = downloader
+                    .queue_position
+                    .and_then(|_| downloader.next_queued());
+                if let Ok(Some(url)) = result {
+                    if wanted == Some(index) && downloader.next_stream.is_none() {
+                        downloader.next_stream = Some((index, url.clone()));
+                        audio_player::queue_next(Some(PlayerSource::Url(url)));
+                    }
+                }
+                Command::none()
+            }
             Message::StopSong => {
                 audio_player::stop_audio();
-                self.state.downloader_state.playing_song_index = None;
-                self.state.downloader_state.streaming_url = None;
-                if let Some(ref artist) = self.state.downloader_state.selected_artist {
-                    self.state.downloader_state.status = format!(
-                        "Found {} song(s) for {}",
-                        self.state.downloader_state.search_results.len(),
-                        artist.name
-                    );
-                }
+                self.clear_playing_song();
                 Command::none()
             }
+            Message::ToggleQueued(index) => {
+                if index < self.state.downloader_state.search_results.len() {
+                    self.state.downloader_state.toggle_queued(index);
+                }
+                self.queue_next_song()
+            }
+            Message::QueueSelectedSongs => {
+                self.state.downloader_state.queue_selected();
+                self.queue_next_song()
+            }
+            Message::PlayQueue => match self.state.downloader_state.play_queue.first() {
+                Some(&index) => self.update(Message::PlaySong(index)),
+                None => Command::none(),
+            },
+            Message::PlayNextQueued => match self.state.downloader_state.next_queued() {
+                Some(index) => self.update(Message::PlaySong(index)),
+                None => Command::none(),
+            },
+            Message::PlayPreviousQueued => match self.state.downloader_state.previous_queued() {
+                Some(index) => self.update(Message::PlaySong(index)),
+                None => Command::none(),
+            },
+            Message::ToggleShuffle(shuffle) => {
+                self.state.downloader_state.set_shuffle(shuffle);
+                self.queue_next_song()
+            }
+            Message::ClearQueue => {
+                self.state.downloader_state.clear_queue();
+                self.queue_next_song()
+            }
             Message::SelectDownloadDirectory => {
                 let mut dialog = rfd::FileDialog::new();
                 if let Some(ref saved_path) = self.state.downloader_state.download_path {
@@ -600,131 +1674,152 @@ impl Application for MusicToolsApp {
                     self.state.downloader_state.status = "No songs selected".to_string();
                     return Command::none();
                 }
-                if self.state.downloader_state.download_path.is_none() {
+                let Some(download_path) = self.state.downloader_state.download_path.clone() else {
                     self.state.downloader_state.status =
                         "Please select download directory".to_string();
                     return Command::none();
-                }
-
-                let songs = self.state.downloader_state.search_results.clone();
-                let selected_indices = self.state.downloader_state.selected_songs.clone();
-                let download_path = self.state.downloader_state.download_path.clone().unwrap();
-
-                self.state.downloader_state.downloading = true;
-                self.state.downloader_state.status = "Starting download...".to_string();
-
-                Command::perform(
-                    async move {
-                        use futures::stream::{FuturesUnordered, StreamExt};
-                        use std::sync::Arc;
-                        use tokio::sync::Semaphore;
-
-                        let scraper = SongHubScraper::new()?;
-                        let mut errors = Vec::new();
-                        let mut success_logs = Vec::new();
-
-                        // Limit concurrent downloads to 5
-                        let semaphore = Arc::new(Semaphore::new(5));
-                        let mut download_tasks = FuturesUnordered::new();
-
-                        for &song_idx in selected_indices.iter() {
-                            if song_idx >= songs.len() {
-                                continue;
-                            }
-
-                            let song = songs[song_idx].clone();
-                            let scraper_clone = scraper.clone();
-                            let semaphore_clone = semaphore.clone();
-                            let download_path_clone = download_path.clone();
-
-                            // Spawn download task with semaphore limit
-                            let task = async move {
-                                let _permit = semaphore_clone.acquire().await.unwrap();
-
-                                let download_url = match scraper_clone
-                                    .get_download_url(&song.url)
-                                    .await
-                                {
-                                    Ok(Some(url)) => url,
-                                    Ok(None) => {
-                                        return Err(format!("No download URL for: {}", song.title));
-                                    }
-                                    Err(e) => {
-                                        return Err(format!(
-                                            "Failed to get URL for {}: {}",
-                                            song.title, e
-                                        ));
-                                    }
-                                };
-
-                                let safe_title = song
-                                    .title
-                                    .chars()
-                                    .map(|c| match c {
-                                        '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
-                                        _ => c,
-                                    })
-                                    .collect::<String>()
-                                    .trim()
-                                    .to_string();
-                                let filename = format!("{}.mp3", safe_title);
-                                let output_path = download_path_clone.join(&filename);
-
-                                match scraper_clone
-                                    .download_song(&download_url, &output_path)
-                                    .await
-                                {
-                                    Ok(_) => Ok(format!("✓ Downloaded: {}", song.title)),
-                                    Err(e) => {
-                                        Err(format!("Failed to download {}: {}", song.title, e))
-                                    }
-                                }
-                            };
-
-                            download_tasks.push(task);
-                        }
-
-                        // Process all downloads concurrently (max 5 at a time)
-                        while let Some(result) = download_tasks.next().await {
-                            match result {
-                                Ok(log) => success_logs.push(log),
-                                Err(error) => errors.push(error),
-                            }
-                        }
+                };
 
-                        Ok((success_logs, errors))
-                    },
-                    |result: Result<(Vec<String>, Vec<String>), anyhow::Error>| {
-                        Message::DownloadComplete(result.map_err(|e| e.to_string()))
-                    },
-                )
+                let downloader = &mut self.state.downloader_state;
+                let mut added = 0;
+                for &i in &downloader.selected_songs {
+                    let Some(song) = downloader.search_results.get(i) else {
+                        continue;
+                    };
+                    let output_path =
+                        download_path.join(format!("{}.mp3", sanitize_filename(&song.title)));
+                    if downloader.downloads.push(
+                        song.title.clone(),
+                        song.artist.clone(),
+                        song.url.clone(),
+                        output_path,
+                    ) {
+                        added += 1;
+                    }
+                }
+                if added == 0 {
+                    downloader.status = "Selected songs are already queued".to_string();
+                    return Command::none();
+                }
+                self.save_download_queue();
+                self.start_queued_downloads()
             }
-            Message::DownloadComplete(result) => {
-                self.state.downloader_state.downloading = false;
-                match result {
-                    Ok((success_logs, errors)) => {
-                        let total = self.state.downloader_state.selected_songs.len();
-                        let success_count = success_logs.len();
-                        let error_count = errors.len();
-
-                        if errors.is_empty() {
-                            self.state.downloader_state.status =
-                                format!("Successfully downloaded {} song(s)", success_count);
-                        } else {
-                            self.state.downloader_state.status = format!(
-                                "Downloaded {} of {} song(s) ({} error(s))",
-                                success_count, total, error_count
-                            );
+            Message::DownloadProgressed(progress) => {
+                let downloader = &mut self.state.downloader_state;
+                if downloader.downloads.apply_progress(progress) {
+                    downloader.progress = downloader.downloads.progress();
+                }
+                Command::none()
+            }
+            Message::DownloadFinished(id, run, result) => {
+                let downloader = &mut self.state.downloader_state;
+                let Some(item) = downloader
+                    .downloads
+                    .get_mut(id)
+                    .filter(|item| item.run == run && item.state.is_running())
+                else {
+                    // Paused or cancelled while it ran
+                    return Command::none();
+                };
+                downloader.download_tasks.remove(&id);
+                let retry = match result {
+                    Ok(tag_error) => {
+                        if let Some(e) = tag_error {
+                            self.state.error_logs.push(format!(
+                                "Downloaded {} but could not tag it: {}",
+                                item.title, e
+                            ));
                         }
-
-                        // Clear individual logs - only show summary
-                        self.state.error_logs.clear();
+                        item.state = DownloadState::Done;
+                        None
                     }
                     Err(e) => {
-                        self.state.downloader_state.status = format!("Download failed: {}", e);
-                        self.state.error_logs = vec![format!("Download failed: {}", e)];
+                        item.attempts += 1;
+                        if item.attempts < MAX_ATTEMPTS {
+                            item.state = DownloadState::Retrying(e);
+                            Some(retry_delay(item.attempts))
+                        } else {
+                            self.state
+                                .error_logs
+                                .push(format!("Failed to download {}: {}", item.title, e));
+                            item.state = DownloadState::Failed(e);
+                            None
+                        }
+                    }
+                };
+                self.save_download_queue();
+                let next = self.start_queued_downloads();
+                match retry {
+                    Some(delay) => Command::batch([
+                        next,
+                        Command::perform(tokio::time::sleep(delay), move |_| {
+                            Message::RetryDownload(id)
+                        }),
+                    ]),
+                    None => next,
+                }
+            }
+            Message::RetryDownload(id) => {
+                match self.state.downloader_state.downloads.get_mut(id) {
+                    Some(item) if matches!(item.state, DownloadState::Retrying(_)) => {
+                        item.state = DownloadState::Queued;
                     }
+                    _ => return Command::none(),
+                }
+                self.start_queued_downloads()
+            }
+            Message::PauseDownload(id) => {
+                let downloader = &mut self.state.downloader_state;
+                let Some(item) = downloader.downloads.get_mut(id) else {
+                    return Command::none();
+                };
+                if item.state.is_finished() || item.state == DownloadState::Paused {
+                    return Command::none();
+                }
+                // The part file stays, so resuming continues where it stopped
+                item.state = DownloadState::Paused;
+                item.speed = 0.0;
+                if let Some(task) = downloader.download_tasks.remove(&id) {
+                    task.abort();
+                }
+                self.save_download_queue();
+                self.start_queued_downloads()
+            }
+            Message::ResumeDownload(id) => {
+                let Some(item) = self.state.downloader_state.downloads.get_mut(id) else {
+                    return Command::none();
+                };
+                match item.state {
+                    DownloadState::Paused => {}
+                    DownloadState::Failed(_) => item.attempts = 0,
+                    _ => return Command::none(),
+                }
+                item.state = DownloadState::Queued;
+                self.save_download_queue();
+                self.start_queued_downloads()
+            }
+            Message::CancelDownload(id) => {
+                let downloader = &mut self.state.downloader_state;
+                if let Some(task) = downloader.download_tasks.remove(&id) {
+                    task.abort();
+                }
+                let Some(item) = downloader.downloads.remove(id) else {
+                    return Command::none();
+                };
+                if item.state != DownloadState::Done {
+                    let _ = std::fs::remove_file(part_path(&item.output_path));
                 }
+                self.save_download_queue();
+                self.start_queued_downloads()
+            }
+            Message::ClearFinishedDownloads => {
+                self.state
+                    .downloader_state
+                    .downloads
+                    .items
+                    .retain(|item| item.state != DownloadState::Done);
+                self.save_download_queue();
+                self.update_download_status();
                 Command::none()
             }
 
@@ -741,6 +1836,102 @@ impl Application for MusicToolsApp {
                 Command::none()
             }
 
+            Message::SelectWatchFolder => {
+                let saved_path = self
+                    .state
+                    .watch_folder
+                    .clone()
+                    .or_else(|| self.state.downloader_state.download_path.clone());
+                Command::perform(
+                    async move {
+                        let mut dialog = rfd::AsyncFileDialog::new();
+                        if let Some(path) = saved_path {
+                            dialog = dialog.set_directory(&path);
+                        }
+                        dialog
+                            .pick_folder()
+                            .await
+                            .map(|handle| handle.path().to_path_buf())
+                    },
+                    Message::WatchFolderSelected,
+                )
+            }
+            Message::WatchFolderSelected(path) => {
+                if let Some(path) = path {
+                    self.state.watch_status = if self.state.watch_active {
+                        format!("Watching {}", path.display())
+                    } else {
+                        format!("Ready to watch {}", path.display())
+                    };
+                    let mut settings = AppSettings::load();
+                    settings.set_watch_folder(Some(&path));
+                    let _ = settings.save();
+                    self.state.watch_folder = Some(path);
+                }
+                Command::none()
+            }
+            Message::ToggleWatching => {
+                if let Some(ref folder) = self.state.watch_folder {
+                    self.state.watch_active = !self.state.watch_active;
+                    self.state.watch_status = if self.state.watch_active {
+                        format!("Watching {}", folder.display())
+                    } else {
+                        "Watching stopped".to_string()
+                    };
+                } else {
+                    self.state.watch_status = "Please select a folder to watch".to_string();
+                }
+                Command::none()
+            }
+            Message::WatchParseFilenameToggled(enabled) => {
+                self.state.watch_rules.parse_filename = enabled;
+                self.save_watch_rules();
+                Command::none()
+            }
+            Message::WatchDefaultGenreChanged(genre) => {
+                self.state.watch_rules.default_genre = genre;
+                self.save_watch_rules();
+                Command::none()
+            }
+            Message::WatchFolderCoverToggled(enabled) => {
+                self.state.watch_rules.use_folder_cover = enabled;
+                self.save_watch_rules();
+                Command::none()
+            }
+            Message::WatchedFileDetected(path) => {
+                let rules = self.state.watch_rules.clone();
+                Command::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || {
+                            folder_watcher::apply_rules(path, &rules)
+                        })
+                        .await
+                        .unwrap_or_else(|e| Err(format!("Task error: {}", e)))
+                    },
+                    Message::WatchedFileTagged,
+                )
+            }
+            Message::WatchedFileTagged(result) => {
+                let entry = match result {
+                    Ok(log) => format!("✓ {}", log),
+                    Err(e) => format!("✗ {}", e),
+                };
+                self.state.watch_status = entry.clone();
+                self.state.watch_log.insert(0, entry);
+                Command::none()
+            }
+            Message::WatcherError(e) => {
+                self.state.watch_status = format!("Watcher error: {}", e);
+                self.state
+                    .watch_log
+                    .insert(0, format!("✗ Watcher error: {}", e));
+                Command::none()
+            }
+            Message::ClearWatchLog => {
+                self.state.watch_log.clear();
+                Command::none()
+            }
+
             Message::ToggleTheme => {
                 self.theme_mode = match self.theme_mode {
                     ThemeMode::Dark => ThemeMode::Light,
@@ -769,6 +1960,10 @@ impl Application for MusicToolsApp {
                 }
                 Command::none()
             }
+            Message::ModifiersChanged(modifiers) => {
+                self.state.shift_held = modifiers.shift();
+                Command::none()
+            }
             Message::NoOp => Command::none(),
         }
     }
@@ -786,10 +1981,515 @@ impl Application for MusicToolsApp {
             || self.state.processing
             || self.state.downloader_state.loading_artists
             || self.state.downloader_state.loading_songs;
-        if active {
+        let ticks = if active {
             time::every(Duration::from_millis(16)).map(Message::Tick)
         } else {
             Subscription::none()
+        };
+        let modifiers = event::listen_with(|event, _status| match event {
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                Some(Message::ModifiersChanged(modifiers))
+            }
+            _ => None,
+        });
+        let watcher = match (&self.state.watch_folder, self.state.watch_active) {
+            (Some(folder), true) => watch_folder_subscription(folder.clone()),
+            _ => Subscription::none(),
+        };
+        Subscription::batch([ticks, modifiers, watcher, player_subscription()])
+    }
+}
+
+impl MusicToolsApp {
+    fn save_watch_rules(&self) {
+        let mut settings = AppSettings::load();
+        settings.watch_rules = self.state.watch_rules.clone();
+        let _ = settings.save();
+    }
+
+    /// The downloader song `source` streams, if it is the one playing there
+    fn streamed_song(&self, source: &PlayerSource) -> Option<&Song> {
+        let downloader = &self.state.downloader_state;
+        match source {
+            PlayerSource::Url(url) if downloader.streaming_url.as_ref() == Some(url) => downloader
+                .playing_song_index
+                .and_then(|i| downloader.search_results.get(i)),
+            _ => None,
+        }
+    }
+
+    /// Reset the downloader's play button and status once its song stops
+    fn clear_playing_song(&mut self) {
+        let downloader = &mut self.state.downloader_state;
+        downloader.playing_song_index = None;
+        downloader.streaming_url = None;
+        downloader.queue_position = None;
+        downloader.next_stream = None;
+        if let Some(ref artist) = downloader.selected_artist {
+            downloader.status = format!(
+                "Found {} song(s) for {}",
+                downloader.search_results.len(),
+                artist.name
+            );
+        }
+    }
+
+    /// Play a file of the list from `start`, selecting it so its tags show alongside
+    fn preview_file(&mut self, index: usize, start: Duration) -> Command<Message> {
+        let Some(file) = self.state.files.get(index).cloned() else {
+            return Command::none();
+        };
+        audio_player::play_file(file, start);
+        self.state.preview_follow = true;
+        if self.state.selected_file_index == Some(index) {
+            Command::none()
+        } else {
+            self.update(Message::FileSelected(index))
+        }
+    }
+
+    /// Hand the file after the playing preview to the player, or take it back when play
+    /// through is off
+    fn queue_next_file(&self) {
+        let Some(playing) = self
+            .state
+            .playing_file()
+            .filter(|_| self.state.preview_follow)
+        else {
+            return;
+        };
+        let next = self
+            .state
+            .files
+            .iter()
+            .position(|f| f == playing)
+            .and_then(|i| self.state.files.get(i + 1))
+            .filter(|_| self.state.play_through)
+            .map(|f| PlayerSource::File(f.clone()));
+        audio_player::queue_next(next);
+    }
+
+    /// Resolve the queued song after the playing one so the player can run straight into
+    /// it, replacing one handed over before the queue changed
+    fn queue_next_song(&mut self) -> Command<Message> {
+        let downloader = &mut self.state.downloader_state;
+        let wanted = downloader
+            .playing_song_index
+            .and(downloader.queue_position)
+            .and_then(|_| downloader.next_queued());
+        if wanted == downloader.next_stream.as_ref().map(|(i, _)| *i) {
+            return Command::none();
+        }
+        if downloader.next_stream.take().is_some() {
+            audio_player::queue_next(None);
+        }
+        let Some((index, song)) = wanted.and_then(|i| Some((i, downloader.search_results.get(i)?)))
+        else {
+            return Command::none();
+        };
+        let song_url = song.url.clone();
+        Command::perform(
+            async move {
+                let scraper = SongHubScraper::new()?;
+                scraper.get_streaming_url(&song_url).await
+            },
+            move |result| Message::NextStreamingUrlLoaded(index, result.map_err(|e| e.to_string())),
+        )
+    }
+
+    /// Stop the file preview and lyrics playback when leaving the Metadata Editor
+    fn stop_editor_playback(&mut self) {
+        if self.state.playing_file().is_some() {
+            audio_player::stop_audio();
+        }
+    }
+
+    /// Decode the next queued file for loudness, one at a time to keep the UI responsive
+    fn analyze_next_loudness(&mut self) -> Command<Message> {
+        let Some(file) = self.state.loudness_queue.pop() else {
+            self.state.analyzing_loudness = false;
+            let failed = self
+                .state
+                .loudness_results
+                .iter()
+                .filter(|(_, r)| r.is_err())
+                .count();
+            self.state.status = if failed == 0 {
+                format!("Analyzed {} file(s)", self.state.loudness_results.len())
+            } else {
+                format!(
+                    "Analyzed {} file(s), {} could not be decoded",
+                    self.state.loudness_results.len(),
+                    failed
+                )
+            };
+            return Command::none();
+        };
+        self.state.status = format!(
+            "Analyzing {} / {}...",
+            self.state.loudness_results.len() + 1,
+            self.state.loudness_total
+        );
+        Command::perform(
+            async move {
+                let path = file.clone();
+                let result = tokio::task::spawn_blocking(move || analyze_file(&path))
+                    .await
+                    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+                (file, result)
+            },
+            |(file, result)| Message::LoudnessAnalyzed(file, result),
+        )
+    }
+
+    /// Decode the selected file in the background and render its waveform and spectrogram
+    fn load_spectrum(&mut self) -> Command<Message> {
+        let Some(file) = self
+            .state
+            .selected_file_index
+            .and_then(|i| self.state.files.get(i))
+            .cloned()
+        else {
+            return Command::none();
+        };
+        if self.state.spectrum_file.as_ref() == Some(&file) {
+            return Command::none();
+        }
+        self.state.spectrum_file = Some(file.clone());
+        self.state.spectrum = None;
+        Command::perform(
+            async move {
+                let path = file.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let analysis = spectrum::analyze_file(&path)?;
+                    Ok(SpectrumView {
+                        waveform: svg::Handle::from_memory(
+                            waveform_svg(&analysis.waveform).into_bytes(),
+                        ),
+                        spectrogram: svg::Handle::from_memory(
+                            spectrogram_svg(&analysis.spectrogram).into_bytes(),
+                        ),
+                        nyquist_hz: analysis.nyquist_hz(),
+                    })
+                })
+                .await
+                .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+                (file, result)
+            },
+            |(file, result)| Message::SpectrumReady(file, result),
+        )
+    }
+
+    /// Read the selected file's embedded lyrics into the lyrics panel
+    fn load_lyrics(&mut self) -> Command<Message> {
+        let Some(file) = self
+            .state
+            .selected_file_index
+            .and_then(|i| self.state.files.get(i))
+            .cloned()
+        else {
+            self.state.lyrics_file = None;
+            return Command::none();
+        };
+        if self.state.lyrics_file.as_ref() == Some(&file) {
+            return Command::none();
+        }
+        if self.state.lyrics_playing() {
+            audio_player::stop_audio();
+        }
+        self.state.lyrics_file = Some(file.clone());
+        self.state.lyrics_text = text_editor::Content::new();
+        self.state.lyrics_lines.clear();
+        self.state.lyrics_cursor = 0;
+        Command::perform(
+            async move {
+                let path = file.clone();
+                let result = tokio::task::spawn_blocking(move || read_lyrics(path))
+                    .await
+                    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+                (file, result)
+            },
+            |(file, result)| Message::LyricsLoaded(file, result),
+        )
+    }
+
+    /// Start queued downloads while fewer than `MAX_DOWNLOADS` are running
+    fn start_queued_downloads(&mut self) -> Command<Message> {
+        let downloader = &mut self.state.downloader_state;
+        let free = MAX_DOWNLOADS.saturating_sub(downloader.downloads.running());
+        let queued: Vec<u64> = downloader
+            .downloads
+            .items
+            .iter()
+            .filter(|item| item.state == DownloadState::Queued)
+            .take(free)
+            .map(|item| item.id)
+            .collect();
+        let mut commands = Vec::new();
+        for id in queued {
+            let Some(item) = downloader.downloads.get_mut(id) else {
+                continue;
+            };
+            let run = item.start();
+            let (task, registration) = AbortHandle::new_pair();
+            downloader.download_tasks.insert(id, task);
+            commands.push(download_command(item, run, registration));
+        }
+        if !commands.is_empty() {
+            self.save_download_queue();
+        }
+        self.update_download_status();
+        Command::batch(commands)
+    }
+
+    fn save_download_queue(&self) {
+        let _ = self.state.downloader_state.downloads.save();
+    }
+
+    /// Summarise the download queue in the downloader status line
+    fn update_download_status(&mut self) {
+        let downloader = &mut self.state.downloader_state;
+        let items = &downloader.downloads.items;
+        let count = |matches: fn(&DownloadState) -> bool| {
+            items.iter().filter(|item| matches(&item.state)).count()
+        };
+        let running = count(DownloadState::is_running);
+        let waiting =
+            count(|state| matches!(state, DownloadState::Queued | DownloadState::Retrying(_)));
+        let paused = count(|state| *state == DownloadState::Paused);
+        let done = count(|state| *state == DownloadState::Done);
+        let failed = count(|state| matches!(state, DownloadState::Failed(_)));
+
+        downloader.downloading = running > 0;
+        downloader.progress = downloader.downloads.progress();
+        downloader.status = if running + waiting > 0 {
+            format!("Downloading {} song(s), {} waiting...", running, waiting)
+        } else if failed > 0 {
+            format!("Downloaded {} song(s) ({} error(s))", done, failed)
+        } else if paused > 0 {
+            format!("{} download(s) paused", paused)
+        } else if done > 0 {
+            format!("Successfully downloaded {} song(s)", done)
+        } else {
+            "Download queue is empty".to_string()
+        };
+    }
+
+    /// Check the next queued file for transcoding, one at a time like loudness analysis
+    fn analyze_next_quality(&mut self) -> Command<Message> {
+        let Some(file) = self.state.quality_queue.pop() else {
+            self.state.analyzing_quality = false;
+            let suspect = self
+                .state
+                .quality_results
+                .values()
+                .filter(|r| matches!(r, Ok(report) if report.is_suspect()))
+                .count();
+            self.state.status = format!(
+                "Checked {} file(s), {} look transcoded or upsampled",
+                self.state.quality_results.len(),
+                suspect
+            );
+            return Command::none();
+        };
+        self.state.status = format!(
+            "Checking quality {} / {}...",
+            self.state.quality_results.len() + 1,
+            self.state.quality_total
+        );
+        Command::perform(
+            async move {
+                let path = file.clone();
+                let result = tokio::task::spawn_blocking(move || analyze_quality(&path))
+                    .await
+                    .unwrap_or_else(|e| Err(format!("Task error: {}", e)));
+                (file, result)
+            },
+            |(file, result)| Message::QualityAnalyzed(file, result),
+        )
+    }
+
+    /// Verify the next queued file, one at a time like loudness analysis
+    fn verify_next_file(&mut self) -> Command<Message> {
+        let Some(file) = self.state.verify_queue.pop() else {
+            self.state.verifying = false;
+            let problems = self
+                .state
+                .verify_results
+                .iter()
+                .filter(|(_, check)| !check.is_ok())
+                .count();
+            self.state.status = if problems == 0 {
+                format!(
+                    "✓ {} file(s) verified, no problems found",
+                    self.state.verify_results.len()
+                )
+            } else {
+                format!(
+                    "Verified {} file(s), {} with problems",
+                    self.state.verify_results.len(),
+                    problems
+                )
+            };
+            return Command::none();
+        };
+        self.state.status = format!(
+            "Verifying {} / {}...",
+            self.state.verify_results.len() + 1,
+            self.state.verify_total
+        );
+        Command::perform(
+            async move {
+                let path = file.clone();
+                let check = tokio::task::spawn_blocking(move || check_file(&path))
+                    .await
+                    .unwrap_or_else(|e| FileCheck {
+                        issues: vec![IntegrityIssue::DecodeFailed(format!("Task error: {}", e))],
+                        decoded: None,
+                    });
+                (file, check)
+            },
+            |(file, check)| Message::FileVerified(file, check),
+        )
+    }
+
+    fn save_custom_genres(&self) {
+        let mut settings = AppSettings::load();
+        settings.custom_genres = self.state.custom_genres.clone();
+        let _ = settings.save();
+    }
+
+    fn save_presets(&self) {
+        let mut settings = AppSettings::load();
+        settings.presets = self.state.presets.clone();
+        let _ = settings.save();
+    }
+}
+
+/// Forward the audio player's events to the app
+fn player_subscription() -> Subscription<Message> {
+    iced::subscription::channel("audio-player", 100, |mut output| async move {
+        use iced::futures::SinkExt;
+
+        let mut events = audio_player::subscribe();
+        while let Some(event) = events.recv().await {
+            let message = match event {
+                PlayerEvent::Status(status) => Message::PlayerStatusChanged(status),
+                PlayerEvent::Started(source) => Message::PlaybackStarted(source),
+                PlayerEvent::Finished(source) => Message::PlaybackFinished(source),
+                PlayerEvent::Failed(source, reason) => Message::PlaybackFailed(source, reason),
+            };
+            let _ = output.send(message).await;
+        }
+
+        std::future::pending().await
+    })
+}
+
+/// Forward new arrivals in `folder` to the app; restarted whenever the folder changes
+fn watch_folder_subscription(folder: PathBuf) -> Subscription<Message> {
+    iced::subscription::channel(folder.clone(), 100, move |mut output| async move {
+        use iced::futures::SinkExt;
+
+        match folder_watcher::watch(&folder) {
+            Ok((_watcher, mut events)) => {
+                while let Some(event) = events.recv().await {
+                    let message = match event {
+                        WatchEvent::NewFile(path) => Message::WatchedFileDetected(path),
+                        WatchEvent::Error(e) => Message::WatcherError(e),
+                    };
+                    let _ = output.send(message).await;
+                }
+            }
+            Err(e) => {
+                let _ = output.send(Message::WatcherError(e.to_string())).await;
+            }
         }
+
+        std::future::pending().await
+    })
+}
+
+/// Read the metadata of every loaded file in the background
+fn load_all_metadata(files: Vec<PathBuf>) -> Command<Message> {
+    if files.is_empty() {
+        return Command::none();
     }
+    Command::perform(
+        async move {
+            tokio::task::spawn_blocking(move || {
+                files
+                    .into_iter()
+                    .map(|path| {
+                        let result = read_file_metadata(path.clone());
+                        (path, result)
+                    })
+                    .collect::<Vec<(PathBuf, Result<FileMetadata, String>)>>()
+            })
+            .await
+            .unwrap_or_default()
+        },
+        Message::AllMetadataLoaded,
+    )
+}
+
+/// Resolve and download one queued song, reporting progress until it ends or is stopped
+fn download_command(
+    item: &DownloadItem,
+    run: u64,
+    registration: AbortRegistration,
+) -> Command<Message> {
+    let id = item.id;
+    let (title, artist) = (item.title.clone(), item.artist.clone());
+    let page_url = item.page_url.clone();
+    let output_path = item.output_path.clone();
+    let (progress_tx, progress_rx) = futures::channel::mpsc::unbounded();
+    let report = move |state, downloaded, total| {
+        let _ = progress_tx.unbounded_send(DownloadProgress {
+            id,
+            run,
+            state,
+            downloaded,
+            total,
+        });
+    };
+
+    let task = async move {
+        let scraper = SongHubScraper::new().map_err(|e| e.to_string())?;
+        let page = scraper
+            .get_song_page(&page_url)
+            .await
+            .map_err(|e| format!("Failed to get URL: {}", e))?;
+        let Some(download_url) = page.download_url.clone() else {
+            return Err("No download URL".to_string());
+        };
+
+        // Throttle byte counts; the list only redraws a few times a second
+        let mut last_report: Option<Instant> = None;
+        let mut last_bytes = (0, None);
+        let result = scraper
+            .download_song_with_progress(&download_url, &output_path, |downloaded, total| {
+                last_bytes = (downloaded, total);
+                if last_report.is_none_or(|at| at.elapsed() >= DOWNLOAD_PROGRESS_INTERVAL) {
+                    last_report = Some(Instant::now());
+                    report(DownloadState::Downloading, downloaded, total);
+                }
+            })
+            .await;
+        let (downloaded, total) = last_bytes;
+        report(DownloadState::Downloading, downloaded, total);
+        result.map_err(|e| format!("{:#}", e))?;
+
+        Ok(tag_download(&scraper, &output_path, &title, &artist, &page)
+            .await
+            .err())
+    };
+
+    Command::batch([
+        Command::perform(Abortable::new(task, registration), move |result| {
+            let result = result.unwrap_or_else(|_| Err("Stopped".to_string()));
+            Message::DownloadFinished(id, run, result)
+        }),
+        Command::run(progress_rx, Message::DownloadProgressed),
+    ])
 }
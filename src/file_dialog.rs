@@ -1,8 +1,14 @@
 use crate::config::SUPPORTED_FORMATS;
 use rfd::{AsyncFileDialog, FileDialog};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "bmp", "gif", "webp"];
+
+/// Sidecar cover image names, most preferred first
+const COVER_IMAGE_STEMS: &[&str] = &["cover", "folder", "front"];
+
 pub async fn select_image_async() -> Option<PathBuf> {
     let mut dialog = AsyncFileDialog::new()
         .add_filter("Image Files", &["jpg", "jpeg", "png", "bmp", "gif", "webp"]);
@@ -53,3 +59,50 @@ pub fn scan_folder_for_audio(folder_path: &PathBuf) -> Vec<PathBuf> {
     }
     audio_files
 }
+
+/// Find a sidecar cover image (`cover.*`, `folder.*`, `front.*`, `AlbumArt*.jpg`) in `dir`
+pub fn find_cover_image(dir: &Path) -> Option<PathBuf> {
+    let mut images: Vec<(String, String, PathBuf)> = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter_map(|p| {
+            let stem = p.file_stem()?.to_string_lossy().to_lowercase();
+            let ext = p.extension()?.to_string_lossy().to_lowercase();
+            IMAGE_EXTENSIONS
+                .contains(&ext.as_str())
+                .then_some((stem, ext, p))
+        })
+        .collect();
+    // Directory order is arbitrary; sort so the pick is stable between scans
+    images.sort_by(|a, b| a.2.cmp(&b.2));
+
+    for wanted in COVER_IMAGE_STEMS {
+        if let Some((_, _, path)) = images.iter().find(|(stem, _, _)| stem == wanted) {
+            return Some(path.clone());
+        }
+    }
+
+    // Windows Media Player leaves AlbumArt_{GUID}_Large.jpg / AlbumArtSmall.jpg behind
+    let mut album_art: Vec<&(String, String, PathBuf)> = images
+        .iter()
+        .filter(|(stem, ext, _)| stem.starts_with("albumart") && ext == "jpg")
+        .collect();
+    album_art.sort_by_key(|(stem, _, _)| !stem.contains("large"));
+    album_art.first().map(|(_, _, path)| path.clone())
+}
+
+/// Map each directory containing one of `files` to its sidecar cover image, if any
+pub fn find_directory_covers(files: &[PathBuf]) -> HashMap<PathBuf, PathBuf> {
+    let mut covers = HashMap::new();
+    let mut seen = std::collections::HashSet::new();
+    for dir in files.iter().filter_map(|f| f.parent()) {
+        if seen.insert(dir.to_path_buf()) {
+            if let Some(cover) = find_cover_image(dir) {
+                covers.insert(dir.to_path_buf(), cover);
+            }
+        }
+    }
+    covers
+}
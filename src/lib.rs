@@ -4,6 +4,7 @@
 //! - Metadata Editor: Edit artist, album, genre, year, and cover art
 //! - Music Downloader: Download music from online sources (coming soon)
 //! - Audio Converter: Convert between audio formats (coming soon)
+//! - Folder Watcher: Auto-tag new audio files as they arrive in a folder
 
 pub mod app;
 pub mod config;
@@ -1,6 +1,16 @@
-use crate::model::{FileMetadata, Screen};
+use crate::model::{EditorPanel, FileMetadata, Screen, SpectrumView};
+use crate::utils::audio_player::{PlayerSource, PlayerStatus};
+use crate::utils::download_queue::DownloadProgress;
+use crate::utils::integrity::FileCheck;
+use crate::utils::loudness::TrackLoudness;
+use crate::utils::lyrics::Lyrics;
+use crate::utils::quality::QualityReport;
+use crate::utils::text_rules::{CaseRule, TextRule};
+use crate::utils::track_numbering::TrackOrder;
+use iced::widget::text_editor;
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -20,12 +30,80 @@ pub enum Message {
     YearChanged(String),
     SelectImage,
     ImageSelected(Option<PathBuf>),
+    DirectoryCoversFound(HashMap<PathBuf, PathBuf>),
+    UseDirectoryCoversToggled(bool),
     ProcessFiles,
     ProcessingComplete(Result<Vec<String>, String>),
     RemoveFile(usize),
     ClearAllFiles,
     FileSelected(usize),
     MetadataLoaded(usize, Result<FileMetadata, String>),
+    AllMetadataLoaded(Vec<(PathBuf, Result<FileMetadata, String>)>),
+    ToggleFileChecked(usize),
+    CheckAllFiles,
+    UncheckAllFiles,
+    CheckFilesByAlbum(String),
+    GroupByAlbumToggled(bool),
+    SelectAlbumGroup(usize),
+    ToggleGroupChecked(usize),
+    TrackOrderChanged(TrackOrder),
+    NumberTracks,
+    ShowEditorPanel(EditorPanel),
+    TextRuleToggled(TextRule, bool),
+    TextCaseChanged(CaseRule),
+    TextFindChanged(String),
+    TextReplaceChanged(String),
+    ApplyTextRules,
+    PresetNameChanged(String),
+    SavePreset,
+    LoadPreset(usize),
+    RenamePreset(usize),
+    DeletePreset(usize),
+    CustomGenreInputChanged(String),
+    AddCustomGenre,
+    RemoveCustomGenre(usize),
+    MergeSourceSelected(String),
+    MergeTargetChanged(String),
+    /// Rewrite every loaded file whose genre is one of the variants to the canonical value
+    MergeGenres(Vec<String>, String),
+    AnalyzeLoudness,
+    StopLoudnessAnalysis,
+    LoudnessAnalyzed(PathBuf, Result<TrackLoudness, String>),
+    WriteReplayGain,
+    VerifyFiles,
+    StopVerification,
+    FileVerified(PathBuf, FileCheck),
+    /// Check the files with problems in the file list so they can be dealt with together
+    CheckProblemFiles,
+    AnalyzeQuality,
+    StopQualityAnalysis,
+    QualityAnalyzed(PathBuf, Result<QualityReport, String>),
+    /// Check the files that look transcoded in the file list
+    CheckSuspectFiles,
+    SpectrumReady(PathBuf, Result<SpectrumView, String>),
+    LyricsLoaded(PathBuf, Result<Lyrics, String>),
+    LyricsTextEdited(text_editor::Action),
+    LyricLineTextChanged(usize, String),
+    SelectLyricLine(usize),
+    /// Set the selected line's timestamp to the playback position and move to the next line
+    StampLyricLine,
+    NudgeLyricLine(usize, i64),
+    AddLyricLine,
+    RemoveLyricLine(usize),
+    /// Start synced lines from the unsynchronized lyrics, one per non-blank line
+    LyricLinesFromText,
+    /// Play a file of the Metadata Editor list from the start
+    PreviewFile(usize),
+    StopPreview,
+    PreviewNext,
+    PreviewPrevious,
+    /// Continue the preview with the next file of the list when one ends
+    TogglePlayThrough(bool),
+    PlayLyricsFrom(Duration),
+    StopLyricsPlayback,
+    ImportLrc,
+    ExportLrc,
+    SaveLyrics,
 
     // Music Downloader
     LoadArtists,
@@ -42,22 +120,69 @@ pub enum Message {
     SelectDownloadDirectory,
     DownloadDirectorySelected(Option<PathBuf>),
     DownloadSelectedSongs,
-    DownloadComplete(Result<(Vec<String>, Vec<String>), String>),
+    DownloadProgressed(DownloadProgress),
+    /// A run of a queued download ended: item id, run and outcome, with the reason the
+    /// finished file could not be tagged
+    DownloadFinished(u64, u64, Result<Option<String>, String>),
+    /// The backoff of a failed download has passed
+    RetryDownload(u64),
+    PauseDownload(u64),
+    ResumeDownload(u64),
+    CancelDownload(u64),
+    ClearFinishedDownloads,
     PlaySong(usize),
     StopSong,
+    /// Add a song to the preview queue, or remove it
+    ToggleQueued(usize),
+    QueueSelectedSongs,
+    PlayQueue,
+    PlayNextQueued,
+    PlayPreviousQueued,
+    ToggleShuffle(bool),
+    ClearQueue,
+    PlayerPause,
+    PlayerResume,
+    /// Seek slider dragged to a position in seconds; applied on release
+    PlayerSeek(f32),
+    PlayerSeekReleased,
+    PlayerVolume(f32),
+    /// Crossfade slider dragged to a length in seconds; saved on release
+    CrossfadeChanged(f32),
+    CrossfadeReleased,
+    PlayerStatusChanged(PlayerStatus),
+    PlaybackStarted(PlayerSource),
+    PlaybackFinished(PlayerSource),
+    PlaybackFailed(PlayerSource, String),
     StreamingUrlLoaded(usize, Result<Option<String>, String>),
+    /// Streaming URL of the queued song after the playing one, handed to the player early
+    NextStreamingUrlLoaded(usize, Result<Option<String>, String>),
 
     // Audio Converter (placeholder)
     SelectConvertFiles,
     ConvertFormatChanged(String),
     StartConvert,
 
+    // Folder Watcher
+    SelectWatchFolder,
+    WatchFolderSelected(Option<PathBuf>),
+    ToggleWatching,
+    WatchParseFilenameToggled(bool),
+    WatchDefaultGenreChanged(String),
+    WatchFolderCoverToggled(bool),
+    WatchedFileDetected(PathBuf),
+    WatchedFileTagged(Result<String, String>),
+    WatcherError(String),
+    ClearWatchLog,
+
     // Theme
     ToggleTheme,
 
     // Animation
     Tick(Instant),
 
+    // Keyboard
+    ModifiersChanged(iced::keyboard::Modifiers),
+
     // No-op (for read-only inputs)
     NoOp,
 }
@@ -1,8 +1,11 @@
 use crate::model::FileMetadata;
+use crate::utils::lyrics::{LyricLine, Lyrics};
 use lofty::config::WriteOptions;
+use lofty::id3::v2::{SyncTextContentType, SynchronizedText, TimestampFormat};
 use lofty::picture::Picture;
 use lofty::prelude::*;
-use lofty::tag::{Tag, TagType};
+use lofty::tag::{ItemValue, Tag, TagItem, TagType};
+use lofty::TextEncoding;
 use std::fs;
 use std::panic;
 use std::path::PathBuf;
@@ -63,37 +66,144 @@ pub fn remove_all_metadata(file_path: PathBuf) -> Result<(), String> {
     }
 }
 
-fn set_metadata_inner(
-    file_path: PathBuf,
-    artist: String,
-    album: String,
-    genre: Option<String>,
-    year: Option<u32>,
-    album_art: Option<PathBuf>,
-) -> Result<(), String> {
+/// Tag values to write; `None` and empty strings leave the existing value untouched
+#[derive(Debug, Clone, Default)]
+pub struct TagUpdate {
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub title: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    pub track: Option<u32>,
+    pub track_total: Option<u32>,
+    pub disc: Option<u32>,
+    pub disc_total: Option<u32>,
+    pub replay_gain: Option<ReplayGain>,
+    pub album_art: Option<PathBuf>,
+    /// Replaces both kinds of lyrics; empty parts remove them
+    pub lyrics: Option<Lyrics>,
+}
+
+/// ReplayGain 2.0 values, written as `REPLAYGAIN_*` tags
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayGain {
+    pub track_gain: f64,
+    pub track_peak: f64,
+    pub album_gain: Option<f64>,
+    pub album_peak: Option<f64>,
+}
+
+impl TagUpdate {
+    /// True when applying this update would not write any value
+    pub fn is_empty(&self) -> bool {
+        let blank = |v: &Option<String>| v.as_ref().is_none_or(|v| v.trim().is_empty());
+        blank(&self.artist)
+            && blank(&self.album)
+            && blank(&self.title)
+            && blank(&self.genre)
+            && self.year.is_none()
+            && self.track.is_none()
+            && self.track_total.is_none()
+            && self.disc.is_none()
+            && self.disc_total.is_none()
+            && self.replay_gain.is_none()
+            && self.album_art.is_none()
+            && self.lyrics.is_none()
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.is_empty())
+}
+
+/// ID3v2 keeps synchronized lyrics in SYLT frames, which lofty passes through as raw bytes
+fn sylt_key() -> ItemKey {
+    ItemKey::Unknown("SYLT".to_string())
+}
+
+fn set_lyrics(tag: &mut Tag, lyrics: Lyrics) -> Result<(), String> {
+    tag.remove_key(&ItemKey::Lyrics);
+    if !lyrics.unsynced.trim().is_empty() {
+        tag.insert_text(ItemKey::Lyrics, lyrics.unsynced);
+    }
+
+    // Other tag formats have no synchronized lyrics field; those go to .lrc files instead
+    if tag.tag_type() == TagType::Id3v2 {
+        tag.remove_key(&sylt_key());
+        if !lyrics.synced.is_empty() {
+            let frame = SynchronizedText {
+                encoding: TextEncoding::UTF8,
+                language: *b"XXX",
+                timestamp_format: TimestampFormat::MS,
+                content_type: SyncTextContentType::Lyrics,
+                description: None,
+                content: lyrics
+                    .synced
+                    .into_iter()
+                    .map(|l| (l.time.as_millis() as u32, l.text))
+                    .collect(),
+            };
+            let bytes = frame
+                .as_bytes()
+                .map_err(|e| format!("Error encoding synced lyrics: {}", e))?;
+            tag.insert_unchecked(TagItem::new(sylt_key(), ItemValue::Binary(bytes)));
+        }
+    }
+    Ok(())
+}
+
+fn set_metadata_inner(file_path: PathBuf, update: TagUpdate) -> Result<(), String> {
     match lofty::read_from_path(&file_path) {
         Ok(mut tagged_file) => {
             if tagged_file.primary_tag().is_none() {
-                tagged_file.insert_tag(Tag::new(TagType::Id3v2));
+                // ID3v2 for MP3, Vorbis comments for FLAC/Ogg, ...
+                let tag_type = tagged_file.primary_tag_type();
+                tagged_file.insert_tag(Tag::new(tag_type));
             }
 
             if let Some(tag) = tagged_file.primary_tag_mut() {
-                if !artist.is_empty() {
+                if let Some(artist) = non_empty(update.artist) {
                     tag.set_artist(artist);
                 }
-                if !album.is_empty() {
+                if let Some(album) = non_empty(update.album) {
                     tag.set_album(album);
                 }
-                if let Some(g) = genre {
-                    if !g.is_empty() {
-                        tag.set_genre(g);
-                    }
+                if let Some(title) = non_empty(update.title) {
+                    tag.set_title(title);
+                }
+                if let Some(genre) = non_empty(update.genre) {
+                    tag.set_genre(genre);
                 }
-                if let Some(y) = year {
+                if let Some(y) = update.year {
                     tag.set_year(y);
                 }
+                if let Some(track) = update.track {
+                    tag.set_track(track);
+                }
+                if let Some(total) = update.track_total {
+                    tag.set_track_total(total);
+                }
+                if let Some(disc) = update.disc {
+                    tag.set_disk(disc);
+                }
+                if let Some(total) = update.disc_total {
+                    tag.set_disk_total(total);
+                }
+                if let Some(rg) = update.replay_gain {
+                    let gain = |db: f64| format!("{:.2} dB", db);
+                    let peak = |linear: f64| format!("{:.6}", linear);
+                    tag.insert_text(ItemKey::ReplayGainTrackGain, gain(rg.track_gain));
+                    tag.insert_text(ItemKey::ReplayGainTrackPeak, peak(rg.track_peak));
+                    if let (Some(album_gain), Some(album_peak)) = (rg.album_gain, rg.album_peak) {
+                        tag.insert_text(ItemKey::ReplayGainAlbumGain, gain(album_gain));
+                        tag.insert_text(ItemKey::ReplayGainAlbumPeak, peak(album_peak));
+                    }
+                }
+                if let Some(lyrics) = update.lyrics {
+                    set_lyrics(tag, lyrics)?;
+                }
 
-                if let Some(art_path) = album_art {
+                if let Some(art_path) = update.album_art {
                     match fs::read(&art_path) {
                         Ok(image_data) => {
                             let mime_type = art_path
@@ -143,35 +253,16 @@ fn set_metadata_inner(
     }
 }
 
-pub fn set_metadata(
-    file_path: PathBuf,
-    artist: String,
-    album: String,
-    genre: Option<String>,
-    year: Option<u32>,
-    album_art: Option<PathBuf>,
-) -> Result<(), String> {
+pub fn set_metadata(file_path: PathBuf, update: TagUpdate) -> Result<(), String> {
     let (tx, rx) = mpsc::channel();
     let path = file_path.clone();
-    let artist_clone = artist.clone();
-    let album_clone = album.clone();
-    let genre_clone = genre.clone();
-    let art_clone = album_art.clone();
 
     let handle = thread::spawn(move || {
         let old_hook = panic::take_hook();
         panic::set_hook(Box::new(|_| {})); // Suppress panic output
 
-        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
-            set_metadata_inner(
-                path,
-                artist_clone,
-                album_clone,
-                genre_clone,
-                year,
-                art_clone,
-            )
-        }));
+        let result =
+            panic::catch_unwind(panic::AssertUnwindSafe(|| set_metadata_inner(path, update)));
 
         panic::set_hook(old_hook);
 
@@ -233,18 +324,56 @@ pub fn read_file_metadata(file_path: PathBuf) -> Result<FileMetadata, String> {
     }
 }
 
-pub async fn process_files(
-    files: Vec<PathBuf>,
-    artist: String,
-    album: String,
-    genre: Option<String>,
-    year: Option<u32>,
-    album_art: Option<PathBuf>,
-) -> Result<Vec<String>, String> {
+/// Time of an MP3 frame count: 1152 samples per frame for MPEG-1 (32 kHz and up), 576 for
+/// MPEG-2 and 2.5
+fn mpeg_frames_duration(frames: u32, sample_rate: Option<u32>) -> Duration {
+    let rate = sample_rate.filter(|r| *r > 0).unwrap_or(44_100);
+    let samples = if rate >= 32_000 { 1152 } else { 576 };
+    Duration::from_secs_f64(frames as f64 * samples as f64 / rate as f64)
+}
+
+/// Embedded lyrics of a file; synchronized lyrics are only read from ID3v2 SYLT frames
+pub fn read_lyrics(file_path: PathBuf) -> Result<Lyrics, String> {
+    let tagged_file =
+        lofty::read_from_path(&file_path).map_err(|e| format!("Error reading file: {}", e))?;
+    let Some(tag) = tagged_file.primary_tag() else {
+        return Ok(Lyrics::default());
+    };
+    let sample_rate = tagged_file.properties().sample_rate();
+
+    let unsynced = tag
+        .get_string(&ItemKey::Lyrics)
+        .map(str::to_string)
+        .unwrap_or_default();
+    let synced = tag
+        .get_binary(&sylt_key(), false)
+        .and_then(|bytes| SynchronizedText::parse(bytes).ok())
+        .map(|frame| {
+            let to_duration = |t: u32| match frame.timestamp_format {
+                TimestampFormat::MS => Duration::from_millis(t as u64),
+                // MPEG frame timestamps; saving the lyrics again writes milliseconds
+                TimestampFormat::MPEG => mpeg_frames_duration(t, sample_rate),
+            };
+            frame
+                .content
+                .into_iter()
+                .map(|(t, text)| LyricLine {
+                    time: to_duration(t),
+                    text,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(Lyrics { unsynced, synced })
+}
+
+/// Strip each file's existing tags and write its own [`TagUpdate`]
+pub async fn process_files(jobs: Vec<(PathBuf, TagUpdate)>) -> Result<Vec<String>, String> {
     let mut errors = Vec::new();
     const FILE_TIMEOUT: Duration = Duration::from_secs(30);
 
-    for file_path in files {
+    for (file_path, update) in jobs {
         let file_display = file_path.display().to_string();
 
         // Process remove_all_metadata with timeout
@@ -285,54 +414,124 @@ pub async fn process_files(
             }
         }
 
-        // Process set_metadata with timeout
-        let set_result = timeout(
-            FILE_TIMEOUT,
-            tokio::task::spawn_blocking({
-                let path = file_path.clone();
-                let artist_clone = artist.clone();
-                let album_clone = album.clone();
-                let genre_clone = genre.clone();
-                let art_clone = album_art.clone();
-                move || {
-                    set_metadata(
-                        path,
-                        artist_clone,
-                        album_clone,
-                        genre_clone,
-                        year,
-                        art_clone,
-                    )
-                }
-            }),
-        )
-        .await;
+        if let Err(e) = write_tags(file_path, update, FILE_TIMEOUT).await {
+            errors.push(format!("{}: {}", file_display, e));
+        }
+    }
 
-        match set_result {
-            Ok(Ok(Ok(()))) => {}
-            Ok(Ok(Err(e))) => {
-                errors.push(format!("{}: {}", file_display, e));
-            }
-            Ok(Err(e)) => {
-                let error_msg = if e.is_panic() {
-                    format!(
-                        "{}: Processing failed due to encoding/metadata corruption issue",
-                        file_display
-                    )
-                } else {
-                    format!("{}: Task error: {}", file_display, e)
-                };
-                errors.push(error_msg);
-            }
-            Err(_) => {
-                errors.push(format!(
-                    "{}: Timeout while setting metadata (exceeded {}s)",
-                    file_display,
-                    FILE_TIMEOUT.as_secs()
-                ));
-            }
+    Ok(errors)
+}
+
+/// Write each file's [`TagUpdate`] on top of its existing tags
+pub async fn update_files(jobs: Vec<(PathBuf, TagUpdate)>) -> Result<Vec<String>, String> {
+    let mut errors = Vec::new();
+    const FILE_TIMEOUT: Duration = Duration::from_secs(30);
+
+    for (file_path, update) in jobs {
+        let file_display = file_path.display().to_string();
+        if let Err(e) = write_tags(file_path, update, FILE_TIMEOUT).await {
+            errors.push(format!("{}: {}", file_display, e));
         }
     }
 
     Ok(errors)
 }
+
+async fn write_tags(
+    file_path: PathBuf,
+    update: TagUpdate,
+    file_timeout: Duration,
+) -> Result<(), String> {
+    let set_result = timeout(
+        file_timeout,
+        tokio::task::spawn_blocking(move || set_metadata(file_path, update)),
+    )
+    .await;
+
+    match set_result {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => {
+            if e.is_panic() {
+                Err("Processing failed due to encoding/metadata corruption issue".to_string())
+            } else {
+                Err(format!("Task error: {}", e))
+            }
+        }
+        Err(_) => Err(format!(
+            "Timeout while setting metadata (exceeded {}s)",
+            file_timeout.as_secs()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Silent CBR MP3 (128 kbps, 44.1 kHz mono) for lofty to tag
+    fn silent_mp3(path: &Path) {
+        let mut frame = vec![0u8; 417];
+        frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0xC4]);
+        std::fs::write(path, frame.repeat(100)).unwrap();
+    }
+
+    fn sylt(path: &Path) -> SynchronizedText {
+        let tagged_file = lofty::read_from_path(path).unwrap();
+        let bytes = tagged_file
+            .primary_tag()
+            .and_then(|tag| tag.get_binary(&sylt_key(), false))
+            .unwrap();
+        SynchronizedText::parse(bytes).unwrap()
+    }
+
+    #[test]
+    fn synced_lyrics_are_written_in_milliseconds() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("song.mp3");
+        silent_mp3(&path);
+        let synced = vec![
+            LyricLine {
+                time: Duration::from_millis(1_250),
+                text: "One".to_string(),
+            },
+            LyricLine {
+                time: Duration::from_millis(61_500),
+                text: "Two".to_string(),
+            },
+        ];
+        let update = TagUpdate {
+            lyrics: Some(Lyrics {
+                unsynced: String::new(),
+                synced: synced.clone(),
+            }),
+            ..TagUpdate::default()
+        };
+        set_metadata(path.clone(), update).unwrap();
+
+        let frame = sylt(&path);
+        assert_eq!(frame.timestamp_format, TimestampFormat::MS);
+        assert_eq!(
+            frame.content,
+            [(1_250, "One".to_string()), (61_500, "Two".to_string())]
+        );
+        assert_eq!(read_lyrics(path).unwrap().synced, synced);
+    }
+
+    #[test]
+    fn mpeg_frame_timestamps_use_the_file_sample_rate() {
+        assert_eq!(
+            mpeg_frames_duration(441, Some(44_100)),
+            Duration::from_millis(11_520)
+        );
+        assert_eq!(
+            mpeg_frames_duration(1000, Some(48_000)),
+            Duration::from_secs(24)
+        );
+        // MPEG-2 frames hold half as many samples
+        assert_eq!(
+            mpeg_frames_duration(1000, Some(24_000)),
+            Duration::from_secs(24)
+        );
+    }
+}
@@ -1,6 +1,23 @@
+use crate::metadata::TagUpdate;
+use crate::settings::TagPreset;
+use crate::utils::audio_player::{PlaybackState, PlayerSource, PlayerStatus};
+use crate::utils::folder_watcher::WatchRules;
+use crate::utils::fuzzy::fuzzy_suggest;
+use crate::utils::genres::vocabulary;
+use crate::utils::integrity::FileCheck;
+use crate::utils::loudness::TrackLoudness;
+use crate::utils::lyrics::LyricLine;
 use crate::utils::music_downloader::MusicDownloaderState;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use crate::utils::quality::QualityReport;
+use crate::utils::text_rules::{FieldChange, TagField, TextRules};
+use crate::utils::track_numbering::{disc_number, TrackOrder};
+use iced::widget::{svg, text_editor};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Autocomplete matches shown under the artist and album fields
+const SUGGESTION_LIMIT: usize = 3;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Screen {
@@ -9,6 +26,7 @@ pub enum Screen {
     MetadataEditor,
     MusicDownloader,
     AudioConverter,
+    FolderWatcher,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -26,6 +44,145 @@ pub struct FileMetadata {
     pub channels: Option<u8>,
 }
 
+/// Rendered waveform and spectrogram of the selected file
+#[derive(Debug, Clone)]
+pub struct SpectrumView {
+    pub waveform: svg::Handle,
+    pub spectrogram: svg::Handle,
+    pub nyquist_hz: f32,
+}
+
+/// What the middle column of the Metadata Editor shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EditorPanel {
+    #[default]
+    FileDetails,
+    TextCleanup,
+    Presets,
+    Genres,
+    Loudness,
+    Integrity,
+    Lyrics,
+    Quality,
+}
+
+impl EditorPanel {
+    pub const ALL: [EditorPanel; 8] = [
+        EditorPanel::FileDetails,
+        EditorPanel::Presets,
+        EditorPanel::TextCleanup,
+        EditorPanel::Genres,
+        EditorPanel::Lyrics,
+        EditorPanel::Loudness,
+        EditorPanel::Quality,
+        EditorPanel::Integrity,
+    ];
+}
+
+impl std::fmt::Display for EditorPanel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EditorPanel::FileDetails => write!(f, "File Details"),
+            EditorPanel::TextCleanup => write!(f, "Text Cleanup"),
+            EditorPanel::Presets => write!(f, "Presets"),
+            EditorPanel::Genres => write!(f, "Genres"),
+            EditorPanel::Loudness => write!(f, "Loudness"),
+            EditorPanel::Integrity => write!(f, "Verify Files"),
+            EditorPanel::Lyrics => write!(f, "Lyrics"),
+            EditorPanel::Quality => write!(f, "Quality Check"),
+        }
+    }
+}
+
+/// Files sharing a directory, treated as one album with its own tag values.
+/// Values not edited for the group fall back to the edit panel's shared values.
+#[derive(Debug, Clone, Default)]
+pub struct AlbumGroup {
+    pub dir: PathBuf,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub year: Option<String>,
+    /// Guesses from the directory name, only shown as hints
+    pub guessed_artist: String,
+    pub guessed_album: String,
+    pub guessed_year: String,
+    pub album_art_path: Option<PathBuf>,
+}
+
+impl AlbumGroup {
+    /// Start a group with hints guessed from directory names like `Artist - Album (2001)`.
+    /// Disc folders such as `CD1` take their values from the album folder above them.
+    pub fn from_dir(dir: &Path) -> Self {
+        let album_dir = match (disc_number(dir), dir.parent()) {
+            (Some(_), Some(parent)) => parent,
+            _ => dir,
+        };
+        let name = album_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().trim().to_string())
+            .unwrap_or_default();
+
+        let mut year = String::new();
+        let mut rest = name.as_str();
+        // "(2001)" / "[2001]" at either end of the name
+        for (open, close) in [('(', ')'), ('[', ']')] {
+            if let Some(inner) = rest
+                .strip_suffix(close)
+                .and_then(|r| r.rsplit_once(open))
+                .filter(|(_, y)| y.len() == 4 && y.chars().all(|c| c.is_ascii_digit()))
+            {
+                year = inner.1.to_string();
+                rest = inner.0.trim_end();
+            } else if let Some((y, r)) = rest
+                .strip_prefix(open)
+                .and_then(|r| r.split_once(close))
+                .filter(|(y, _)| y.len() == 4 && y.chars().all(|c| c.is_ascii_digit()))
+            {
+                year = y.to_string();
+                rest = r.trim_start();
+            }
+        }
+
+        let (artist, album) = match rest.split_once(" - ") {
+            Some((artist, album)) => (artist.trim().to_string(), album.trim().to_string()),
+            None => (String::new(), rest.to_string()),
+        };
+
+        Self {
+            dir: dir.to_path_buf(),
+            guessed_artist: artist,
+            guessed_album: album,
+            guessed_year: year,
+            ..Self::default()
+        }
+    }
+
+    pub fn display_name(&self) -> String {
+        self.dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.dir.display().to_string())
+    }
+}
+
+/// Autocomplete matches for a field, with the value they were computed for
+#[derive(Debug, Clone, Default)]
+pub struct Suggestions {
+    pub query: String,
+    pub matches: Vec<String>,
+}
+
+impl Suggestions {
+    /// The matches, when they still belong to the field's current value
+    pub fn for_value(&self, value: &str) -> Vec<String> {
+        if self.query == value {
+            self.matches.clone()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
 pub struct AppState {
     pub current_screen: Screen,
     pub files: Vec<PathBuf>,
@@ -34,6 +191,8 @@ pub struct AppState {
     pub genre: String,
     pub year: String,
     pub album_art_path: Option<PathBuf>,
+    pub directory_covers: HashMap<PathBuf, PathBuf>,
+    pub use_directory_covers: bool,
     pub status: String,
     pub error_logs: Vec<String>,
     pub processing: bool,
@@ -42,13 +201,69 @@ pub struct AppState {
     pub pending_folder_scan: Option<PathBuf>,
     pub scan_delay_ticks: u32,
     pub selected_file_index: Option<usize>,
+    pub checked_files: HashSet<usize>,
+    pub last_checked_index: Option<usize>,
+    pub shift_held: bool,
+    pub processing_count: usize,
     pub file_metadata: HashMap<usize, FileMetadata>,
     pub last_metadata_folder: Option<PathBuf>,
+    pub group_by_album: bool,
+    pub album_groups: Vec<AlbumGroup>,
+    pub selected_group: Option<usize>,
+    pub track_order: TrackOrder,
+    pub editor_panel: EditorPanel,
+    pub text_rules: TextRules,
+    pub presets: Vec<TagPreset>,
+    pub preset_name: String,
+    pub custom_genres: Vec<String>,
+    pub custom_genre_input: String,
+    pub artist_suggestions: Suggestions,
+    pub album_suggestions: Suggestions,
+    pub merge_source: Option<String>,
+    pub merge_target: String,
+    pub analyzing_loudness: bool,
+    pub loudness_queue: Vec<PathBuf>,
+    pub loudness_total: usize,
+    pub loudness_results: Vec<(PathBuf, Result<TrackLoudness, String>)>,
+    pub verifying: bool,
+    pub verify_queue: Vec<PathBuf>,
+    pub verify_total: usize,
+    pub verify_results: Vec<(PathBuf, FileCheck)>,
+    pub analyzing_quality: bool,
+    pub quality_queue: Vec<PathBuf>,
+    pub quality_total: usize,
+    /// Transcode check per file, also shown as a column in the file list
+    pub quality_results: HashMap<PathBuf, Result<QualityReport, String>>,
+    /// File the waveform and spectrogram are for; `spectrum` is `None` while computing
+    pub spectrum_file: Option<PathBuf>,
+    pub spectrum: Option<Result<SpectrumView, String>>,
+    /// File the lyrics panel is editing
+    pub lyrics_file: Option<PathBuf>,
+    pub lyrics_text: text_editor::Content,
+    pub lyrics_lines: Vec<LyricLine>,
+    /// Line the next timestamp is stamped onto
+    pub lyrics_cursor: usize,
+    /// Latest state published by the audio player
+    pub player: PlayerStatus,
+    /// Position under a seek slider while it is being dragged
+    pub player_seek: Option<f32>,
+    /// Overlap between consecutive tracks, in seconds
+    pub crossfade_secs: f32,
+    /// Editor previews carry on with the next file of the list
+    pub play_through: bool,
+    /// The playing file was started from the editor preview rather than the lyrics panel
+    pub preview_follow: bool,
     // Music Downloader state
     pub downloader_state: MusicDownloaderState,
     // Audio Converter state
     pub convert_format: String,
     pub convert_status: String,
+    // Folder Watcher state
+    pub watch_folder: Option<PathBuf>,
+    pub watch_active: bool,
+    pub watch_rules: WatchRules,
+    pub watch_log: Vec<String>,
+    pub watch_status: String,
 }
 
 impl Default for AppState {
@@ -61,6 +276,8 @@ impl Default for AppState {
             genre: String::new(),
             year: String::new(),
             album_art_path: None,
+            directory_covers: HashMap::new(),
+            use_directory_covers: true,
             status: "Ready to edit metadata".to_string(),
             error_logs: Vec::new(),
             processing: false,
@@ -69,11 +286,327 @@ impl Default for AppState {
             pending_folder_scan: None,
             scan_delay_ticks: 0,
             selected_file_index: None,
+            checked_files: HashSet::new(),
+            last_checked_index: None,
+            shift_held: false,
+            processing_count: 0,
             file_metadata: HashMap::new(),
             last_metadata_folder: None,
+            group_by_album: false,
+            album_groups: Vec::new(),
+            selected_group: None,
+            track_order: TrackOrder::default(),
+            editor_panel: EditorPanel::default(),
+            text_rules: TextRules::default(),
+            presets: Vec::new(),
+            preset_name: String::new(),
+            custom_genres: Vec::new(),
+            custom_genre_input: String::new(),
+            artist_suggestions: Suggestions::default(),
+            album_suggestions: Suggestions::default(),
+            merge_source: None,
+            merge_target: String::new(),
+            analyzing_loudness: false,
+            loudness_queue: Vec::new(),
+            loudness_total: 0,
+            loudness_results: Vec::new(),
+            verifying: false,
+            verify_queue: Vec::new(),
+            verify_total: 0,
+            verify_results: Vec::new(),
+            analyzing_quality: false,
+            quality_queue: Vec::new(),
+            quality_total: 0,
+            quality_results: HashMap::new(),
+            spectrum_file: None,
+            spectrum: None,
+            lyrics_file: None,
+            lyrics_text: text_editor::Content::new(),
+            lyrics_lines: Vec::new(),
+            lyrics_cursor: 0,
+            player: PlayerStatus::default(),
+            player_seek: None,
+            crossfade_secs: 0.0,
+            play_through: false,
+            preview_follow: false,
             downloader_state: MusicDownloaderState::new(),
             convert_format: "MP3".to_string(),
             convert_status: "Select files to convert".to_string(),
+            watch_folder: None,
+            watch_active: false,
+            watch_rules: WatchRules::default(),
+            watch_log: Vec::new(),
+            watch_status: "Select a folder to watch".to_string(),
+        }
+    }
+}
+
+impl AppState {
+    /// Local file the player has loaded, playing or paused
+    pub fn playing_file(&self) -> Option<&Path> {
+        match &self.player.source {
+            Some(PlayerSource::File(path)) if self.player.state != PlaybackState::Stopped => {
+                Some(path)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the lyrics panel's file is playing
+    pub fn lyrics_playing(&self) -> bool {
+        self.lyrics_file.is_some() && self.playing_file() == self.lyrics_file.as_deref()
+    }
+
+    /// Playback position of the lyrics panel's file, or zero when it is not playing
+    pub fn lyrics_position(&self) -> Duration {
+        if self.lyrics_playing() {
+            self.player.position
+        } else {
+            Duration::ZERO
         }
     }
+
+    /// Files the edit panel applies to: the checked files, or every file when none are checked
+    pub fn target_files(&self) -> Vec<PathBuf> {
+        self.target_indices()
+            .into_iter()
+            .filter_map(|i| self.files.get(i).cloned())
+            .collect()
+    }
+
+    /// Indices into `files` of [`Self::target_files`], in list order
+    pub fn target_indices(&self) -> Vec<usize> {
+        if self.checked_files.is_empty() {
+            return (0..self.files.len()).collect();
+        }
+        let mut indices: Vec<usize> = self.checked_files.iter().copied().collect();
+        indices.sort_unstable();
+        indices
+    }
+
+    /// Changes the text cleanup rules would make to each target file with loaded metadata
+    pub fn text_rule_changes(&self) -> Result<Vec<(usize, Vec<FieldChange>)>, String> {
+        let rules = self.text_rules.compile()?;
+        Ok(self
+            .target_indices()
+            .into_iter()
+            .filter_map(|index| {
+                let metadata = self.file_metadata.get(&index)?;
+                let changes: Vec<FieldChange> = TagField::ALL
+                    .iter()
+                    .filter_map(|&field| {
+                        let before = match field {
+                            TagField::Title => &metadata.title,
+                            TagField::Artist => &metadata.artist,
+                            TagField::Album => &metadata.album,
+                            TagField::Genre => &metadata.genre,
+                        };
+                        let after = rules.apply(before);
+                        (after != *before).then(|| FieldChange {
+                            field,
+                            before: before.clone(),
+                            after,
+                        })
+                    })
+                    .collect();
+                (!changes.is_empty()).then_some((index, changes))
+            })
+            .collect())
+    }
+
+    /// Cover image for `file`: its album group's image, then the chosen image, else its
+    /// directory's sidecar image when enabled
+    pub fn cover_for(&self, file: &Path) -> Option<PathBuf> {
+        if let Some(art) = self.group_for(file).and_then(|g| g.album_art_path.clone()) {
+            return Some(art);
+        }
+        let directory_cover = if self.use_directory_covers {
+            file.parent()
+                .and_then(|dir| self.directory_covers.get(dir))
+                .cloned()
+        } else {
+            None
+        };
+        self.album_art_path.clone().or(directory_cover)
+    }
+
+    /// Values the edit panel will write to `file`, with values edited for its album group
+    /// taking precedence over the shared ones
+    pub fn tag_update_for(&self, file: &Path) -> TagUpdate {
+        let group = self.group_for(file);
+        let pick = |group_value: Option<&String>, shared: &String| {
+            group_value
+                .filter(|v| !v.trim().is_empty())
+                .unwrap_or(shared)
+                .clone()
+        };
+        let year = pick(group.and_then(|g| g.year.as_ref()), &self.year);
+
+        TagUpdate {
+            artist: Some(pick(group.and_then(|g| g.artist.as_ref()), &self.artist)),
+            album: Some(pick(group.and_then(|g| g.album.as_ref()), &self.album)),
+            title: None,
+            genre: if self.genre.trim().is_empty() {
+                None
+            } else {
+                Some(self.genre.clone())
+            },
+            year: year.trim().parse::<u32>().ok(),
+            album_art: self.cover_for(file),
+            ..TagUpdate::default()
+        }
+    }
+
+    /// The album group `file` belongs to, when grouping by directory is enabled
+    pub fn group_for(&self, file: &Path) -> Option<&AlbumGroup> {
+        if !self.group_by_album {
+            return None;
+        }
+        let dir = file.parent()?;
+        self.album_groups.iter().find(|g| g.dir == dir)
+    }
+
+    /// The group whose values the edit panel is currently editing
+    pub fn editing_group(&self) -> Option<&AlbumGroup> {
+        if !self.group_by_album {
+            return None;
+        }
+        self.selected_group.and_then(|i| self.album_groups.get(i))
+    }
+
+    pub fn editing_group_mut(&mut self) -> Option<&mut AlbumGroup> {
+        if !self.group_by_album {
+            return None;
+        }
+        self.selected_group
+            .and_then(|i| self.album_groups.get_mut(i))
+    }
+
+    /// Indices into `files` for each album group, in group order
+    pub fn group_file_indices(&self) -> Vec<Vec<usize>> {
+        self.album_groups
+            .iter()
+            .map(|g| {
+                self.files
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, f)| f.parent() == Some(g.dir.as_path()))
+                    .map(|(i, _)| i)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Sync album groups with the current file list, keeping values of groups that remain
+    pub fn rebuild_album_groups(&mut self) {
+        let mut groups: Vec<AlbumGroup> = Vec::new();
+        for dir in self.files.iter().filter_map(|f| f.parent()) {
+            if groups.iter().any(|g| g.dir == dir) {
+                continue;
+            }
+            let group = self
+                .album_groups
+                .iter()
+                .find(|g| g.dir == dir)
+                .cloned()
+                .unwrap_or_else(|| AlbumGroup::from_dir(dir));
+            groups.push(group);
+        }
+        let selected_dir = self
+            .selected_group
+            .and_then(|i| self.album_groups.get(i))
+            .map(|g| g.dir.clone());
+        self.selected_group = selected_dir.and_then(|dir| groups.iter().position(|g| g.dir == dir));
+        self.album_groups = groups;
+    }
+
+    /// Known artists closest to `query`, from the loaded files, album groups, presets and the
+    /// downloader's artist list
+    pub fn suggest_artists(&self, query: &str, limit: usize) -> Vec<String> {
+        let candidates = self
+            .file_metadata
+            .values()
+            .map(|m| m.artist.as_str())
+            .chain(
+                self.other_groups()
+                    .map(|g| g.artist.as_deref().unwrap_or(&g.guessed_artist)),
+            )
+            .chain(self.presets.iter().map(|p| p.artist.as_str()))
+            .chain(
+                self.downloader_state
+                    .all_artists
+                    .iter()
+                    .map(|a| a.name.as_str()),
+            );
+        fuzzy_suggest(query, candidates, limit)
+    }
+
+    /// Refresh the artist matches for the value just typed; too slow to run on every view
+    pub fn update_artist_suggestions(&mut self, value: &str) {
+        self.artist_suggestions = Suggestions {
+            query: value.to_string(),
+            matches: self.suggest_artists(value, SUGGESTION_LIMIT),
+        };
+    }
+
+    pub fn update_album_suggestions(&mut self, value: &str) {
+        self.album_suggestions = Suggestions {
+            query: value.to_string(),
+            matches: self.suggest_albums(value, SUGGESTION_LIMIT),
+        };
+    }
+
+    /// Album groups other than the one being edited, whose values are still being typed
+    fn other_groups(&self) -> impl Iterator<Item = &AlbumGroup> {
+        self.album_groups
+            .iter()
+            .enumerate()
+            .filter(move |(i, _)| Some(*i) != self.selected_group)
+            .map(|(_, g)| g)
+    }
+
+    /// Known albums closest to `query`, from the loaded files, album groups and presets
+    pub fn suggest_albums(&self, query: &str, limit: usize) -> Vec<String> {
+        let candidates = self
+            .file_metadata
+            .values()
+            .map(|m| m.album.as_str())
+            .chain(
+                self.other_groups()
+                    .map(|g| g.album.as_deref().unwrap_or(&g.guessed_album)),
+            )
+            .chain(self.presets.iter().map(|p| p.album.as_str()));
+        fuzzy_suggest(query, candidates, limit)
+    }
+
+    /// Custom genres followed by the standard ID3 genre list
+    pub fn genre_vocabulary(&self) -> Vec<String> {
+        vocabulary(&self.custom_genres)
+    }
+
+    /// Distinct, non-empty genres from the loaded file metadata
+    pub fn library_genres(&self) -> Vec<String> {
+        let mut genres: Vec<String> = self
+            .file_metadata
+            .values()
+            .map(|m| m.genre.trim().to_string())
+            .filter(|g| !g.is_empty())
+            .collect();
+        genres.sort();
+        genres.dedup();
+        genres
+    }
+
+    /// Distinct, non-empty album names from the loaded file metadata
+    pub fn known_albums(&self) -> Vec<String> {
+        let mut albums: Vec<String> = self
+            .file_metadata
+            .values()
+            .map(|m| m.album.trim().to_string())
+            .filter(|a| !a.is_empty())
+            .collect();
+        albums.sort();
+        albums.dedup();
+        albums
+    }
 }
@@ -1,3 +1,5 @@
+use crate::utils::folder_watcher::WatchRules;
+use crate::utils::text_rules::TextRules;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
@@ -6,6 +8,41 @@ use std::path::{Path, PathBuf};
 pub struct AppSettings {
     pub last_download_directory: Option<String>,
     pub last_metadata_folder: Option<String>,
+    #[serde(default)]
+    pub watch_folder: Option<String>,
+    #[serde(default)]
+    pub watch_rules: WatchRules,
+    #[serde(default)]
+    pub presets: Vec<TagPreset>,
+    #[serde(default)]
+    pub custom_genres: Vec<String>,
+    /// Overlap between consecutive preview tracks, in seconds
+    #[serde(default)]
+    pub crossfade_secs: f32,
+    #[serde(default)]
+    pub play_through: bool,
+}
+
+/// Named set of edit panel values for compilations and labels that are tagged repeatedly
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct TagPreset {
+    pub name: String,
+    pub artist: String,
+    pub album: String,
+    pub genre: String,
+    pub year: String,
+    pub cover_path: Option<String>,
+    pub text_rules: TextRules,
+}
+
+impl TagPreset {
+    pub fn get_cover_path(&self) -> Option<PathBuf> {
+        self.cover_path
+            .as_ref()
+            .map(PathBuf::from)
+            .filter(|p| p.exists())
+    }
 }
 
 impl AppSettings {
@@ -22,10 +59,9 @@ impl AppSettings {
             Ok(path) => {
                 if path.exists() {
                     match std::fs::read_to_string(&path) {
-                        Ok(content) => match serde_json::from_str::<AppSettings>(&content) {
-                            Ok(settings) => settings,
-                            Err(_) => Self::default(),
-                        },
+                        Ok(content) => {
+                            serde_json::from_str::<AppSettings>(&content).unwrap_or_default()
+                        }
                         Err(_) => Self::default(),
                     }
                 } else {
@@ -46,14 +82,21 @@ impl AppSettings {
     pub fn get_download_directory(&self) -> Option<PathBuf> {
         self.last_download_directory
             .as_ref()
-            .map(|s| PathBuf::from(s))
+            .map(PathBuf::from)
             .filter(|p| p.exists())
     }
 
     pub fn get_metadata_folder(&self) -> Option<PathBuf> {
         self.last_metadata_folder
             .as_ref()
-            .map(|s| PathBuf::from(s))
+            .map(PathBuf::from)
+            .filter(|p| p.exists())
+    }
+
+    pub fn get_watch_folder(&self) -> Option<PathBuf> {
+        self.watch_folder
+            .as_ref()
+            .map(PathBuf::from)
             .filter(|p| p.exists())
     }
 
@@ -64,4 +107,8 @@ impl AppSettings {
     pub fn set_metadata_folder(&mut self, path: Option<&Path>) {
         self.last_metadata_folder = path.map(|p| p.to_string_lossy().to_string());
     }
+
+    pub fn set_watch_folder(&mut self, path: Option<&Path>) {
+        self.watch_folder = path.map(|p| p.to_string_lossy().to_string());
+    }
 }
@@ -0,0 +1,562 @@
+// Audio Decode Utility
+// Decodes audio files to PCM with the symphonia decoders playback, analysis and integrity
+// checks share: MP3, FLAC, Ogg Vorbis, WAV, AIFF, and AAC or ALAC in MP4/M4A. There is
+// no Opus or WMA decoder, so those files can be tagged but not played or analyzed.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::panic;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use symphonia::core::audio::{Channels, SampleBuffer, SignalSpec};
+use symphonia::core::codecs::{self, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::conv::ConvertibleSample;
+use symphonia::core::errors::{Error, SeekErrorKind};
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use symphonia::core::units::TimeBase;
+
+/// Interleaved frames handed to the callback per call
+const CHUNK_FRAMES: usize = 4096;
+/// Shown when a file is in a format symphonia has no reader or decoder for
+pub const UNSUPPORTED_FORMAT: &str = "Unsupported audio format (WMA and Opus cannot be decoded)";
+/// Recorded when the data ends inside a packet, as in a cut-off download
+pub const TRUNCATED: &str = "Audio data ends partway through a frame";
+/// Bytes at the end of a source handed to the reader one at a time, so its position there is
+/// exact. More than the largest packet of any supported format.
+const TAIL_LEN: u64 = 256 * 1024;
+
+/// Streaming decoder yielding interleaved samples, usable directly as a rodio source.
+/// Playback takes 16-bit samples; analysis takes `f32` so peaks over full scale survive.
+pub struct AudioDecoder<S: ConvertibleSample = i16> {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn codecs::Decoder>,
+    track_id: u32,
+    spec: SignalSpec,
+    time_base: Option<TimeBase>,
+    duration: Option<Duration>,
+    buffer: SampleBuffer<S>,
+    index: usize,
+    /// Samples still to drop after a seek landed before the position asked for
+    skip: usize,
+    /// Sought past the end
+    finished: bool,
+    /// Why decoding stopped early, if it did
+    error: Option<String>,
+    tail: Arc<Mutex<SourceTail>>,
+}
+
+impl<S: ConvertibleSample> AudioDecoder<S> {
+    /// Probe and open `source`; `extension` helps the probe pick the right reader
+    pub fn open<M>(source: M, extension: Option<&str>) -> Result<Self, String>
+    where
+        M: MediaSource + 'static,
+    {
+        let source = TrackedSource::new(source);
+        let tail = source.tail.clone();
+        let stream = MediaSourceStream::new(Box::new(source), Default::default());
+        let mut hint = Hint::new();
+        if let Some(extension) = extension {
+            hint.with_extension(extension);
+        }
+        let probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                stream,
+                // Trims the encoder delay and padding given by MP3 LAME/Xing headers and Ogg
+                // granule positions; M4A's iTunSMPB is not read, so its padding stays
+                &FormatOptions {
+                    enable_gapless: true,
+                    ..Default::default()
+                },
+                &MetadataOptions::default(),
+            )
+            .map_err(|e| match e {
+                Error::Unsupported(_) => UNSUPPORTED_FORMAT.to_string(),
+                e => format!("Unreadable audio: {}", e),
+            })?;
+        let format = probed.format;
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or("No audio track found")?;
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|_| UNSUPPORTED_FORMAT.to_string())?;
+        let params = &track.codec_params;
+        let duration = params.n_frames.and_then(|frames| match params.time_base {
+            Some(base) => Some(base.calc_time(frames).into()),
+            None => params
+                .sample_rate
+                .map(|rate| Duration::from_secs_f64(frames as f64 / rate as f64)),
+        });
+
+        let mut audio = Self {
+            track_id: track.id,
+            spec: SignalSpec::new(
+                params.sample_rate.unwrap_or(44_100),
+                params.channels.unwrap_or_default(),
+            ),
+            time_base: params.time_base,
+            duration,
+            buffer: SampleBuffer::new(0, SignalSpec::new(44_100, Channels::FRONT_LEFT)),
+            index: 0,
+            skip: 0,
+            finished: false,
+            error: None,
+            tail,
+            format,
+            decoder,
+        };
+        // The first packet settles the layout when the container does not give it
+        if !audio.decode_next() && audio.spec.channels.count() == 0 {
+            return Err(audio
+                .error
+                .unwrap_or_else(|| "No audio data found".to_string()));
+        }
+        Ok(audio)
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.spec.channels.count().max(1) as u16
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.spec.rate
+    }
+
+    pub fn total_duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// Read error that ended the stream early, as opposed to reaching its end
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Move to `position`. The reader jumps to a nearby byte offset instead of decoding
+    /// its way there, then the samples between where it landed and `position` are dropped.
+    pub fn seek(&mut self, position: Duration) -> Result<(), String> {
+        self.index = self.buffer.len();
+        // Some readers clamp a seek past the end to their last packet
+        if self.duration.is_some_and(|duration| position >= duration) {
+            self.finished = true;
+            return Ok(());
+        }
+        let to = SeekTo::Time {
+            time: position.into(),
+            track_id: Some(self.track_id),
+        };
+        let seeked = match self.format.seek(SeekMode::Coarse, to) {
+            Ok(seeked) => seeked,
+            Err(Error::SeekError(SeekErrorKind::OutOfRange)) => {
+                self.finished = true;
+                return Ok(());
+            }
+            Err(e) => return Err(format!("Failed to seek: {}", e)),
+        };
+        self.decoder.reset();
+        let behind = seeked.required_ts.saturating_sub(seeked.actual_ts);
+        let frames = match self.time_base {
+            Some(base) => {
+                let time = base.calc_time(behind);
+                ((time.seconds as f64 + time.frac) * self.spec.rate as f64).round() as usize
+            }
+            None => behind as usize,
+        };
+        self.skip = frames * self.channels() as usize;
+        Ok(())
+    }
+
+    /// Decode the next packet of the track into the buffer, skipping corrupt packets.
+    /// Returns false at the end of the stream.
+    fn decode_next(&mut self) -> bool {
+        if self.finished {
+            return false;
+        }
+        loop {
+            let before = self.tail.lock().unwrap_or_else(|e| e.into_inner()).position;
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(Error::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    if self.ended_inside_packet(before) {
+                        self.error = Some(TRUNCATED.to_string());
+                    }
+                    return false;
+                }
+                Err(e) => {
+                    self.error = Some(e.to_string());
+                    return false;
+                }
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+            match self.decoder.decode(&packet) {
+                Ok(decoded) => {
+                    if decoded.frames() == 0 {
+                        continue;
+                    }
+                    let spec = *decoded.spec();
+                    if spec != self.spec
+                        || self.buffer.capacity() < decoded.capacity() * spec.channels.count()
+                    {
+                        self.spec = spec;
+                        self.buffer = SampleBuffer::new(decoded.capacity() as u64, spec);
+                    }
+                    self.buffer.copy_interleaved_ref(decoded);
+                    self.index = self.skip.min(self.buffer.len());
+                    self.skip -= self.index;
+                    if self.index < self.buffer.len() {
+                        return true;
+                    }
+                }
+                Err(Error::DecodeError(_)) => continue,
+                Err(e) => {
+                    self.error = Some(e.to_string());
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// Whether the read that hit the end of the data, started at `before`, had begun a packet:
+    /// it took bytes that are not just trailing tags or padding
+    fn ended_inside_packet(&self, before: u64) -> bool {
+        let tail = self.tail.lock().unwrap_or_else(|e| e.into_inner());
+        match tail.start {
+            Some(start) if before >= start && tail.position > before => {
+                let rest = tail
+                    .bytes
+                    .get((before - start) as usize..)
+                    .unwrap_or_default();
+                !is_trailer(rest)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<S: ConvertibleSample> Iterator for AudioDecoder<S> {
+    type Item = S;
+
+    fn next(&mut self) -> Option<S> {
+        if self.index >= self.buffer.len() && !self.decode_next() {
+            return None;
+        }
+        let sample = self.buffer.samples()[self.index];
+        self.index += 1;
+        Some(sample)
+    }
+}
+
+impl<S: ConvertibleSample + rodio::Sample> rodio::Source for AudioDecoder<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        AudioDecoder::channels(self)
+    }
+
+    fn sample_rate(&self) -> u32 {
+        AudioDecoder::sample_rate(self)
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.duration
+    }
+}
+
+/// The end of a source as the reader has seen it
+#[derive(Default)]
+struct SourceTail {
+    /// Offset of the last `TAIL_LEN` bytes; `None` when the length is unknown
+    start: Option<u64>,
+    /// The bytes from `start` on, loaded when the reader first gets there
+    bytes: Vec<u8>,
+    loaded: bool,
+    /// Bytes handed to the reader so far
+    position: u64,
+}
+
+/// Source wrapper that lets the decoder tell data cut off inside a packet from data that
+/// ends cleanly: in the tail it hands over one byte per read, so the reader never buffers
+/// past what it has parsed and `position` shows where each packet read started
+struct TrackedSource<M> {
+    inner: M,
+    tail: Arc<Mutex<SourceTail>>,
+    /// Where `inner` is, which differs from `position` once the tail is loaded
+    inner_position: u64,
+}
+
+impl<M: MediaSource> TrackedSource<M> {
+    fn new(inner: M) -> Self {
+        let tail = SourceTail {
+            start: inner.byte_len().map(|len| len.saturating_sub(TAIL_LEN)),
+            ..SourceTail::default()
+        };
+        Self {
+            inner,
+            tail: Arc::new(Mutex::new(tail)),
+            inner_position: 0,
+        }
+    }
+}
+
+impl<M: MediaSource> Read for TrackedSource<M> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut tail = self.tail.lock().unwrap_or_else(|e| e.into_inner());
+        let position = tail.position;
+        let Some(start) = tail.start.filter(|start| position >= *start) else {
+            // Stop at the tail so the reader's buffer cannot reach into it
+            let len = match tail.start {
+                Some(start) => buf.len().min((start - position) as usize),
+                None => buf.len(),
+            };
+            if self.inner_position != position {
+                self.inner_position = self.inner.seek(SeekFrom::Start(position))?;
+            }
+            let read = self.inner.read(&mut buf[..len])?;
+            tail.position += read as u64;
+            self.inner_position += read as u64;
+            return Ok(read);
+        };
+        if !tail.loaded {
+            if self.inner_position != start {
+                self.inner_position = self.inner.seek(SeekFrom::Start(start))?;
+            }
+            let mut bytes = Vec::new();
+            let read = (&mut self.inner).take(TAIL_LEN).read_to_end(&mut bytes)?;
+            self.inner_position += read as u64;
+            tail.bytes = bytes;
+            tail.loaded = true;
+        }
+        match (buf.first_mut(), tail.bytes.get((position - start) as usize)) {
+            (Some(out), Some(byte)) => {
+                *out = *byte;
+                tail.position += 1;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+impl<M: MediaSource> Seek for TrackedSource<M> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let mut tail = self.tail.lock().unwrap_or_else(|e| e.into_inner());
+        let pos = match pos {
+            SeekFrom::Current(offset) => {
+                SeekFrom::Start(tail.position.saturating_add_signed(offset))
+            }
+            pos => pos,
+        };
+        self.inner_position = self.inner.seek(pos)?;
+        tail.position = self.inner_position;
+        Ok(tail.position)
+    }
+}
+
+impl<M: MediaSource> MediaSource for TrackedSource<M> {
+    fn is_seekable(&self) -> bool {
+        self.inner.is_seekable()
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.inner.byte_len()
+    }
+}
+
+/// Whether `bytes` after the last packet are only tags or padding: ID3v1, APEv2 or zeros
+fn is_trailer(mut bytes: &[u8]) -> bool {
+    loop {
+        let len = bytes.len();
+        if len >= 128 && bytes[len - 128..].starts_with(b"TAG") {
+            bytes = &bytes[..len - 128];
+        } else if len >= 32 && bytes[len - 32..].starts_with(b"APETAGEX") {
+            let footer = &bytes[len - 32..];
+            let size =
+                u32::from_le_bytes([footer[12], footer[13], footer[14], footer[15]]) as usize;
+            let flags = u32::from_le_bytes([footer[20], footer[21], footer[22], footer[23]]);
+            // The size covers the items and footer; a header is flagged separately
+            let size = size + if flags & 0x8000_0000 != 0 { 32 } else { 0 };
+            if size < 32 || size > len {
+                return false;
+            }
+            bytes = &bytes[..len - size];
+        } else {
+            return bytes.iter().all(|b| *b == 0);
+        }
+    }
+}
+
+/// Stream layout and length of a fully decoded file
+#[derive(Debug, Clone, Copy)]
+pub struct DecodeSummary {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Frames (samples per channel) actually decoded
+    pub frames: u64,
+    /// The data stops partway through a frame, so the file was cut off
+    pub truncated: bool,
+}
+
+impl DecodeSummary {
+    pub fn duration(&self) -> Duration {
+        if self.sample_rate == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(self.frames as f64 / self.sample_rate as f64)
+    }
+}
+
+/// Decode `path` from start to end, passing interleaved samples at full scale `1.0` to
+/// `on_chunk` along with the channel count and sample rate. Lossy formats can go past
+/// full scale; those peaks are kept rather than clipped.
+///
+/// Decoder panics on corrupt data are reported as errors instead of taking down the caller.
+pub fn decode_file<F>(path: &Path, mut on_chunk: F) -> Result<DecodeSummary, String>
+where
+    F: FnMut(&[f32], u16, u32),
+{
+    let file = File::open(path).map_err(|e| format!("Error opening file: {}", e))?;
+    if file.metadata().map(|m| m.len()).unwrap_or(0) == 0 {
+        return Err("File is empty".to_string());
+    }
+
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let extension = path.extension().and_then(|e| e.to_str());
+        let mut decoder = AudioDecoder::<f32>::open(file, extension)?;
+        let channels = decoder.channels().max(1);
+        let sample_rate = decoder.sample_rate();
+
+        let chunk_len = CHUNK_FRAMES * channels as usize;
+        let mut chunk = Vec::with_capacity(chunk_len);
+        let mut samples: u64 = 0;
+        for sample in decoder.by_ref() {
+            chunk.push(sample);
+            if chunk.len() == chunk_len {
+                on_chunk(&chunk, channels, sample_rate);
+                samples += chunk.len() as u64;
+                chunk.clear();
+            }
+        }
+        // Drop a trailing partial frame so every chunk holds whole frames
+        chunk.truncate(chunk.len() - chunk.len() % channels as usize);
+        if !chunk.is_empty() {
+            on_chunk(&chunk, channels, sample_rate);
+            samples += chunk.len() as u64;
+        }
+
+        let truncated = match decoder.error() {
+            Some(TRUNCATED) => true,
+            Some(e) => return Err(e.to_string()),
+            None => false,
+        };
+        Ok(DecodeSummary {
+            sample_rate,
+            channels,
+            frames: samples / channels as u64,
+            truncated,
+        })
+    }));
+
+    match result {
+        Ok(summary) => summary,
+        Err(_) => Err("Decoder crashed on corrupt audio data".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 8000;
+
+    /// Mono 32-bit float WAV holding `samples`
+    fn float_wav(path: &Path, samples: &[f32]) {
+        let data_len = samples.len() as u32 * 4;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&3u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&RATE.to_le_bytes());
+        wav.extend_from_slice(&(RATE * 4).to_le_bytes());
+        wav.extend_from_slice(&4u16.to_le_bytes());
+        wav.extend_from_slice(&32u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        wav.extend(samples.iter().flat_map(|s| s.to_le_bytes()));
+        std::fs::write(path, wav).unwrap();
+    }
+
+    /// Silent CBR MP3 (MPEG-1 layer III, 128 kbps, 44.1 kHz mono) with no Xing frame
+    fn silent_mp3(frames: usize) -> Vec<u8> {
+        const FRAME_LEN: usize = 417;
+        let mut frame = vec![0u8; FRAME_LEN];
+        frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0xC4]);
+        frame.repeat(frames)
+    }
+
+    #[test]
+    fn decode_file_keeps_peaks_over_full_scale() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("loud.wav");
+        let samples: Vec<f32> = (0..RATE)
+            .map(|n| if n == 100 { 1.5 } else { 0.25 })
+            .collect();
+        float_wav(&path, &samples);
+
+        let mut decoded = Vec::new();
+        let summary = decode_file(&path, |chunk, _, _| decoded.extend_from_slice(chunk)).unwrap();
+        assert_eq!(summary.frames, RATE as u64);
+        assert_eq!(decoded, samples);
+    }
+
+    #[test]
+    fn complete_mp3_is_not_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("complete.mp3");
+        std::fs::write(&path, silent_mp3(40)).unwrap();
+        let summary = decode_file(&path, |_, _, _| {}).unwrap();
+        assert!(!summary.truncated);
+        assert!(summary.frames > 0);
+    }
+
+    #[test]
+    fn trailing_tags_and_padding_are_not_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tagged.mp3");
+        let mut data = silent_mp3(40);
+        data.extend_from_slice(&[0; 100]);
+        let mut id3v1 = vec![0u8; 128];
+        id3v1[..3].copy_from_slice(b"TAG");
+        id3v1[3..8].copy_from_slice(b"Title");
+        data.extend(id3v1);
+        std::fs::write(&path, data).unwrap();
+        assert!(!decode_file(&path, |_, _, _| {}).unwrap().truncated);
+    }
+
+    #[test]
+    fn mp3_cut_off_mid_frame_is_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cut.mp3");
+        let complete = silent_mp3(40);
+        std::fs::write(&path, &complete[..complete.len() - 217]).unwrap();
+        let summary = decode_file(&path, |_, _, _| {}).unwrap();
+        assert!(summary.truncated);
+
+        let mut decoder =
+            AudioDecoder::<i16>::open(File::open(&path).unwrap(), Some("mp3")).unwrap();
+        assert!(decoder.by_ref().count() > 0);
+        assert_eq!(decoder.error(), Some(TRUNCATED));
+    }
+}
@@ -0,0 +1,306 @@
+// Audio Output Utility
+// Where the player sends decoded audio: the sound device, nowhere, or a recording
+// that can be inspected or saved as WAV on machines without audio hardware
+
+use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Samples pulled from a track at a time by the headless backends
+const PULL_SAMPLES: usize = 1024;
+/// How long a paused headless track sleeps between checks
+const PAUSE_POLL: Duration = Duration::from_millis(5);
+
+/// Decoded audio handed to an output
+pub type OutputSource = Box<dyn Source<Item = i16> + Send>;
+
+/// A destination for decoded audio. Lives on the player thread, so it need not be `Send`.
+pub trait AudioOutput {
+    /// Start playing `source`; it stops when the returned track is dropped
+    fn play(
+        &mut self,
+        source: OutputSource,
+        volume: f32,
+        paused: bool,
+    ) -> Result<Box<dyn OutputTrack>, String>;
+
+    /// Whether the output consumes audio at playback speed. Other outputs take samples as
+    /// fast as they are decoded and wait for data instead of inserting silence.
+    fn is_realtime(&self) -> bool;
+}
+
+/// A track playing on an output
+pub trait OutputTrack {
+    fn pause(&self);
+    fn resume(&self);
+    fn set_volume(&self, volume: f32);
+    /// The source has run out
+    fn is_finished(&self) -> bool;
+}
+
+/// Which output a player uses; opened on the player thread
+#[derive(Debug, Clone, Default)]
+pub enum OutputBackend {
+    /// The default sound device
+    #[default]
+    Device,
+    /// Discard the audio
+    Null,
+    /// Keep the audio in a recording
+    Recording(Recording),
+}
+
+impl OutputBackend {
+    pub fn open(self) -> Box<dyn AudioOutput> {
+        match self {
+            OutputBackend::Device => Box::<DeviceOutput>::default(),
+            OutputBackend::Null => Box::new(HeadlessOutput { recording: None }),
+            OutputBackend::Recording(recording) => Box::new(HeadlessOutput {
+                recording: Some(recording),
+            }),
+        }
+    }
+}
+
+/// The default sound device, opened on first use
+#[derive(Default)]
+pub struct DeviceOutput {
+    stream: Option<(OutputStream, OutputStreamHandle)>,
+}
+
+impl AudioOutput for DeviceOutput {
+    fn play(
+        &mut self,
+        source: OutputSource,
+        volume: f32,
+        paused: bool,
+    ) -> Result<Box<dyn OutputTrack>, String> {
+        if self.stream.is_none() {
+            let stream = OutputStream::try_default()
+                .map_err(|e| format!("No audio output device: {}", e))?;
+            self.stream = Some(stream);
+        }
+        let Some((_, handle)) = &self.stream else {
+            return Err("No audio output device".to_string());
+        };
+        let sink = match Sink::try_new(handle) {
+            Ok(sink) => sink,
+            Err(e) => {
+                // The device may have gone away; open it again next time
+                self.stream = None;
+                return Err(format!("Audio output unavailable: {}", e));
+            }
+        };
+        sink.set_volume(volume);
+        if paused {
+            sink.pause();
+        }
+        sink.append(source);
+        Ok(Box::new(sink))
+    }
+
+    fn is_realtime(&self) -> bool {
+        true
+    }
+}
+
+impl OutputTrack for Sink {
+    fn pause(&self) {
+        Sink::pause(self);
+    }
+
+    fn resume(&self) {
+        self.play();
+    }
+
+    fn set_volume(&self, volume: f32) {
+        Sink::set_volume(self, volume);
+    }
+
+    fn is_finished(&self) -> bool {
+        self.empty()
+    }
+}
+
+/// Samples captured by a headless output, with the format of the last track
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    audio: Arc<Mutex<RecordedAudio>>,
+}
+
+#[derive(Debug, Default)]
+struct RecordedAudio {
+    samples: Vec<i16>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Interleaved samples recorded so far, after volume
+    pub fn samples(&self) -> Vec<i16> {
+        self.lock().samples.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.lock().channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.lock().sample_rate
+    }
+
+    pub fn clear(&self) {
+        self.lock().samples.clear();
+    }
+
+    /// Write the recording as a 16-bit PCM WAV file
+    pub fn save_wav(&self, path: &Path) -> Result<(), String> {
+        let audio = self.lock();
+        let channels = audio.channels.max(1);
+        let sample_rate = audio.sample_rate.max(1);
+        let data_len = (audio.samples.len() * 2) as u32;
+        let block_align = channels * 2;
+
+        let file = File::create(path).map_err(|e| format!("Failed to create WAV file: {}", e))?;
+        let mut out = BufWriter::new(file);
+        let mut header = Vec::with_capacity(44);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&(36 + data_len).to_le_bytes());
+        header.extend_from_slice(b"WAVEfmt ");
+        header.extend_from_slice(&16u32.to_le_bytes());
+        header.extend_from_slice(&1u16.to_le_bytes());
+        header.extend_from_slice(&channels.to_le_bytes());
+        header.extend_from_slice(&sample_rate.to_le_bytes());
+        header.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+        header.extend_from_slice(&block_align.to_le_bytes());
+        header.extend_from_slice(&16u16.to_le_bytes());
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&data_len.to_le_bytes());
+
+        let data: Vec<u8> = audio.samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        out.write_all(&header)
+            .and_then(|_| out.write_all(&data))
+            .and_then(|_| out.flush())
+            .map_err(|e| format!("Failed to write WAV file: {}", e))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RecordedAudio> {
+        self.audio.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Pulls tracks on a thread of their own as fast as they decode, optionally recording them
+struct HeadlessOutput {
+    recording: Option<Recording>,
+}
+
+impl AudioOutput for HeadlessOutput {
+    fn play(
+        &mut self,
+        mut source: OutputSource,
+        volume: f32,
+        paused: bool,
+    ) -> Result<Box<dyn OutputTrack>, String> {
+        let track = HeadlessTrack {
+            paused: Arc::new(AtomicBool::new(paused)),
+            volume: Arc::new(AtomicU32::new(volume.to_bits())),
+            stopped: Arc::new(AtomicBool::new(false)),
+            finished: Arc::new(AtomicBool::new(false)),
+        };
+        if let Some(recording) = &self.recording {
+            let mut audio = recording.lock();
+            audio.channels = source.channels();
+            audio.sample_rate = source.sample_rate();
+        }
+
+        let recording = self.recording.clone();
+        let (paused, volume, stopped, finished) = (
+            track.paused.clone(),
+            track.volume.clone(),
+            track.stopped.clone(),
+            track.finished.clone(),
+        );
+        thread::spawn(move || {
+            let mut block = Vec::with_capacity(PULL_SAMPLES);
+            while !stopped.load(Ordering::Relaxed) {
+                if paused.load(Ordering::Relaxed) {
+                    thread::sleep(PAUSE_POLL);
+                    continue;
+                }
+                let gain = f32::from_bits(volume.load(Ordering::Relaxed));
+                block.extend(
+                    source
+                        .by_ref()
+                        .take(PULL_SAMPLES)
+                        .map(|s| (s as f32 * gain) as i16),
+                );
+                // A pull waiting on the decoder can outlast the track being dropped
+                if stopped.load(Ordering::Relaxed) {
+                    break;
+                }
+                let done = block.len() < PULL_SAMPLES;
+                if let Some(recording) = &recording {
+                    recording.lock().samples.append(&mut block);
+                }
+                block.clear();
+                if done {
+                    break;
+                }
+            }
+            finished.store(true, Ordering::Relaxed);
+        });
+        Ok(Box::new(track))
+    }
+
+    fn is_realtime(&self) -> bool {
+        false
+    }
+}
+
+struct HeadlessTrack {
+    paused: Arc<AtomicBool>,
+    volume: Arc<AtomicU32>,
+    stopped: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+}
+
+impl OutputTrack for HeadlessTrack {
+    fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    fn set_volume(&self, volume: f32) {
+        self.volume.store(volume.to_bits(), Ordering::Relaxed);
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for HeadlessTrack {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
@@ -1,132 +1,1179 @@
-use anyhow::Result;
-use rodio::{Decoder, OutputStream, Sink};
-use std::io::Cursor;
-use std::sync::{Arc, Mutex};
+// Audio Player Utility
+// Plays local files and URLs on a background thread with pause, seek and volume,
+// publishing state changes to subscribers as events
 
-static AUDIO_SINK: Mutex<Option<Arc<Mutex<Sink>>>> = Mutex::new(None);
+use crate::utils::audio_decode::AudioDecoder;
+use crate::utils::audio_output::{AudioOutput, OutputBackend, OutputTrack};
+use crate::utils::http_stream::{BufferStatus, HttpStream, StreamProgress};
+use rodio::Source;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TryRecvError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use symphonia::core::io::MediaSource;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 
-pub fn stop_audio() {
-    if let Ok(mut sink_guard) = AUDIO_SINK.lock() {
-        if let Some(sink) = sink_guard.take() {
-            if let Ok(sink) = sink.lock() {
-                sink.stop();
-                sink.clear();
-            }
+/// How often the player thread checks for the end of a track
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How often position updates are published while playing
+const STATUS_INTERVAL: Duration = Duration::from_millis(250);
+/// Samples per block handed from the decoding thread to the output
+const BLOCK_SAMPLES: usize = 4096;
+/// Decoded blocks queued ahead of the output (about a second of stereo 44.1 kHz)
+const QUEUED_BLOCKS: usize = 24;
+/// How often a chain checks whether to start fading into the next track
+const CHAIN_CHECK_SAMPLES: usize = 1024;
+
+/// What the player plays
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlayerSource {
+    File(PathBuf),
+    Url(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlaybackState {
+    #[default]
+    Stopped,
+    /// Opening, buffering the first data or seeking
+    Loading,
+    Playing,
+    Paused,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerStatus {
+    pub state: PlaybackState,
+    /// Current or last played source
+    pub source: Option<PlayerSource>,
+    pub position: Duration,
+    pub duration: Option<Duration>,
+    pub volume: f32,
+    /// Buffer of a streamed URL
+    pub buffer: Option<BufferStatus>,
+}
+
+impl Default for PlayerStatus {
+    fn default() -> Self {
+        Self {
+            state: PlaybackState::Stopped,
+            source: None,
+            position: Duration::ZERO,
+            duration: None,
+            volume: 1.0,
+            buffer: None,
         }
     }
 }
 
-pub fn play_streaming_url_async(url: String) {
-    // Stop any currently playing audio
-    stop_audio();
+impl PlayerStatus {
+    /// Whether `source` is loaded, playing or paused
+    pub fn is_active(&self, source: &PlayerSource) -> bool {
+        self.state != PlaybackState::Stopped && self.source.as_ref() == Some(source)
+    }
+}
 
-    // Use a detached std::thread that won't prevent app shutdown
-    // The thread will be terminated when the process exits
-    let _handle = std::thread::spawn(move || {
-        // Create a minimal tokio runtime just for this thread to do HTTP requests
-        let rt = match tokio::runtime::Runtime::new() {
-            Ok(rt) => rt,
-            Err(e) => {
-                eprintln!("Failed to create runtime for audio: {}", e);
-                return;
+#[derive(Debug, Clone)]
+pub enum PlayerEvent {
+    /// Published on every change, and periodically while playing
+    Status(PlayerStatus),
+    /// A track started playing; not sent again when seeking within it
+    Started(PlayerSource),
+    /// A track played to its end
+    Finished(PlayerSource),
+    /// A track could not be opened, decoded or played
+    Failed(PlayerSource, String),
+}
+
+enum Request {
+    Play(PlayerSource, Duration),
+    QueueNext(Option<PlayerSource>),
+    Pause,
+    Resume,
+    Seek(Duration),
+    SetVolume(f32),
+    SetCrossfade(Duration),
+    Stop,
+    /// The player was dropped
+    Shutdown,
+    /// A source finished opening on its decoding thread
+    Loaded {
+        generation: u64,
+        result: Result<Feed, String>,
+    },
+    /// The next track finished opening
+    Preloaded {
+        generation: u64,
+        result: Result<Feed, String>,
+    },
+}
+
+/// A player with its own thread and output. The app shares one on the sound device
+/// through the free functions below; others can play headless, e.g. into a recording.
+pub struct AudioPlayer {
+    requests: Mutex<Sender<Request>>,
+    status: Arc<Mutex<PlayerStatus>>,
+    subscribers: Arc<Mutex<Vec<UnboundedSender<PlayerEvent>>>>,
+}
+
+impl AudioPlayer {
+    pub fn new(backend: OutputBackend) -> Self {
+        let (requests, receiver) = mpsc::channel();
+        let status = Arc::new(Mutex::new(PlayerStatus::default()));
+        let subscribers = Arc::new(Mutex::new(Vec::new()));
+        let (loaded, thread_status, thread_subscribers) =
+            (requests.clone(), status.clone(), subscribers.clone());
+        // Device streams cannot leave the thread they are opened on, so build everything there
+        thread::spawn(move || {
+            PlayerThread {
+                requests: loaded,
+                status: thread_status,
+                subscribers: thread_subscribers,
+                output: backend.open(),
+                current: None,
+                generation: 0,
+                paused: false,
+                announce_start: false,
+                last_published: Instant::now(),
+                next: None,
+                next_generation: 0,
+                preloading: false,
+                preloaded: None,
+                upcoming: None,
+                crossfade: Duration::ZERO,
             }
-        };
+            .run(receiver)
+        });
+        Self {
+            requests: Mutex::new(requests),
+            status,
+            subscribers,
+        }
+    }
 
-        let response = match rt.block_on(reqwest::get(&url)) {
-            Ok(resp) => resp,
-            Err(e) => {
-                eprintln!("Failed to fetch audio URL: {}", e);
-                return;
+    fn send(&self, request: Request) {
+        if let Ok(requests) = self.requests.lock() {
+            let _ = requests.send(request);
+        }
+    }
+
+    /// Play a local file from `start`, replacing whatever is playing
+    pub fn play_file(&self, path: PathBuf, start: Duration) {
+        self.send(Request::Play(PlayerSource::File(path), start));
+    }
+
+    /// Stream a URL: playback starts once the first chunk has arrived while the rest is
+    /// fetched in the background
+    pub fn play_url(&self, url: String) {
+        self.send(Request::Play(PlayerSource::Url(url), Duration::ZERO));
+    }
+
+    /// Continue with `source` when the current track ends, without a gap when both share
+    /// a format; `None` cancels. Kept across seeks, dropped by play and stop.
+    pub fn queue_next(&self, source: Option<PlayerSource>) {
+        self.send(Request::QueueNext(source));
+    }
+
+    pub fn pause(&self) {
+        self.send(Request::Pause);
+    }
+
+    pub fn resume(&self) {
+        self.send(Request::Resume);
+    }
+
+    pub fn seek(&self, position: Duration) {
+        self.send(Request::Seek(position));
+    }
+
+    /// Volume from 0.0 (muted) to 1.0 (full)
+    pub fn set_volume(&self, volume: f32) {
+        self.send(Request::SetVolume(volume.clamp(0.0, 1.0)));
+    }
+
+    /// Overlap consecutive tracks by `length`; zero plays them back to back
+    pub fn set_crossfade(&self, length: Duration) {
+        self.send(Request::SetCrossfade(length));
+    }
+
+    pub fn stop(&self) {
+        self.send(Request::Stop);
+    }
+
+    pub fn status(&self) -> PlayerStatus {
+        self.status.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// Position in the current track, or `None` when nothing is playing
+    pub fn playback_position(&self) -> Option<Duration> {
+        let status = self.status();
+        matches!(status.state, PlaybackState::Playing | PlaybackState::Paused)
+            .then_some(status.position)
+    }
+
+    /// Receive player events from now on
+    pub fn subscribe(&self) -> UnboundedReceiver<PlayerEvent> {
+        let (tx, rx) = unbounded_channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(tx);
+        }
+        rx
+    }
+}
+
+impl Drop for AudioPlayer {
+    fn drop(&mut self) {
+        self.send(Request::Shutdown);
+    }
+}
+
+static PLAYER: OnceLock<AudioPlayer> = OnceLock::new();
+
+fn player() -> &'static AudioPlayer {
+    PLAYER.get_or_init(|| AudioPlayer::new(OutputBackend::Device))
+}
+
+pub fn play_file(path: PathBuf, start: Duration) {
+    player().play_file(path, start);
+}
+
+pub fn play_url(url: String) {
+    player().play_url(url);
+}
+
+pub fn queue_next(source: Option<PlayerSource>) {
+    player().queue_next(source);
+}
+
+pub fn pause() {
+    player().pause();
+}
+
+pub fn resume() {
+    player().resume();
+}
+
+pub fn seek(position: Duration) {
+    player().seek(position);
+}
+
+pub fn set_volume(volume: f32) {
+    player().set_volume(volume);
+}
+
+pub fn set_crossfade(length: Duration) {
+    player().set_crossfade(length);
+}
+
+pub fn stop_audio() {
+    // Nothing to stop before the player has been used
+    if let Some(player) = PLAYER.get() {
+        player.stop();
+    }
+}
+
+pub fn status() -> PlayerStatus {
+    player().status()
+}
+
+pub fn playback_position() -> Option<Duration> {
+    player().playback_position()
+}
+
+pub fn subscribe() -> UnboundedReceiver<PlayerEvent> {
+    player().subscribe()
+}
+
+/// Counters of a track whose feed has been handed to the output
+struct TrackInfo {
+    source: PlayerSource,
+    start: Duration,
+    samples: Arc<AtomicU64>,
+    underrun: Arc<AtomicBool>,
+    samples_per_second: u64,
+    duration: Option<Duration>,
+    stream: Option<StreamProgress>,
+}
+
+impl TrackInfo {
+    fn of(feed: &Feed, source: PlayerSource, start: Duration) -> Self {
+        Self {
+            source,
+            start,
+            samples: feed.samples.clone(),
+            underrun: feed.underrun.clone(),
+            samples_per_second: (feed.sample_rate as u64 * feed.channels as u64).max(1),
+            duration: feed.duration,
+            stream: feed.stream.clone(),
+        }
+    }
+
+    fn position(&self) -> Duration {
+        let played = self.samples.load(Ordering::Relaxed);
+        self.start + Duration::from_secs_f64(played as f64 / self.samples_per_second as f64)
+    }
+
+    fn buffer(&self) -> Option<BufferStatus> {
+        self.stream.as_ref().map(|stream| {
+            let mut status = stream.status();
+            status.buffering |= self.underrun.swap(false, Ordering::Relaxed);
+            status
+        })
+    }
+}
+
+/// The output track playing and the track its chain is on
+struct Current {
+    track: Box<dyn OutputTrack>,
+    chain: Arc<ChainShared>,
+    /// Channels and sample rate of the chain; following tracks must match to join it
+    format: (u16, u32),
+    info: TrackInfo,
+}
+
+struct PlayerThread {
+    requests: Sender<Request>,
+    status: Arc<Mutex<PlayerStatus>>,
+    subscribers: Arc<Mutex<Vec<UnboundedSender<PlayerEvent>>>>,
+    output: Box<dyn AudioOutput>,
+    current: Option<Current>,
+    /// Bumped on every play, seek and stop so late loads are ignored
+    generation: u64,
+    paused: bool,
+    /// Send `Started` once the loading track plays; seeks reload without it
+    announce_start: bool,
+    last_published: Instant,
+    /// Track to play once the current one ends
+    next: Option<PlayerSource>,
+    /// Bumped whenever `next` changes so late preloads are ignored
+    next_generation: u64,
+    preloading: bool,
+    /// `next`, opened ahead of time but not handed to the output yet
+    preloaded: Option<Feed>,
+    /// `next`, waiting in the chain of the current track
+    upcoming: Option<TrackInfo>,
+    crossfade: Duration,
+}
+
+impl PlayerThread {
+    fn run(&mut self, receiver: Receiver<Request>) {
+        loop {
+            match receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(Request::Shutdown) => return,
+                Ok(request) => self.handle(request),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
             }
-        };
+            self.poll();
+        }
+    }
 
-        if !response.status().is_success() {
-            eprintln!("Failed to fetch audio: HTTP {}", response.status());
-            return;
+    fn handle(&mut self, request: Request) {
+        match request {
+            Request::Play(source, start) => {
+                self.paused = false;
+                self.announce_start = true;
+                self.set_next(None);
+                self.load(source, start);
+            }
+            Request::QueueNext(source) => {
+                if source != self.next {
+                    self.set_next(source);
+                    self.preload();
+                }
+            }
+            Request::Pause => {
+                self.paused = true;
+                if let Some(current) = &self.current {
+                    current.track.pause();
+                    self.update(|s| s.state = PlaybackState::Paused);
+                }
+            }
+            Request::Resume => {
+                self.paused = false;
+                if let Some(current) = &self.current {
+                    current.track.resume();
+                    self.update(|s| s.state = PlaybackState::Playing);
+                }
+            }
+            // Reopened at `position`; the decoder seeks the file or stream by byte offset
+            Request::Seek(position) => {
+                let source = self.lock_status().source.clone();
+                if let Some(source) = source.filter(|_| self.current.is_some()) {
+                    self.load(source, position);
+                }
+            }
+            Request::SetVolume(volume) => {
+                if let Some(current) = &self.current {
+                    current.track.set_volume(volume);
+                }
+                self.update(|s| s.volume = volume);
+            }
+            Request::SetCrossfade(length) => {
+                self.crossfade = length;
+                if let Some(current) = &self.current {
+                    current
+                        .chain
+                        .crossfade
+                        .store(crossfade_samples(length, current.format), Ordering::Relaxed);
+                }
+            }
+            Request::Stop => {
+                self.generation += 1;
+                self.set_next(None);
+                self.current = None;
+                self.update(|s| {
+                    s.state = PlaybackState::Stopped;
+                    s.position = Duration::ZERO;
+                    s.buffer = None;
+                });
+            }
+            Request::Loaded { generation, result } => {
+                if generation == self.generation {
+                    self.start(result);
+                }
+            }
+            Request::Preloaded { generation, result } => {
+                if generation != self.next_generation {
+                    return;
+                }
+                self.preloading = false;
+                match result {
+                    Ok(feed) => {
+                        self.preloaded = Some(feed);
+                        self.join_chain();
+                    }
+                    Err(e) => {
+                        if let Some(source) = self.next.take() {
+                            self.emit(PlayerEvent::Failed(source, e));
+                        }
+                    }
+                }
+                self.sync_pending();
+            }
+            Request::Shutdown => {}
         }
+    }
 
-        let bytes = match rt.block_on(response.bytes()) {
-            Ok(bytes) => bytes,
-            Err(e) => {
-                eprintln!("Failed to read audio data: {}", e);
-                return;
+    /// Forget the next track, taking it back out of the chain if it was handed over
+    fn set_next(&mut self, source: Option<PlayerSource>) {
+        self.next_generation += 1;
+        self.preloading = false;
+        self.preloaded = None;
+        self.upcoming = None;
+        if let Some(current) = &self.current {
+            if let Ok(mut next) = current.chain.next.lock() {
+                *next = None;
             }
-        };
+        }
+        self.next = source;
+        self.sync_pending();
+    }
 
-        // Now do the audio playback (blocking operation)
+    /// Open the next track on a decoding thread; it reports back with `Request::Preloaded`
+    fn preload(&mut self) {
+        let Some(source) = self.next.clone() else {
+            return;
+        };
+        if self.current.is_none()
+            || self.preloading
+            || self.preloaded.is_some()
+            || self.upcoming.is_some()
         {
-            let (_stream, stream_handle) = match OutputStream::try_default() {
-                Ok(stream) => stream,
+            return;
+        }
+        self.preloading = true;
+        let generation = self.next_generation;
+        let lookahead = self.crossfade;
+        let requests = self.requests.clone();
+        thread::spawn(
+            move || match open_feed(&source, Duration::ZERO, lookahead) {
+                Ok((feed, decode)) => {
+                    let _ = requests.send(Request::Preloaded {
+                        generation,
+                        result: Ok(feed),
+                    });
+                    decode();
+                }
                 Err(e) => {
-                    eprintln!("Failed to create audio output stream: {}", e);
-                    return;
+                    let _ = requests.send(Request::Preloaded {
+                        generation,
+                        result: Err(e),
+                    });
                 }
-            };
+            },
+        );
+    }
+
+    /// Queue the preloaded track behind the current one when their formats match
+    fn join_chain(&mut self) {
+        let (Some(current), Some(source)) = (&self.current, &self.next) else {
+            return;
+        };
+        let Some(mut feed) = self
+            .preloaded
+            .take_if(|feed| (feed.channels, feed.sample_rate) == current.format)
+        else {
+            return;
+        };
+        feed.wait_for_data = !self.output.is_realtime();
+        let info = TrackInfo::of(&feed, source.clone(), Duration::ZERO);
+        if let Ok(mut next) = current.chain.next.lock() {
+            *next = Some(feed);
+            self.upcoming = Some(info);
+        }
+    }
+
+    /// Tell the chain whether a next track is on its way into it
+    fn sync_pending(&self) {
+        if let Some(current) = &self.current {
+            let pending = self.next.is_some() && self.preloaded.is_none();
+            current.chain.pending.store(pending, Ordering::Relaxed);
+        }
+    }
+
+    /// Open `source` on a decoding thread; it reports back with `Request::Loaded`
+    fn load(&mut self, source: PlayerSource, start: Duration) {
+        self.generation += 1;
+        self.drop_current();
+        self.update(|s| {
+            s.state = PlaybackState::Loading;
+            s.source = Some(source.clone());
+            s.position = start;
+            s.buffer = None;
+        });
 
-            let sink = match Sink::try_new(&stream_handle) {
-                Ok(sink) => sink,
+        let generation = self.generation;
+        let lookahead = self.crossfade;
+        let requests = self.requests.clone();
+        thread::spawn(move || {
+            let (feed, decode) = match open_feed(&source, start, lookahead) {
+                Ok(opened) => opened,
                 Err(e) => {
-                    eprintln!("Failed to create audio sink: {}", e);
+                    let _ = requests.send(Request::Loaded {
+                        generation,
+                        result: Err(e),
+                    });
                     return;
                 }
             };
+            let _ = requests.send(Request::Loaded {
+                generation,
+                result: Ok(feed),
+            });
+            decode();
+        });
+    }
 
-            // Store the sink so we can stop it
-            let sink_arc = Arc::new(Mutex::new(sink));
-            if let Ok(mut sink_guard) = AUDIO_SINK.lock() {
-                *sink_guard = Some(sink_arc.clone());
+    /// Stop the output, keeping a next track that was already handed to it
+    fn drop_current(&mut self) {
+        if let Some(current) = self.current.take() {
+            if let Some(feed) = current.chain.next.lock().ok().and_then(|mut n| n.take()) {
+                self.preloaded = Some(feed);
+                self.upcoming = None;
             }
+        }
+    }
 
-            let cursor = Cursor::new(bytes.to_vec());
-            let source = match Decoder::new(cursor) {
-                Ok(source) => source,
-                Err(e) => {
-                    eprintln!("Failed to decode audio: {}", e);
-                    if let Ok(mut sink_guard) = AUDIO_SINK.lock() {
-                        sink_guard.take();
+    fn start(&mut self, result: Result<Feed, String>) {
+        let mut feed = match result {
+            Ok(feed) => feed,
+            Err(e) => return self.fail(e),
+        };
+        feed.wait_for_data = !self.output.is_realtime();
+
+        let (volume, start, source) = {
+            let status = self.lock_status();
+            (status.volume, status.position, status.source.clone())
+        };
+        let Some(source) = source else {
+            return;
+        };
+        let info = TrackInfo::of(&feed, source, start);
+        let format = (feed.channels, feed.sample_rate);
+        let chain = Arc::new(ChainShared {
+            next: Mutex::new(None),
+            advanced: AtomicBool::new(false),
+            pending: AtomicBool::new(false),
+            crossfade: AtomicU64::new(crossfade_samples(self.crossfade, format)),
+        });
+        let output = Chain {
+            current: feed,
+            fade: None,
+            shared: chain.clone(),
+            until_check: 0,
+        };
+        let track = match self.output.play(Box::new(output), volume, self.paused) {
+            Ok(track) => track,
+            Err(e) => return self.fail(e),
+        };
+        let duration = info.duration;
+        self.current = Some(Current {
+            track,
+            chain,
+            format,
+            info,
+        });
+        self.join_chain();
+        self.preload();
+        self.sync_pending();
+
+        let paused = self.paused;
+        self.update(|s| {
+            s.state = if paused {
+                PlaybackState::Paused
+            } else {
+                PlaybackState::Playing
+            };
+            s.duration = duration;
+        });
+        if std::mem::take(&mut self.announce_start) {
+            if let Some(source) = self.lock_status().source.clone() {
+                self.emit(PlayerEvent::Started(source));
+            }
+        }
+    }
+
+    fn fail(&mut self, reason: String) {
+        self.current = None;
+        self.announce_start = false;
+        self.update(|s| s.state = PlaybackState::Stopped);
+        if let Some(source) = self.lock_status().source.clone() {
+            self.emit(PlayerEvent::Failed(source, reason));
+        }
+    }
+
+    /// Track the position, follow the chain onto the next track, notice the end of the
+    /// last one and publish progress
+    fn poll(&mut self) {
+        let Some(current) = &mut self.current else {
+            return;
+        };
+
+        if current.chain.advanced.swap(false, Ordering::Relaxed) {
+            if let Some(info) = self.upcoming.take() {
+                let finished = std::mem::replace(&mut current.info, info);
+                self.next = None;
+                self.next_generation += 1;
+                current.chain.pending.store(false, Ordering::Relaxed);
+                let (source, duration) = (current.info.source.clone(), current.info.duration);
+                self.update(|s| {
+                    s.source = Some(source.clone());
+                    s.position = Duration::ZERO;
+                    s.duration = duration;
+                    s.buffer = None;
+                });
+                self.emit(PlayerEvent::Finished(finished.source));
+                self.emit(PlayerEvent::Started(source));
+            }
+            return;
+        }
+
+        let position = current.info.position();
+        let buffer = current.info.buffer();
+        if current.track.is_finished() {
+            // A dropped connection also ends the decoder early
+            let error = current
+                .info
+                .stream
+                .as_ref()
+                .and_then(|stream| stream.error());
+            self.current = None;
+            self.update(|s| {
+                s.state = PlaybackState::Stopped;
+                s.position = position;
+                s.buffer = buffer;
+            });
+            if let Some(source) = self.lock_status().source.clone() {
+                self.emit(match error {
+                    Some(e) => PlayerEvent::Failed(source, e),
+                    None => PlayerEvent::Finished(source),
+                });
+            }
+            // The next track could not join the chain, or was not ready in time
+            if let Some(next) = self.next.take() {
+                let preloaded = self.preloaded.take();
+                self.set_next(None);
+                self.announce_start = true;
+                match preloaded {
+                    Some(feed) => {
+                        self.generation += 1;
+                        self.update(|s| {
+                            s.source = Some(next);
+                            s.position = Duration::ZERO;
+                        });
+                        self.start(Ok(feed));
                     }
-                    return;
+                    None => self.load(next, Duration::ZERO),
                 }
-            };
+            }
+            return;
+        }
 
-            // Append source to sink
-            {
-                let sink = sink_arc.lock().unwrap();
-                sink.append(source);
+        let mut status = self.lock_status();
+        status.position = position;
+        status.buffer = buffer;
+        drop(status);
+        if self.last_published.elapsed() >= STATUS_INTERVAL {
+            self.publish();
+        }
+    }
+
+    fn lock_status(&self) -> std::sync::MutexGuard<'_, PlayerStatus> {
+        self.status.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn update(&mut self, change: impl FnOnce(&mut PlayerStatus)) {
+        change(&mut self.lock_status());
+        self.publish();
+    }
+
+    fn publish(&mut self) {
+        self.last_published = Instant::now();
+        let status = self.lock_status().clone();
+        self.emit(PlayerEvent::Status(status));
+    }
+
+    fn emit(&self, event: PlayerEvent) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+        }
+    }
+}
+
+fn crossfade_samples(length: Duration, (channels, sample_rate): (u16, u32)) -> u64 {
+    (length.as_secs_f64() * sample_rate as f64) as u64 * channels as u64
+}
+
+/// State a chain shares with the player thread
+struct ChainShared {
+    /// Track to continue with, in the chain's format
+    next: Mutex<Option<Feed>>,
+    /// Set when the chain moves on to `next`
+    advanced: AtomicBool,
+    /// A next track is opening and will join the chain
+    pending: AtomicBool,
+    /// Overlap between tracks, in samples
+    crossfade: AtomicU64,
+}
+
+/// What the output plays: the current track, followed by the next one the moment it
+/// runs out, or faded into it over the last `crossfade` samples
+struct Chain {
+    current: Feed,
+    /// The previous track, fading out under the start of `current`
+    fade: Option<Fade>,
+    shared: Arc<ChainShared>,
+    until_check: usize,
+}
+
+struct Fade {
+    outgoing: Feed,
+    length: u64,
+    done: u64,
+}
+
+impl Chain {
+    /// Start fading into the next track once the current one has `crossfade` samples left
+    /// to play. Returns how many samples to play before checking again.
+    fn start_fade_if_due(&mut self) -> usize {
+        let crossfade = self.shared.crossfade.load(Ordering::Relaxed);
+        if crossfade == 0 {
+            return CHAIN_CHECK_SAMPLES;
+        }
+        let remaining = loop {
+            let decoded_all = self.current.decoded_all.load(Ordering::Relaxed);
+            let remaining = self
+                .current
+                .decoded
+                .load(Ordering::Relaxed)
+                .saturating_sub(self.current.samples.load(Ordering::Relaxed));
+            // The end is at least `remaining` samples away, whether or not it is known yet
+            if remaining > crossfade {
+                return (remaining - crossfade).min(CHAIN_CHECK_SAMPLES as u64) as usize;
+            }
+            if decoded_all {
+                break remaining;
+            }
+            if !self.current.wait_for_data {
+                return CHAIN_CHECK_SAMPLES;
+            }
+            thread::sleep(Duration::from_millis(1));
+        };
+        if let Some(next) = self.take_next() {
+            self.fade = Some(Fade {
+                outgoing: std::mem::replace(&mut self.current, next),
+                length: remaining.max(1),
+                done: 0,
+            });
+        }
+        CHAIN_CHECK_SAMPLES
+    }
+
+    /// Move on to the next track if it is ready. Outputs that are not realtime wait for one
+    /// that is still opening, so what they capture does not depend on timing.
+    fn take_next(&mut self) -> Option<Feed> {
+        loop {
+            let next = match self.shared.next.try_lock() {
+                Ok(mut next) => next.take(),
+                Err(_) => None,
+            };
+            if let Some(next) = next {
+                self.shared.advanced.store(true, Ordering::Relaxed);
+                return Some(next);
             }
+            if !self.current.wait_for_data || !self.shared.pending.load(Ordering::Relaxed) {
+                return None;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
 
-            // Wait for playback to finish with shorter sleep intervals for responsiveness
-            loop {
-                std::thread::sleep(std::time::Duration::from_millis(50));
+impl Iterator for Chain {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if self.until_check == 0 {
+            self.until_check = match self.fade {
+                Some(_) => CHAIN_CHECK_SAMPLES,
+                None => self.start_fade_if_due(),
+            };
+        }
+        self.until_check -= 1;
 
-                // Check if playback finished or was stopped
-                let sink = sink_arc.lock().unwrap();
-                if sink.empty() {
-                    drop(sink);
-                    break;
+        if let Some(fade) = &mut self.fade {
+            let incoming = self.current.next().unwrap_or(0);
+            match fade.outgoing.next() {
+                Some(outgoing) if fade.done < fade.length => {
+                    let gain = fade.done as f32 / fade.length as f32;
+                    fade.done += 1;
+                    return Some((outgoing as f32 * (1.0 - gain) + incoming as f32 * gain) as i16);
+                }
+                _ => {
+                    self.fade = None;
+                    return Some(incoming);
                 }
-                drop(sink);
+            }
+        }
 
-                // Check if sink was removed (stopped)
-                if let Ok(sink_guard) = AUDIO_SINK.lock() {
-                    if sink_guard.is_none() {
-                        break;
-                    }
+        match self.current.next() {
+            Some(sample) => Some(sample),
+            None => {
+                self.current = self.take_next()?;
+                self.current.next()
+            }
+        }
+    }
+}
+
+impl Source for Chain {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.current.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.current.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Output side of a track: decoded blocks arrive from the decoding thread, so slow
+/// disks or networks never stall the audio device. Plays silence when it runs dry.
+struct Feed {
+    blocks: Receiver<Vec<i16>>,
+    block: Vec<i16>,
+    index: usize,
+    channels: u16,
+    sample_rate: u32,
+    duration: Option<Duration>,
+    /// Samples actually played, for the position
+    samples: Arc<AtomicU64>,
+    /// Samples decoded so far, and whether that is all of them
+    decoded: Arc<AtomicU64>,
+    decoded_all: Arc<AtomicBool>,
+    underrun: Arc<AtomicBool>,
+    stream: Option<StreamProgress>,
+    /// Block until data arrives instead of playing silence, for outputs that are not realtime
+    wait_for_data: bool,
+}
+
+impl Iterator for Feed {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if self.index >= self.block.len() {
+            let received = if self.wait_for_data {
+                self.blocks.recv().map_err(|_| TryRecvError::Disconnected)
+            } else {
+                self.blocks.try_recv()
+            };
+            match received {
+                Ok(block) => {
+                    self.block = block;
+                    self.index = 0;
                 }
+                Err(TryRecvError::Empty) => {
+                    self.underrun.store(true, Ordering::Relaxed);
+                    return Some(0);
+                }
+                Err(TryRecvError::Disconnected) => return None,
             }
+        }
+        let sample = self.block.get(self.index).copied();
+        self.index += 1;
+        self.samples.fetch_add(1, Ordering::Relaxed);
+        sample
+    }
+}
 
-            // Clean up
-            if let Ok(mut sink_guard) = AUDIO_SINK.lock() {
-                sink_guard.take();
+type DecodeLoop = Box<dyn FnOnce() + Send>;
+
+/// Open and probe `source`, returning the output feed and the loop that fills it. The
+/// feed holds `lookahead` more audio so the end of the track is known that much early.
+fn open_feed(
+    source: &PlayerSource,
+    start: Duration,
+    lookahead: Duration,
+) -> Result<(Feed, DecodeLoop), String> {
+    match source {
+        PlayerSource::File(path) => {
+            let file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+            let extension = path.extension().and_then(|e| e.to_str());
+            decoder_feed(file, extension, start, lookahead, None)
+        }
+        PlayerSource::Url(url) => {
+            let stream = HttpStream::open(url)?;
+            let progress = stream.progress();
+            let path = url::Url::parse(url)
+                .map(|u| u.path().to_string())
+                .unwrap_or_default();
+            let extension = Path::new(&path).extension().and_then(|e| e.to_str());
+            decoder_feed(stream, extension, start, lookahead, Some(progress))
+        }
+    }
+}
+
+fn decoder_feed<R>(
+    data: R,
+    extension: Option<&str>,
+    start: Duration,
+    lookahead: Duration,
+    stream: Option<StreamProgress>,
+) -> Result<(Feed, DecodeLoop), String>
+where
+    R: MediaSource + 'static,
+{
+    let mut decoder = AudioDecoder::<i16>::open(data, extension)?;
+    if !start.is_zero() {
+        decoder.seek(start)?;
+    }
+    let format = (decoder.channels(), decoder.sample_rate());
+    let lookahead_blocks = crossfade_samples(lookahead, format).div_ceil(BLOCK_SAMPLES as u64);
+    let (tx, rx): (SyncSender<Vec<i16>>, _) =
+        mpsc::sync_channel(QUEUED_BLOCKS + lookahead_blocks as usize);
+    let feed = Feed {
+        blocks: rx,
+        block: Vec::new(),
+        index: 0,
+        channels: format.0,
+        sample_rate: format.1,
+        duration: decoder.total_duration(),
+        samples: Arc::new(AtomicU64::new(0)),
+        decoded: Arc::new(AtomicU64::new(0)),
+        decoded_all: Arc::new(AtomicBool::new(false)),
+        underrun: Arc::new(AtomicBool::new(false)),
+        stream,
+        wait_for_data: false,
+    };
+
+    let (decoded, decoded_all) = (feed.decoded.clone(), feed.decoded_all.clone());
+    let decode = Box::new(move || {
+        let mut block = Vec::with_capacity(BLOCK_SAMPLES);
+        for sample in decoder {
+            block.push(sample);
+            if block.len() == BLOCK_SAMPLES {
+                decoded.fetch_add(BLOCK_SAMPLES as u64, Ordering::Relaxed);
+                // The feed was dropped: stopped or replaced
+                if tx.send(std::mem::take(&mut block)).is_err() {
+                    return;
+                }
             }
         }
+        decoded.fetch_add(block.len() as u64, Ordering::Relaxed);
+        decoded_all.store(true, Ordering::Relaxed);
+        if !block.is_empty() {
+            let _ = tx.send(block);
+        }
     });
-    // Handle is dropped here, making the thread detached
-    // The thread will continue running but won't prevent process exit
+    Ok((feed, decode))
 }
 
-// Keep the old async function for compatibility, but make it non-blocking
-pub async fn play_streaming_url(url: String) -> Result<()> {
-    play_streaming_url_async(url);
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::audio_output::Recording;
+    use std::f32::consts::PI;
+
+    const RATE: u32 = 8000;
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Write a second of a mono sine at `hz` and return its samples
+    fn sine_wav(path: &Path, hz: f32) -> Vec<i16> {
+        let samples: Vec<i16> = (0..RATE)
+            .map(|n| ((2.0 * PI * hz * n as f32 / RATE as f32).sin() * 12_000.0) as i16)
+            .collect();
+        let data_len = samples.len() as u32 * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&RATE.to_le_bytes());
+        wav.extend_from_slice(&(RATE * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        wav.extend(samples.iter().flat_map(|s| s.to_le_bytes()));
+        std::fs::write(path, wav).unwrap();
+        samples
+    }
+
+    fn recording_player() -> (AudioPlayer, Recording, UnboundedReceiver<PlayerEvent>) {
+        let recording = Recording::new();
+        let player = AudioPlayer::new(OutputBackend::Recording(recording.clone()));
+        let events = player.subscribe();
+        (player, recording, events)
+    }
+
+    fn wait_for_event(
+        events: &mut UnboundedReceiver<PlayerEvent>,
+        wanted: impl Fn(&PlayerEvent) -> bool,
+    ) -> PlayerEvent {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            match events.try_recv() {
+                Ok(PlayerEvent::Failed(_, e)) => panic!("playback failed: {}", e),
+                Ok(event) if wanted(&event) => return event,
+                Ok(_) => {}
+                Err(_) => {
+                    assert!(Instant::now() < deadline, "timed out waiting for an event");
+                    thread::sleep(Duration::from_millis(5));
+                }
+            }
+        }
+    }
+
+    fn state(state: PlaybackState) -> impl Fn(&PlayerEvent) -> bool {
+        move |event| matches!(event, PlayerEvent::Status(s) if s.state == state)
+    }
+
+    fn finished(source: &PlayerSource) -> impl Fn(&PlayerEvent) -> bool + '_ {
+        move |event| matches!(event, PlayerEvent::Finished(s) if s == source)
+    }
+
+    #[test]
+    fn plays_a_file_into_the_recording() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.wav");
+        let samples = sine_wav(&path, 440.0);
+        let (player, recording, mut events) = recording_player();
+
+        player.play_file(path.clone(), Duration::ZERO);
+        wait_for_event(&mut events, finished(&PlayerSource::File(path)));
+        assert_eq!(recording.samples(), samples);
+        assert_eq!((recording.channels(), recording.sample_rate()), (1, RATE));
+        assert_eq!(player.status().state, PlaybackState::Stopped);
+    }
+
+    /// Load `path` paused at `start`, with nothing recorded. The headless output may play
+    /// the whole track before the pause arrives, so it is paused, reloaded at `start`
+    /// and tried again if it ran out first.
+    fn load_paused(
+        player: &AudioPlayer,
+        recording: &Recording,
+        events: &mut UnboundedReceiver<PlayerEvent>,
+        path: &Path,
+        start: Duration,
+    ) {
+        let paused_or_done = |event: &PlayerEvent| {
+            state(PlaybackState::Paused)(event) || matches!(event, PlayerEvent::Finished(_))
+        };
+        let loading_or_done = |event: &PlayerEvent| {
+            state(PlaybackState::Loading)(event) || matches!(event, PlayerEvent::Finished(_))
+        };
+        loop {
+            player.play_file(path.to_path_buf(), Duration::ZERO);
+            player.pause();
+            if let PlayerEvent::Finished(_) = wait_for_event(events, paused_or_done) {
+                continue;
+            }
+            player.seek(start);
+            if let PlayerEvent::Status(_) = wait_for_event(events, loading_or_done) {
+                wait_for_event(events, state(PlaybackState::Paused));
+                recording.clear();
+                return;
+            }
+        }
+    }
+
+    #[test]
+    fn stop_drops_the_track() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.wav");
+        sine_wav(&path, 440.0);
+        let (player, recording, mut events) = recording_player();
+
+        load_paused(&player, &recording, &mut events, &path, Duration::ZERO);
+        player.stop();
+        wait_for_event(&mut events, state(PlaybackState::Stopped));
+        player.resume();
+        thread::sleep(Duration::from_millis(200));
+        assert!(recording.is_empty());
+        assert_eq!(player.playback_position(), None);
+    }
+
+    #[test]
+    fn seek_plays_from_the_sample_at_the_position() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.wav");
+        let samples = sine_wav(&path, 440.0);
+        let (player, recording, mut events) = recording_player();
+
+        let position = Duration::from_millis(250);
+        load_paused(&player, &recording, &mut events, &path, position);
+        assert_eq!(player.playback_position(), Some(position));
+        player.resume();
+        wait_for_event(&mut events, finished(&PlayerSource::File(path)));
+        assert_eq!(recording.samples(), samples[RATE as usize / 4..]);
+    }
+
+    #[test]
+    fn queued_track_follows_the_current_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let (first, second) = (dir.path().join("a.wav"), dir.path().join("b.wav"));
+        let mut samples = sine_wav(&first, 440.0);
+        samples.extend(sine_wav(&second, 880.0));
+        let (player, recording, mut events) = recording_player();
+
+        load_paused(&player, &recording, &mut events, &first, Duration::ZERO);
+        player.queue_next(Some(PlayerSource::File(second.clone())));
+        player.resume();
+        wait_for_event(&mut events, finished(&PlayerSource::File(first)));
+        wait_for_event(&mut events, finished(&PlayerSource::File(second)));
+        assert_eq!(recording.samples(), samples);
+    }
 }
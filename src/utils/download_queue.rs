@@ -0,0 +1,261 @@
+// Download Queue Utility
+// Songs waiting to download, saved next to settings.json so a batch survives a restart
+
+use crate::settings::AppSettings;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Weight of the latest sample in the smoothed download speed
+const SPEED_SMOOTHING: f64 = 0.3;
+/// Shortest interval a speed sample is taken over
+const SPEED_SAMPLE: Duration = Duration::from_millis(200);
+/// Failed runs before an item is left for the user to retry
+pub const MAX_ATTEMPTS: u32 = 4;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DownloadState {
+    Queued,
+    ResolvingUrl,
+    Downloading,
+    Paused,
+    /// Failed, and queued again once its backoff has passed
+    Retrying(String),
+    Done,
+    Failed(String),
+}
+
+impl DownloadState {
+    /// A task is working on the item
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self,
+            DownloadState::ResolvingUrl | DownloadState::Downloading
+        )
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, DownloadState::Done | DownloadState::Failed(_))
+    }
+}
+
+/// Update for one item of the queue from its running task
+#[derive(Debug, Clone)]
+pub struct DownloadProgress {
+    pub id: u64,
+    /// Run of the item the update belongs to
+    pub run: u64,
+    pub state: DownloadState,
+    /// Bytes on disk, including any resumed part
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+/// A song in the download queue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadItem {
+    pub id: u64,
+    pub title: String,
+    pub artist: String,
+    /// Song page the download URL is resolved from
+    pub page_url: String,
+    pub output_path: PathBuf,
+    pub state: DownloadState,
+    /// Failed runs since the item was queued or last retried by hand
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(skip)]
+    pub downloaded: u64,
+    #[serde(skip)]
+    pub total: Option<u64>,
+    /// Smoothed bytes per second
+    #[serde(skip)]
+    pub speed: f64,
+    /// Bumped each time the item starts, so reports from a stopped run are ignored
+    #[serde(skip)]
+    pub run: u64,
+    #[serde(skip)]
+    last_sample: Option<(Instant, u64)>,
+}
+
+impl DownloadItem {
+    /// Share of the file on disk, when its size is known
+    pub fn fraction(&self) -> Option<f32> {
+        match self.state {
+            DownloadState::Done => Some(1.0),
+            _ => self
+                .total
+                .filter(|&total| total > 0)
+                .map(|total| (self.downloaded as f64 / total as f64).min(1.0) as f32),
+        }
+    }
+
+    /// Time left at the current speed
+    pub fn eta(&self) -> Option<Duration> {
+        let remaining = self.total?.saturating_sub(self.downloaded);
+        (self.state == DownloadState::Downloading && self.speed > 0.0)
+            .then(|| Duration::from_secs_f64(remaining as f64 / self.speed))
+    }
+
+    /// Mark the item as picked up by a new task and return the run number to report with
+    pub fn start(&mut self) -> u64 {
+        self.run += 1;
+        self.state = DownloadState::ResolvingUrl;
+        self.speed = 0.0;
+        self.last_sample = None;
+        self.run
+    }
+
+    fn apply(&mut self, progress: DownloadProgress) {
+        let now = Instant::now();
+        match self.last_sample {
+            Some((at, bytes)) if progress.state == DownloadState::Downloading => {
+                let elapsed = now.duration_since(at);
+                if elapsed >= SPEED_SAMPLE {
+                    let sample =
+                        progress.downloaded.saturating_sub(bytes) as f64 / elapsed.as_secs_f64();
+                    self.speed = if self.speed > 0.0 {
+                        self.speed * (1.0 - SPEED_SMOOTHING) + sample * SPEED_SMOOTHING
+                    } else {
+                        sample
+                    };
+                    self.last_sample = Some((now, progress.downloaded));
+                }
+            }
+            // The first report may include a resumed part, so only start measuring from it
+            _ => self.last_sample = Some((now, progress.downloaded)),
+        }
+        if progress.state != DownloadState::Downloading {
+            self.speed = 0.0;
+        }
+        self.state = progress.state;
+        self.downloaded = progress.downloaded;
+        self.total = progress.total.or(self.total);
+    }
+}
+
+/// Wait before queueing an item again after its `attempts`th failure
+pub fn retry_delay(attempts: u32) -> Duration {
+    Duration::from_secs(15 << attempts.saturating_sub(1).min(4))
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DownloadQueue {
+    pub items: Vec<DownloadItem>,
+    #[serde(default)]
+    next_id: u64,
+}
+
+impl DownloadQueue {
+    pub fn path() -> Result<PathBuf> {
+        Ok(AppSettings::config_path()?.with_file_name("downloads.json"))
+    }
+
+    /// Load the saved queue. Finished downloads are dropped and interrupted ones queued again.
+    pub fn load() -> Self {
+        let mut queue = Self::path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str::<DownloadQueue>(&content).ok())
+            .unwrap_or_default();
+        queue.items.retain(|item| item.state != DownloadState::Done);
+        for item in &mut queue.items {
+            if item.state.is_running() || matches!(item.state, DownloadState::Retrying(_)) {
+                item.state = DownloadState::Queued;
+            }
+        }
+        queue.next_id = queue
+            .items
+            .iter()
+            .map(|item| item.id + 1)
+            .max()
+            .unwrap_or(0)
+            .max(queue.next_id);
+        queue
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        let content =
+            serde_json::to_string_pretty(self).context("Failed to serialize download queue")?;
+        std::fs::write(&path, content).context("Failed to write download queue")?;
+        Ok(())
+    }
+
+    /// Queue a song, unless an unfinished item already writes to the same file
+    pub fn push(
+        &mut self,
+        title: String,
+        artist: String,
+        page_url: String,
+        output_path: PathBuf,
+    ) -> bool {
+        if self
+            .items
+            .iter()
+            .any(|item| item.output_path == output_path && item.state != DownloadState::Done)
+        {
+            return false;
+        }
+        self.items.push(DownloadItem {
+            id: self.next_id,
+            title,
+            artist,
+            page_url,
+            output_path,
+            state: DownloadState::Queued,
+            attempts: 0,
+            downloaded: 0,
+            total: None,
+            speed: 0.0,
+            run: 0,
+            last_sample: None,
+        });
+        self.next_id += 1;
+        true
+    }
+
+    pub fn get_mut(&mut self, id: u64) -> Option<&mut DownloadItem> {
+        self.items.iter_mut().find(|item| item.id == id)
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<DownloadItem> {
+        let index = self.items.iter().position(|item| item.id == id)?;
+        Some(self.items.remove(index))
+    }
+
+    pub fn running(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.state.is_running())
+            .count()
+    }
+
+    /// Record a report from a running task; reports from an earlier run are ignored
+    pub fn apply_progress(&mut self, progress: DownloadProgress) -> bool {
+        match self.get_mut(progress.id) {
+            Some(item) if item.run == progress.run && item.state.is_running() => {
+                item.apply(progress);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Overall progress of the queue, in percent
+    pub fn progress(&self) -> f32 {
+        if self.items.is_empty() {
+            return 0.0;
+        }
+        let finished: f32 = self
+            .items
+            .iter()
+            .map(|item| match item.state {
+                DownloadState::Done | DownloadState::Failed(_) => 1.0,
+                _ => item.fraction().unwrap_or(0.0),
+            })
+            .sum();
+        finished / self.items.len() as f32 * 100.0
+    }
+}
@@ -0,0 +1,217 @@
+// Folder Watcher Utility
+// Watches a folder for newly arrived audio files and tags them with a rule set
+
+use crate::config::SUPPORTED_FORMATS;
+use crate::file_dialog::find_cover_image;
+use crate::metadata::{read_file_metadata, set_metadata, TagUpdate};
+use notify::event::{CreateKind, ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// How long a file must go without changes before it is considered fully written
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Rules applied to every new file that shows up in the watched folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchRules {
+    pub parse_filename: bool,
+    pub default_genre: String,
+    pub use_folder_cover: bool,
+}
+
+impl Default for WatchRules {
+    fn default() -> Self {
+        Self {
+            parse_filename: true,
+            default_genre: String::new(),
+            use_folder_cover: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum WatchEvent {
+    NewFile(PathBuf),
+    Error(String),
+}
+
+/// Keeps the underlying OS watcher alive; dropping it stops watching
+pub struct FolderWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+/// Start watching `folder` recursively.
+///
+/// New audio files are reported once they have stopped changing for [`SETTLE_TIME`],
+/// so files that are still being downloaded or copied are not tagged half-written.
+pub fn watch(folder: &Path) -> notify::Result<(FolderWatcher, UnboundedReceiver<WatchEvent>)> {
+    let (raw_tx, raw_rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(raw_tx)?;
+    watcher.watch(folder, RecursiveMode::Recursive)?;
+
+    let (tx, rx) = unbounded_channel();
+    thread::spawn(move || settle_events(raw_rx, tx));
+
+    Ok((FolderWatcher { _watcher: watcher }, rx))
+}
+
+fn settle_events(raw_rx: mpsc::Receiver<notify::Result<Event>>, tx: UnboundedSender<WatchEvent>) {
+    let mut pending: HashMap<PathBuf, Instant> = HashMap::new();
+
+    loop {
+        match raw_rx.recv_timeout(POLL_INTERVAL) {
+            Ok(Ok(event)) => {
+                let arrived = matches!(
+                    event.kind,
+                    EventKind::Create(CreateKind::File | CreateKind::Any)
+                        | EventKind::Modify(ModifyKind::Name(RenameMode::To | RenameMode::Both))
+                );
+                let written = matches!(
+                    event.kind,
+                    EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any)
+                );
+                for path in event.paths {
+                    // Writes only matter for files that are still arriving; this also keeps
+                    // our own tag writes from re-triggering the rules
+                    if (arrived && is_audio_file(&path)) || (written && pending.contains_key(&path))
+                    {
+                        pending.insert(path, Instant::now());
+                    }
+                }
+            }
+            Ok(Err(e)) => {
+                if tx.send(WatchEvent::Error(e.to_string())).is_err() {
+                    return;
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+        }
+
+        let settled: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, changed)| changed.elapsed() >= SETTLE_TIME)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in settled {
+            pending.remove(&path);
+            if path.is_file() && tx.send(WatchEvent::NewFile(path)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| SUPPORTED_FORMATS.contains(&ext.as_str()))
+}
+
+/// Values recovered from a file name such as `01 - Artist - Title.mp3`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedFilename {
+    pub track: Option<u32>,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+}
+
+pub fn parse_filename(path: &Path) -> ParsedFilename {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().replace('_', " "))
+        .unwrap_or_default();
+
+    let mut parts: Vec<String> = stem
+        .split(" - ")
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+
+    let mut parsed = ParsedFilename::default();
+
+    // Leading track number, either as its own segment ("01 - ") or prefixed ("01. Title").
+    // Names like "2Pac" or "50 Cent" are left alone.
+    if let Some(first) = parts.first_mut() {
+        let digits: String = first.chars().take_while(|c| c.is_ascii_digit()).collect();
+        let rest = &first[digits.len()..];
+        if !digits.is_empty() && digits.len() <= 3 {
+            if rest.is_empty() {
+                parsed.track = digits.parse().ok();
+                parts.remove(0);
+            } else if rest.starts_with(['.', ')']) {
+                parsed.track = digits.parse().ok();
+                *first = rest.trim_start_matches(['.', ')', ' ']).to_string();
+            }
+        }
+    }
+
+    match parts.len() {
+        0 => {}
+        1 => parsed.title = parts.pop(),
+        _ => {
+            parsed.artist = Some(parts.remove(0));
+            parsed.title = Some(parts.join(" - "));
+        }
+    }
+    parsed
+}
+
+/// Apply the watch rules to a single file.
+///
+/// Text fields are only filled in where the file has no value yet, so tags that
+/// already came with the file are never overwritten.
+pub fn apply_rules(path: PathBuf, rules: &WatchRules) -> Result<String, String> {
+    let existing = read_file_metadata(path.clone()).unwrap_or_default();
+    let mut update = TagUpdate::default();
+    let mut applied = Vec::new();
+
+    if rules.parse_filename {
+        let parsed = parse_filename(&path);
+        if existing.artist.trim().is_empty() {
+            if let Some(artist) = parsed.artist {
+                applied.push(format!("artist '{}'", artist));
+                update.artist = Some(artist);
+            }
+        }
+        if existing.title.trim().is_empty() {
+            if let Some(title) = parsed.title {
+                applied.push(format!("title '{}'", title));
+                update.title = Some(title);
+            }
+        }
+    }
+
+    let genre = rules.default_genre.trim();
+    if !genre.is_empty() && existing.genre.trim().is_empty() {
+        applied.push(format!("genre '{}'", genre));
+        update.genre = Some(genre.to_string());
+    }
+
+    if rules.use_folder_cover {
+        if let Some(cover) = path.parent().and_then(find_cover_image) {
+            applied.push("cover".to_string());
+            update.album_art = Some(cover);
+        }
+    }
+
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    if applied.is_empty() {
+        return Ok(format!("{}: nothing to change", file_name));
+    }
+
+    set_metadata(path, update).map_err(|e| format!("{}: {}", file_name, e))?;
+    Ok(format!("{}: set {}", file_name, applied.join(", ")))
+}
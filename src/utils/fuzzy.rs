@@ -0,0 +1,98 @@
+// Fuzzy Matching Utility
+// Ranks known values against partially typed or misspelled input for autocomplete
+
+use std::collections::HashSet;
+
+/// Score how well `candidate` matches `query`; `None` when it does not match at all.
+///
+/// Characters of the query must appear in order in the candidate (so "dftpnk" matches
+/// "Daft Punk"), with bonuses for prefix, word-start and consecutive matches. Queries that
+/// are not a subsequence still match with a low score when they are within a small edit
+/// distance of the candidate's start, which catches typos like "Metalica".
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| *c != ' ').collect();
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    if query.is_empty() {
+        return None;
+    }
+
+    if let Some(score) = subsequence_score(&query, &candidate) {
+        return Some(score);
+    }
+
+    let prefix: Vec<char> = candidate
+        .iter()
+        .copied()
+        .filter(|c| *c != ' ')
+        .take(query.len())
+        .collect();
+    let allowed = (query.len() / 4).max(1);
+    let distance = edit_distance(&query, &prefix);
+    (query.len() >= 3 && distance <= allowed).then_some(-(distance as i32) * 10)
+}
+
+fn subsequence_score(query: &[char], candidate: &[char]) -> Option<i32> {
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for &q in query {
+        let found = candidate[position..].iter().position(|&c| c == q)? + position;
+        score += 1;
+        if found == 0 {
+            score += 8;
+        } else if !candidate[found - 1].is_alphanumeric() {
+            score += 5;
+        }
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 4;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    // Prefer shorter candidates when the match is otherwise equal
+    Some(score * 4 - candidate.len() as i32 / 4)
+}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Best matching distinct candidates for `query`, skipping a value the query already equals
+pub fn fuzzy_suggest<'a>(
+    query: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+    limit: usize,
+) -> Vec<String> {
+    let query = query.trim();
+    let mut seen = HashSet::new();
+    let mut scored: Vec<(i32, &str)> = Vec::new();
+    for candidate in candidates.into_iter().map(str::trim) {
+        if candidate.is_empty() || !seen.insert(candidate) {
+            continue;
+        }
+        if candidate == query {
+            return Vec::new();
+        }
+        if let Some(score) = fuzzy_score(query, candidate) {
+            scored.push((score, candidate));
+        }
+    }
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, c)| c.to_string())
+        .collect()
+}
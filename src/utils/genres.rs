@@ -0,0 +1,179 @@
+// Genre Utility
+// Standard ID3v1/Winamp genre list, custom vocabulary, autocomplete and variant detection
+
+use lofty::id3::v1::GENRES;
+use std::collections::HashMap;
+
+/// Spelling-insensitive key, so "Hip Hop", "Hip-Hop" and "hiphop" compare equal
+pub fn genre_key(genre: &str) -> String {
+    genre
+        .to_lowercase()
+        .replace('&', "and")
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+/// Custom genres followed by the standard ID3 list, without duplicate spellings
+pub fn vocabulary(custom: &[String]) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    custom
+        .iter()
+        .map(|g| g.trim())
+        .chain(GENRES.iter().copied())
+        .filter(|g| !g.is_empty() && seen.insert(genre_key(g)))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The vocabulary spelling of `genre`, if it has one
+pub fn canonical_genre<'a>(genre: &str, vocabulary: &'a [String]) -> Option<&'a String> {
+    let key = genre_key(genre);
+    vocabulary.iter().find(|g| genre_key(g) == key)
+}
+
+/// Vocabulary entries matching what has been typed so far: prefix matches first, then
+/// entries containing it. Nothing is suggested once the value is already a known spelling.
+pub fn suggest_genres(query: &str, vocabulary: &[String], limit: usize) -> Vec<String> {
+    let key = genre_key(query);
+    if key.is_empty() || vocabulary.iter().any(|g| g == query.trim()) {
+        return Vec::new();
+    }
+    let keyed: Vec<(String, &String)> = vocabulary.iter().map(|g| (genre_key(g), g)).collect();
+    let prefix = keyed.iter().filter(|(k, _)| k.starts_with(&key));
+    let contains = keyed
+        .iter()
+        .filter(|(k, _)| !k.starts_with(&key) && k.contains(&key));
+    prefix
+        .chain(contains)
+        .map(|(_, g)| (*g).clone())
+        .take(limit)
+        .collect()
+}
+
+/// Spellings of one genre found in the library, with the value they should be merged into
+#[derive(Debug, Clone)]
+pub struct GenreVariants {
+    pub canonical: String,
+    /// Each spelling with the number of files using it
+    pub variants: Vec<(String, usize)>,
+}
+
+impl GenreVariants {
+    /// Spellings that differ from the canonical value
+    pub fn to_rewrite(&self) -> Vec<String> {
+        self.variants
+            .iter()
+            .map(|(v, _)| v)
+            .filter(|v| **v != self.canonical)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Group the library's genres by spelling-insensitive key and keep the groups that need
+/// merging: several spellings, or a single one that differs from the vocabulary.
+/// The canonical value is the vocabulary spelling, else the most used spelling.
+pub fn find_genre_variants<'a>(
+    genres: impl Iterator<Item = &'a str>,
+    vocabulary: &[String],
+) -> Vec<GenreVariants> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for genre in genres.map(str::trim).filter(|g| !g.is_empty()) {
+        *counts.entry(genre.to_string()).or_default() += 1;
+    }
+
+    let mut groups: HashMap<String, Vec<(String, usize)>> = HashMap::new();
+    for (genre, count) in counts {
+        groups
+            .entry(genre_key(&genre))
+            .or_default()
+            .push((genre, count));
+    }
+
+    let mut result: Vec<GenreVariants> = groups
+        .into_values()
+        .filter_map(|mut variants| {
+            variants.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            let canonical = canonical_genre(&variants[0].0, vocabulary)
+                .cloned()
+                .unwrap_or_else(|| variants[0].0.clone());
+            let needs_merge = variants.len() > 1 || variants[0].0 != canonical;
+            needs_merge.then_some(GenreVariants {
+                canonical,
+                variants,
+            })
+        })
+        .collect();
+    result.sort_by(|a, b| a.canonical.cmp(&b.canonical));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variants(library: &[&str], custom: &[&str]) -> Vec<GenreVariants> {
+        let custom: Vec<String> = custom.iter().map(|g| g.to_string()).collect();
+        find_genre_variants(library.iter().copied(), &vocabulary(&custom))
+    }
+
+    fn counts(group: &GenreVariants) -> Vec<(&str, usize)> {
+        group
+            .variants
+            .iter()
+            .map(|(v, n)| (v.as_str(), *n))
+            .collect()
+    }
+
+    #[test]
+    fn case_and_spelling_variants_merge_into_the_standard_spelling() {
+        let groups = variants(
+            &["hip hop", "Hip-Hop", "hip hop", "HIPHOP", "hip hop", "Rock"],
+            &[],
+        );
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].canonical, "Hip-Hop");
+        // Most used first, ties alphabetically
+        assert_eq!(
+            counts(&groups[0]),
+            [("hip hop", 3), ("HIPHOP", 1), ("Hip-Hop", 1)]
+        );
+        assert_eq!(groups[0].to_rewrite(), ["hip hop", "HIPHOP"]);
+    }
+
+    #[test]
+    fn single_spelling_is_only_reported_when_it_differs_from_the_vocabulary() {
+        let groups = variants(&["drum and bass", "Rock", " Rock ", ""], &[]);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].canonical, "Drum & Bass");
+        assert_eq!(counts(&groups[0]), [("drum and bass", 1)]);
+    }
+
+    #[test]
+    fn unknown_genres_merge_into_the_most_used_spelling() {
+        let groups = variants(&["Chillwave", "chillwave", "Chillwave", "Vaporwave"], &[]);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].canonical, "Chillwave");
+        assert_eq!(groups[0].to_rewrite(), ["chillwave"]);
+    }
+
+    #[test]
+    fn custom_genres_from_settings_set_the_spelling() {
+        let groups = variants(&["baila", "BAILA", "baila"], &["Baila"]);
+        assert_eq!(groups[0].canonical, "Baila");
+        assert_eq!(groups[0].to_rewrite(), ["baila", "BAILA"]);
+
+        // A custom spelling takes the place of the standard one
+        let groups = variants(&["Hip-Hop", "hip-hop"], &["Hip Hop"]);
+        assert_eq!(groups[0].canonical, "Hip Hop");
+        assert_eq!(groups[0].to_rewrite(), ["Hip-Hop", "hip-hop"]);
+    }
+
+    #[test]
+    fn groups_are_sorted_by_canonical_name() {
+        let groups = variants(&["rock", "jazz", "blues"], &[]);
+        let names: Vec<&str> = groups.iter().map(|g| g.canonical.as_str()).collect();
+        assert_eq!(names, ["Blues", "Jazz", "Rock"]);
+    }
+}
@@ -0,0 +1,604 @@
+// HTTP Stream Utility
+// Seekable reader over a remote file, fetched in the background with HTTP range requests
+
+use std::collections::BTreeMap;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+use symphonia::core::io::MediaSource;
+
+/// Size of each range request
+const CHUNK_SIZE: u64 = 256 * 1024;
+/// Chunks fetched ahead of the read position
+const READ_AHEAD_CHUNKS: u64 = 32;
+/// Chunks kept behind the read position, for decoders that step back a little
+const KEEP_BEHIND_CHUNKS: u64 = 4;
+/// Attempts per chunk before playback fails
+const FETCH_ATTEMPTS: usize = 3;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest wait for the next piece of a response; a body is read for as long as playback lasts,
+/// so there is no limit on a whole request
+const READ_TIMEOUT: Duration = if cfg!(test) {
+    Duration::from_secs(2)
+} else {
+    Duration::from_secs(30)
+};
+
+#[derive(Default)]
+struct Buffer {
+    /// Fetched data by chunk index; a chunk may still be growing when `sequential`
+    chunks: BTreeMap<u64, Vec<u8>>,
+    total_len: Option<u64>,
+    /// Where the reader is, so the fetcher knows what to fetch next
+    position: u64,
+    /// The server ignored `Range`: the body arrives in order and cannot be refetched
+    sequential: bool,
+    /// The whole body has arrived (sequential mode)
+    finished: bool,
+    /// Chunks below this were dropped in sequential mode and cannot be read again
+    discarded: u64,
+    error: Option<String>,
+    /// The reader is blocked waiting for data
+    waiting: bool,
+    /// The reader was dropped; the fetcher stops
+    closed: bool,
+    /// Headers of the first response have been handled
+    opened: bool,
+}
+
+impl Buffer {
+    fn buffered_bytes(&self) -> u64 {
+        self.chunks.values().map(|c| c.len() as u64).sum()
+    }
+}
+
+struct Shared {
+    buffer: Mutex<Buffer>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Buffer> {
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Snapshot of how much of a stream has arrived
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BufferStatus {
+    /// Playback is stalled waiting for data
+    pub buffering: bool,
+    pub buffered: u64,
+    pub total: Option<u64>,
+}
+
+impl BufferStatus {
+    /// Share of the file held in memory, when its size is known
+    pub fn fraction(&self) -> Option<f32> {
+        self.total
+            .filter(|t| *t > 0)
+            .map(|t| (self.buffered as f32 / t as f32).min(1.0))
+    }
+}
+
+/// Handle for watching a stream's buffer from outside the decoder
+#[derive(Clone)]
+pub struct StreamProgress(Arc<Shared>);
+
+impl StreamProgress {
+    pub fn status(&self) -> BufferStatus {
+        let buffer = self.0.lock();
+        BufferStatus {
+            buffering: buffer.waiting,
+            buffered: buffer.buffered_bytes(),
+            total: buffer.total_len,
+        }
+    }
+
+    /// Why fetching stopped, if it failed
+    pub fn error(&self) -> Option<String> {
+        self.0.lock().error.clone()
+    }
+}
+
+/// `Read + Seek` over a URL. Reads block until the requested bytes have been fetched.
+pub struct HttpStream {
+    shared: Arc<Shared>,
+    position: u64,
+}
+
+impl HttpStream {
+    /// Start fetching `url` and wait for the first response, so HTTP errors surface here
+    pub fn open(url: &str) -> Result<Self, String> {
+        let shared = Arc::new(Shared {
+            buffer: Mutex::new(Buffer::default()),
+            changed: Condvar::new(),
+        });
+
+        let fetcher = shared.clone();
+        let url = url.to_string();
+        std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(e) => {
+                    fail(&fetcher, format!("Failed to create runtime: {}", e));
+                    return;
+                }
+            };
+            if let Err(e) = runtime.block_on(fetch(&fetcher, &url)) {
+                fail(&fetcher, e);
+            }
+        });
+
+        let mut buffer = shared.lock();
+        while !buffer.opened && buffer.error.is_none() {
+            buffer = shared
+                .changed
+                .wait(buffer)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        if let Some(e) = buffer.error.clone() {
+            return Err(e);
+        }
+        drop(buffer);
+
+        Ok(Self {
+            shared,
+            position: 0,
+        })
+    }
+
+    pub fn progress(&self) -> StreamProgress {
+        StreamProgress(self.shared.clone())
+    }
+}
+
+impl Read for HttpStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut buffer = self.shared.lock();
+        loop {
+            if buffer.total_len.is_some_and(|total| self.position >= total) {
+                return Ok(0);
+            }
+            let chunk = self.position / CHUNK_SIZE;
+            let offset = (self.position % CHUNK_SIZE) as usize;
+            if let Some(data) = buffer.chunks.get(&chunk).filter(|d| offset < d.len()) {
+                let count = buf.len().min(data.len() - offset);
+                buf[..count].copy_from_slice(&data[offset..offset + count]);
+                self.position += count as u64;
+                buffer.position = self.position;
+                buffer.waiting = false;
+                self.shared.changed.notify_all();
+                return Ok(count);
+            }
+            if let Some(e) = &buffer.error {
+                return Err(io::Error::other(e.clone()));
+            }
+            if buffer.sequential && chunk < buffer.discarded {
+                return Err(io::Error::other(
+                    "Stream data before the buffered part is gone",
+                ));
+            }
+            if buffer.sequential && buffer.finished {
+                return Ok(0);
+            }
+            buffer.position = self.position;
+            buffer.waiting = true;
+            self.shared.changed.notify_all();
+            buffer = self
+                .shared
+                .changed
+                .wait(buffer)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+}
+
+impl Seek for HttpStream {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::Current(offset) => self.position as i64 + offset,
+            SeekFrom::End(offset) => {
+                // Without a Content-Length the end is only known once the body is complete
+                let mut buffer = self.shared.lock();
+                while buffer.total_len.is_none() && buffer.error.is_none() {
+                    buffer.waiting = true;
+                    self.shared.changed.notify_all();
+                    buffer = self
+                        .shared
+                        .changed
+                        .wait(buffer)
+                        .unwrap_or_else(|e| e.into_inner());
+                }
+                buffer.waiting = false;
+                match buffer.total_len {
+                    Some(total) => total as i64 + offset,
+                    None => return Err(io::Error::other(buffer.error.clone().unwrap_or_default())),
+                }
+            }
+        };
+        if target < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek before start of stream",
+            ));
+        }
+        self.position = target as u64;
+        let mut buffer = self.shared.lock();
+        buffer.position = self.position;
+        self.shared.changed.notify_all();
+        Ok(self.position)
+    }
+}
+
+impl MediaSource for HttpStream {
+    /// A body that arrives in order can only be read forward
+    fn is_seekable(&self) -> bool {
+        !self.shared.lock().sequential
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.shared.lock().total_len
+    }
+}
+
+impl Drop for HttpStream {
+    fn drop(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+fn fail(shared: &Shared, error: String) {
+    let mut buffer = shared.lock();
+    buffer.error = Some(error);
+    shared.changed.notify_all();
+}
+
+/// Total length from a `Content-Range: bytes 0-262143/5000000` header
+fn content_range_total(response: &reqwest::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit_once('/')?
+        .1
+        .parse()
+        .ok()
+}
+
+/// Run one step of a request, failing if the server goes quiet for `READ_TIMEOUT`
+async fn timed<T>(
+    step: impl std::future::Future<Output = reqwest::Result<T>>,
+) -> Result<T, String> {
+    match tokio::time::timeout(READ_TIMEOUT, step).await {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(_) => Err("operation timed out".to_string()),
+    }
+}
+
+async fn fetch(shared: &Shared, url: &str) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let response = timed(
+        client
+            .get(url)
+            .header(
+                reqwest::header::RANGE,
+                format!("bytes=0-{}", CHUNK_SIZE - 1),
+            )
+            .send(),
+    )
+    .await
+    .map_err(|e| format!("Failed to fetch audio URL: {}", e))?;
+
+    match response.status() {
+        reqwest::StatusCode::PARTIAL_CONTENT => {
+            let total = content_range_total(&response);
+            let first = timed(response.bytes())
+                .await
+                .map_err(|e| format!("Failed to read audio data: {}", e))?;
+            {
+                let mut buffer = shared.lock();
+                buffer.total_len = total;
+                buffer.chunks.insert(0, first.to_vec());
+                buffer.opened = true;
+                shared.changed.notify_all();
+            }
+            match total {
+                Some(total) => fetch_ranges(shared, &client, url, total).await,
+                // Range support without a length is rare; fall back to one plain request
+                None => {
+                    let response = timed(client.get(url).send())
+                        .await
+                        .map_err(|e| format!("Failed to fetch audio URL: {}", e))?;
+                    {
+                        let mut buffer = shared.lock();
+                        buffer.chunks.clear();
+                        buffer.sequential = true;
+                    }
+                    fetch_sequential(shared, response).await
+                }
+            }
+        }
+        // An empty file cannot satisfy any range
+        reqwest::StatusCode::RANGE_NOT_SATISFIABLE => {
+            let mut buffer = shared.lock();
+            buffer.total_len = Some(0);
+            buffer.opened = true;
+            shared.changed.notify_all();
+            Ok(())
+        }
+        status if status.is_success() => {
+            {
+                let mut buffer = shared.lock();
+                buffer.sequential = true;
+                buffer.total_len = response.content_length();
+                buffer.opened = true;
+                shared.changed.notify_all();
+            }
+            fetch_sequential(shared, response).await
+        }
+        status => Err(format!("Failed to fetch audio: HTTP {}", status)),
+    }
+}
+
+/// Fetch chunks around the read position until the reader is dropped
+async fn fetch_ranges(
+    shared: &Shared,
+    client: &reqwest::Client,
+    url: &str,
+    total: u64,
+) -> Result<(), String> {
+    let chunk_count = total.div_ceil(CHUNK_SIZE);
+    loop {
+        let next = {
+            let mut buffer = shared.lock();
+            loop {
+                if buffer.closed {
+                    return Ok(());
+                }
+                let current = buffer.position / CHUNK_SIZE;
+                let keep_from = current.saturating_sub(KEEP_BEHIND_CHUNKS);
+                buffer.chunks.retain(|index, _| *index >= keep_from);
+                let missing = (current..(current + READ_AHEAD_CHUNKS).min(chunk_count))
+                    .find(|index| !buffer.chunks.contains_key(index));
+                match missing {
+                    Some(index) => break index,
+                    None => {
+                        buffer = shared
+                            .changed
+                            .wait(buffer)
+                            .unwrap_or_else(|e| e.into_inner())
+                    }
+                }
+            }
+        };
+
+        let start = next * CHUNK_SIZE;
+        let end = (start + CHUNK_SIZE).min(total) - 1;
+        let mut last_error = String::new();
+        let mut data = None;
+        for _ in 0..FETCH_ATTEMPTS {
+            match fetch_range(client, url, start, end).await {
+                Ok(bytes) => {
+                    data = Some(bytes);
+                    break;
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        let data = data.ok_or(last_error)?;
+
+        let mut buffer = shared.lock();
+        buffer.chunks.insert(next, data);
+        shared.changed.notify_all();
+    }
+}
+
+async fn fetch_range(
+    client: &reqwest::Client,
+    url: &str,
+    start: u64,
+    end: u64,
+) -> Result<Vec<u8>, String> {
+    let response = timed(
+        client
+            .get(url)
+            .header(reqwest::header::RANGE, format!("bytes={}-{}", start, end))
+            .send(),
+    )
+    .await
+    .map_err(|e| format!("Failed to fetch audio data: {}", e))?;
+    if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+        return Err(format!("Failed to fetch audio: HTTP {}", response.status()));
+    }
+    let bytes = timed(response.bytes())
+        .await
+        .map_err(|e| format!("Failed to read audio data: {}", e))?;
+    if bytes.len() as u64 != end - start + 1 {
+        return Err("Server returned a short range".to_string());
+    }
+    Ok(bytes.to_vec())
+}
+
+/// Read a plain response body in order, pausing while the reader is far behind
+async fn fetch_sequential(shared: &Shared, mut response: reqwest::Response) -> Result<(), String> {
+    let mut received = 0u64;
+    loop {
+        {
+            let mut buffer = shared.lock();
+            // A waiting reader may need the very end, so never pause on it
+            while !buffer.closed
+                && !buffer.waiting
+                && received > buffer.position + READ_AHEAD_CHUNKS * CHUNK_SIZE
+            {
+                buffer = shared
+                    .changed
+                    .wait(buffer)
+                    .unwrap_or_else(|e| e.into_inner());
+            }
+            if buffer.closed {
+                return Ok(());
+            }
+        }
+
+        let bytes = timed(response.chunk())
+            .await
+            .map_err(|e| format!("Failed to read audio data: {}", e))?;
+        let mut buffer = shared.lock();
+        let Some(mut bytes) = bytes.map(|b| b.to_vec()) else {
+            buffer.finished = true;
+            buffer.total_len = Some(received);
+            shared.changed.notify_all();
+            return Ok(());
+        };
+        // Split the body into fixed-size chunks so reads find bytes the same way in both modes
+        while !bytes.is_empty() {
+            let chunk = received / CHUNK_SIZE;
+            let room = (CHUNK_SIZE - received % CHUNK_SIZE) as usize;
+            let rest = bytes.split_off(room.min(bytes.len()));
+            received += bytes.len() as u64;
+            buffer.chunks.entry(chunk).or_default().extend(bytes);
+            bytes = rest;
+        }
+        // Only complete chunks can go; the reader may be waiting ahead of the body
+        let keep_from = (buffer.position / CHUNK_SIZE)
+            .saturating_sub(KEEP_BEHIND_CHUNKS)
+            .min(received / CHUNK_SIZE);
+        buffer.chunks.retain(|index, _| *index >= keep_from);
+        buffer.discarded = buffer.discarded.max(keep_from);
+        shared.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server::{Reply, TestServer};
+    use std::time::Instant;
+
+    fn body(chunks: u64) -> Vec<u8> {
+        (0..chunks * CHUNK_SIZE + 1234)
+            .map(|i| (i * 31 % 251) as u8)
+            .collect()
+    }
+
+    fn read_at(stream: &mut HttpStream, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        stream.seek(SeekFrom::Start(offset))?;
+        let mut data = vec![0u8; len];
+        stream.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    /// Wait until the fetcher has dropped chunks, which it does once it sees the new position
+    fn wait_for_pruning(stream: &HttpStream, total: u64) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while stream.progress().status().buffered >= total {
+            assert!(Instant::now() < deadline, "chunks were never pruned");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn reads_a_ranged_body_in_order() {
+        let body = body(3);
+        let server = TestServer::start(body.clone(), Reply::Ranges);
+        let mut stream = HttpStream::open(&server.url).unwrap();
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+        assert_eq!(data, body);
+        assert!(stream.is_seekable());
+        assert_eq!(stream.byte_len(), Some(body.len() as u64));
+    }
+
+    #[test]
+    fn falls_back_to_one_request_when_range_is_ignored() {
+        let body = body(3);
+        let server = TestServer::start(body.clone(), Reply::IgnoresRange);
+        let mut stream = HttpStream::open(&server.url).unwrap();
+        assert!(!stream.is_seekable());
+        let mut data = Vec::new();
+        stream.read_to_end(&mut data).unwrap();
+        assert_eq!(data, body);
+    }
+
+    #[test]
+    fn sequential_body_survives_a_reader_pausing_past_the_read_timeout() {
+        // Larger than the read-ahead, so the fetcher stops reading the body while the reader pauses
+        let body = body(READ_AHEAD_CHUNKS + 8);
+        let server = TestServer::start(body.clone(), Reply::IgnoresRange);
+        let mut stream = HttpStream::open(&server.url).unwrap();
+        let mut data = vec![0u8; 100];
+        stream.read_exact(&mut data).unwrap();
+        std::thread::sleep(READ_TIMEOUT + Duration::from_secs(1));
+        stream.read_to_end(&mut data).unwrap();
+        assert!(data == body, "body differs after {} bytes", data.len());
+    }
+
+    #[test]
+    fn unsatisfiable_first_range_is_an_empty_file() {
+        let server = TestServer::start(Vec::new(), Reply::Ranges);
+        let mut stream = HttpStream::open(&server.url).unwrap();
+        let mut data = Vec::new();
+        assert_eq!(stream.read_to_end(&mut data).unwrap(), 0);
+        assert_eq!(stream.byte_len(), Some(0));
+    }
+
+    #[test]
+    fn disconnect_mid_body_fails_the_read() {
+        let body = body(2);
+        let cut = CHUNK_SIZE as usize + 1000;
+        let server = TestServer::start(body.clone(), Reply::CutsOffAt(cut));
+        let mut stream = HttpStream::open(&server.url).unwrap();
+        let mut data = Vec::new();
+        assert!(stream.read_to_end(&mut data).is_err());
+        assert_eq!(data[..], body[..data.len()]);
+        assert!(stream.progress().error().is_some());
+    }
+
+    #[test]
+    fn seeking_back_refetches_pruned_ranges() {
+        let body = body(KEEP_BEHIND_CHUNKS + 4);
+        let server = TestServer::start(body.clone(), Reply::Ranges);
+        let mut stream = HttpStream::open(&server.url).unwrap();
+        let end = (KEEP_BEHIND_CHUNKS + 3) * CHUNK_SIZE;
+        assert_eq!(
+            read_at(&mut stream, end, 100).unwrap(),
+            body[end as usize..][..100]
+        );
+        wait_for_pruning(&stream, body.len() as u64);
+        assert_eq!(read_at(&mut stream, 10, 100).unwrap(), body[10..110]);
+    }
+
+    #[test]
+    fn sequential_stream_prunes_behind_the_reader() {
+        let body = body(KEEP_BEHIND_CHUNKS + 4);
+        let server = TestServer::start(body.clone(), Reply::IgnoresRange);
+        let mut stream = HttpStream::open(&server.url).unwrap();
+        let end = (KEEP_BEHIND_CHUNKS + 3) * CHUNK_SIZE;
+        assert_eq!(
+            read_at(&mut stream, end, 100).unwrap(),
+            body[end as usize..][..100]
+        );
+        wait_for_pruning(&stream, body.len() as u64);
+        // Data just behind the reader stays; the start of the body cannot be fetched again
+        let kept = 3 * CHUNK_SIZE;
+        assert_eq!(
+            read_at(&mut stream, kept, 100).unwrap(),
+            body[kept as usize..][..100]
+        );
+        assert!(read_at(&mut stream, 0, 100).is_err());
+    }
+}
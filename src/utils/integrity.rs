@@ -0,0 +1,379 @@
+// Integrity Checker Utility
+// Fully decodes audio files to find empty, truncated, corrupt or mislabeled ones
+
+use crate::metadata::read_file_metadata;
+use crate::utils::audio_decode::{decode_file, UNSUPPORTED_FORMAT};
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+/// Decoded and reported lengths may differ by this much (or 1%) before it is reported
+const LENGTH_TOLERANCE: Duration = Duration::from_secs(2);
+
+/// What a file's bytes actually contain, independent of its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentType {
+    Mp3,
+    Flac,
+    Ogg,
+    Wav,
+    Aiff,
+    Mp4,
+    Asf,
+    Aac,
+    Html,
+    Unknown,
+}
+
+impl ContentType {
+    /// Formats the decoder can read from start to end
+    fn is_decodable(self) -> bool {
+        matches!(
+            self,
+            ContentType::Mp3
+                | ContentType::Flac
+                | ContentType::Ogg
+                | ContentType::Wav
+                | ContentType::Aiff
+                | ContentType::Mp4
+                | ContentType::Aac
+        )
+    }
+
+    /// Content types a file with this extension is expected to hold
+    fn expected_for(extension: &str) -> &'static [ContentType] {
+        match extension {
+            "mp3" => &[ContentType::Mp3],
+            "flac" => &[ContentType::Flac],
+            "ogg" | "opus" => &[ContentType::Ogg],
+            "wav" => &[ContentType::Wav],
+            "aiff" => &[ContentType::Aiff],
+            "m4a" | "mp4" => &[ContentType::Mp4],
+            "aac" => &[ContentType::Aac, ContentType::Mp4],
+            "wma" => &[ContentType::Asf],
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ContentType::Mp3 => "MP3",
+            ContentType::Flac => "FLAC",
+            ContentType::Ogg => "Ogg",
+            ContentType::Wav => "WAV",
+            ContentType::Aiff => "AIFF",
+            ContentType::Mp4 => "MP4/M4A",
+            ContentType::Asf => "WMA",
+            ContentType::Aac => "AAC",
+            ContentType::Html => "HTML",
+            ContentType::Unknown => "unknown data",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum IntegrityIssue {
+    Empty,
+    /// Not audio at all, e.g. an HTML error page saved as `.mp3`
+    NotAudio(ContentType),
+    WrongExtension {
+        content: ContentType,
+        extension: String,
+    },
+    Unreadable(String),
+    /// No decoder exists for the format, so the audio itself was not checked
+    NotDecoded(String),
+    DecodeFailed(String),
+    /// Decoding stopped well before the length the file's headers report, or the data
+    /// ends partway through a frame (`reported` is then only set when it is longer)
+    Truncated {
+        decoded: Duration,
+        reported: Option<Duration>,
+    },
+    /// The audio runs well past the length the file's headers report
+    LengthMismatch {
+        decoded: Duration,
+        reported: Duration,
+    },
+}
+
+impl fmt::Display for IntegrityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityIssue::Empty => write!(f, "File is empty"),
+            IntegrityIssue::NotAudio(ContentType::Html) => {
+                write!(f, "File is an HTML page, not audio")
+            }
+            IntegrityIssue::NotAudio(_) => write!(f, "File does not contain recognizable audio"),
+            IntegrityIssue::WrongExtension { content, extension } => {
+                write!(f, "{} content named .{}", content, extension)
+            }
+            IntegrityIssue::Unreadable(e) => write!(f, "Headers unreadable: {}", e),
+            IntegrityIssue::NotDecoded(format) => {
+                write!(f, "Not verified: {} audio cannot be decoded", format)
+            }
+            IntegrityIssue::DecodeFailed(e) => write!(f, "Decode error: {}", e),
+            IntegrityIssue::Truncated {
+                decoded,
+                reported: Some(reported),
+            } => write!(
+                f,
+                "Truncated or corrupt: audio stops at {} of {}",
+                format_duration(*decoded),
+                format_duration(*reported)
+            ),
+            IntegrityIssue::Truncated {
+                decoded,
+                reported: None,
+            } => write!(
+                f,
+                "Truncated: audio stops partway through a frame at {}",
+                format_duration(*decoded)
+            ),
+            IntegrityIssue::LengthMismatch { decoded, reported } => write!(
+                f,
+                "Length mismatch: decodes to {} but headers say {}",
+                format_duration(*decoded),
+                format_duration(*reported)
+            ),
+        }
+    }
+}
+
+/// Result of checking one file
+#[derive(Debug, Clone, Default)]
+pub struct FileCheck {
+    pub issues: Vec<IntegrityIssue>,
+    /// Length of the decoded audio, when the format could be decoded
+    pub decoded: Option<Duration>,
+}
+
+impl FileCheck {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Check a single file: size, content vs extension, header readability and a full decode
+/// compared against the duration reported by its headers.
+pub fn check_file(path: &Path) -> FileCheck {
+    let mut check = FileCheck::default();
+
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if size == 0 {
+        check.issues.push(IntegrityIssue::Empty);
+        return check;
+    }
+
+    let content = sniff_content(path).unwrap_or(ContentType::Unknown);
+    if matches!(content, ContentType::Html | ContentType::Unknown) {
+        check.issues.push(IntegrityIssue::NotAudio(content));
+        return check;
+    }
+
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let expected = ContentType::expected_for(&extension);
+    if !expected.is_empty() && !expected.contains(&content) {
+        check.issues.push(IntegrityIssue::WrongExtension {
+            content,
+            extension: extension.clone(),
+        });
+    }
+
+    // Headers are parsed by extension, so a misnamed file failing here says nothing new
+    let reported = match read_file_metadata(path.to_path_buf()) {
+        Ok(metadata) => metadata.duration.map(Duration::from_secs),
+        Err(e) => {
+            if check.issues.is_empty() {
+                check.issues.push(IntegrityIssue::Unreadable(e));
+            }
+            None
+        }
+    };
+
+    // Opus is Ogg too, but only Vorbis can be decoded
+    if !content.is_decodable() || extension == "opus" {
+        let format = if content == ContentType::Ogg {
+            "Opus".to_string()
+        } else {
+            content.to_string()
+        };
+        check.issues.push(IntegrityIssue::NotDecoded(format));
+        return check;
+    }
+
+    match decode_file(path, |_, _, _| {}) {
+        Ok(summary) => {
+            let decoded = summary.duration();
+            check.decoded = Some(decoded);
+            // A cut-off WAV data chunk still decodes cleanly, so its declared size gives the length
+            if content == ContentType::Wav {
+                if let Some((declared, available)) = wav_data_sizes(path) {
+                    if available < declared {
+                        let reported = decoded.mul_f64(declared as f64 / available.max(1) as f64);
+                        check.issues.push(IntegrityIssue::Truncated {
+                            decoded,
+                            reported: Some(reported),
+                        });
+                        return check;
+                    }
+                }
+            }
+            // Headers estimated from the file size (CBR MP3 without a frame count) shrink
+            // with the file, so only a frame cut off at the end shows the damage
+            if summary.truncated {
+                check.issues.push(IntegrityIssue::Truncated {
+                    decoded,
+                    reported: reported.filter(|r| *r > decoded),
+                });
+                return check;
+            }
+            if let Some(reported) = reported.filter(|r| !r.is_zero()) {
+                let tolerance = LENGTH_TOLERANCE.max(reported / 100);
+                if decoded + tolerance < reported {
+                    check.issues.push(IntegrityIssue::Truncated {
+                        decoded,
+                        reported: Some(reported),
+                    });
+                } else if decoded > reported + tolerance {
+                    check
+                        .issues
+                        .push(IntegrityIssue::LengthMismatch { decoded, reported });
+                }
+            }
+        }
+        // An Opus stream named `.ogg` only shows up once the codec is known
+        Err(e) if e == UNSUPPORTED_FORMAT => check
+            .issues
+            .push(IntegrityIssue::NotDecoded("Opus".to_string())),
+        Err(e) => check.issues.push(IntegrityIssue::DecodeFailed(e)),
+    }
+
+    check
+}
+
+/// Identify a file's format from its leading bytes, looking past an ID3v2 tag
+fn sniff_content(path: &Path) -> std::io::Result<ContentType> {
+    let mut file = File::open(path)?;
+    let mut header = [0u8; 16];
+    let read = file.read(&mut header)?;
+    let mut header = &header[..read];
+
+    let mut after_tag = [0u8; 16];
+    if header.starts_with(b"ID3") && header.len() >= 10 {
+        // Syncsafe size, plus a 10 byte footer when flagged
+        let size = header[6..10]
+            .iter()
+            .fold(0u64, |acc, b| (acc << 7) | (*b as u64 & 0x7f));
+        let footer = if header[5] & 0x10 != 0 { 10 } else { 0 };
+        file.seek(SeekFrom::Start(10 + size + footer))?;
+        let read = file.read(&mut after_tag)?;
+        header = &after_tag[..read];
+    }
+
+    Ok(sniff_bytes(header))
+}
+
+fn sniff_bytes(bytes: &[u8]) -> ContentType {
+    let starts = |magic: &[u8]| bytes.starts_with(magic);
+    if starts(b"fLaC") {
+        ContentType::Flac
+    } else if starts(b"OggS") {
+        ContentType::Ogg
+    } else if starts(b"RIFF") && bytes.get(8..12) == Some(b"WAVE") {
+        ContentType::Wav
+    } else if starts(b"FORM") && bytes.get(8..11) == Some(b"AIF") {
+        ContentType::Aiff
+    } else if bytes.get(4..8) == Some(b"ftyp") {
+        ContentType::Mp4
+    } else if starts(&[0x30, 0x26, 0xB2, 0x75]) {
+        ContentType::Asf
+    } else if bytes.len() >= 2 && bytes[0] == 0xFF && bytes[1] & 0xE0 == 0xE0 {
+        // MPEG frame sync; ADTS AAC uses the same sync with layer bits set to zero
+        if bytes[1] & 0x06 == 0 {
+            ContentType::Aac
+        } else {
+            ContentType::Mp3
+        }
+    } else if looks_like_html(bytes) {
+        ContentType::Html
+    } else {
+        ContentType::Unknown
+    }
+}
+
+/// Declared size of a WAV file's data chunk and the bytes actually present for it
+fn wav_data_sizes(path: &Path) -> Option<(u64, u64)> {
+    let mut file = File::open(path).ok()?;
+    let file_len = file.metadata().ok()?.len();
+    let mut offset = 12u64;
+    let mut chunk = [0u8; 8];
+    while offset + 8 <= file_len {
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        file.read_exact(&mut chunk).ok()?;
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+        if &chunk[..4] == b"data" {
+            return Some((size, file_len - offset - 8));
+        }
+        // Chunks are padded to an even size
+        offset += 8 + size + size % 2;
+    }
+    None
+}
+
+fn looks_like_html(bytes: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(bytes).trim_start().to_lowercase();
+    ["<!doctype", "<html", "<head", "<?xml", "<body"]
+        .iter()
+        .any(|tag| text.starts_with(tag))
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Silent CBR MP3 (128 kbps, 44.1 kHz mono) with no Xing frame, so its length is
+    /// estimated from the file size
+    fn silent_mp3(frames: usize) -> Vec<u8> {
+        let mut frame = vec![0u8; 417];
+        frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0xC4]);
+        frame.repeat(frames)
+    }
+
+    #[test]
+    fn complete_cbr_mp3_passes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("complete.mp3");
+        std::fs::write(&path, silent_mp3(200)).unwrap();
+        let check = check_file(&path);
+        assert!(check.is_ok(), "{:?}", check.issues);
+    }
+
+    #[test]
+    fn cbr_mp3_cut_off_mid_frame_is_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cut.mp3");
+        let mut data = silent_mp3(200);
+        data.truncate(data.len() - 200);
+        std::fs::write(&path, data).unwrap();
+        let check = check_file(&path);
+        assert!(
+            matches!(check.issues[..], [IntegrityIssue::Truncated { .. }]),
+            "{:?}",
+            check.issues
+        );
+    }
+}
@@ -0,0 +1,263 @@
+// Loudness Utility
+// EBU R128 / ITU-R BS.1770 integrated loudness and true peak, turned into ReplayGain 2.0 values
+
+use crate::utils::audio_decode::decode_file;
+use std::f64::consts::PI;
+use std::path::Path;
+
+/// ReplayGain 2.0 reference level
+pub const REFERENCE_LUFS: f64 = -18.0;
+
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Gating blocks are 400 ms long and start every 100 ms
+const SUB_BLOCKS_PER_BLOCK: usize = 4;
+
+/// 4x oversampling for true peak, 12 taps per phase
+const OVERSAMPLE: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+
+/// Loudness of a single decoded file
+#[derive(Debug, Clone)]
+pub struct TrackLoudness {
+    /// Integrated loudness in LUFS; `None` for silence
+    pub integrated_lufs: Option<f64>,
+    /// Linear true peak (1.0 = full scale)
+    pub true_peak: f64,
+    /// Mean power of each gating block, kept so album loudness can gate across tracks
+    pub block_powers: Vec<f64>,
+}
+
+impl TrackLoudness {
+    pub fn gain_db(&self) -> Option<f64> {
+        self.integrated_lufs.map(|lufs| REFERENCE_LUFS - lufs)
+    }
+}
+
+/// Loudness of an album: all of its tracks' blocks gated together
+#[derive(Debug, Clone, Copy)]
+pub struct AlbumLoudness {
+    pub integrated_lufs: Option<f64>,
+    pub true_peak: f64,
+}
+
+impl AlbumLoudness {
+    pub fn from_tracks<'a>(tracks: impl IntoIterator<Item = &'a TrackLoudness>) -> Self {
+        let mut blocks = Vec::new();
+        let mut true_peak: f64 = 0.0;
+        for track in tracks {
+            blocks.extend_from_slice(&track.block_powers);
+            true_peak = true_peak.max(track.true_peak);
+        }
+        Self {
+            integrated_lufs: gated_loudness(&blocks),
+            true_peak,
+        }
+    }
+
+    pub fn gain_db(&self) -> Option<f64> {
+        self.integrated_lufs.map(|lufs| REFERENCE_LUFS - lufs)
+    }
+}
+
+/// Decode `path` and measure its loudness
+pub fn analyze_file(path: &Path) -> Result<TrackLoudness, String> {
+    let mut meter: Option<LoudnessMeter> = None;
+    decode_file(path, |samples, channels, sample_rate| {
+        meter
+            .get_or_insert_with(|| LoudnessMeter::new(channels, sample_rate))
+            .process(samples);
+    })?;
+    let meter = meter.ok_or_else(|| "No audio data decoded".to_string())?;
+    Ok(meter.finish())
+}
+
+/// Integrated loudness of gating block powers, with the absolute and relative gates applied
+fn gated_loudness(blocks: &[f64]) -> Option<f64> {
+    let absolute: Vec<f64> = blocks
+        .iter()
+        .copied()
+        .filter(|p| block_lufs(*p) > ABSOLUTE_GATE_LUFS)
+        .collect();
+    if absolute.is_empty() {
+        return None;
+    }
+    let relative_gate = block_lufs(mean(&absolute)) + RELATIVE_GATE_LU;
+    let gated: Vec<f64> = absolute
+        .into_iter()
+        .filter(|p| block_lufs(*p) > relative_gate)
+        .collect();
+    if gated.is_empty() {
+        return None;
+    }
+    Some(block_lufs(mean(&gated)))
+}
+
+fn block_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.max(f64::MIN_POSITIVE).log10()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// The two K-weighting stages of BS.1770 (high shelf, then high pass) for `sample_rate`
+fn k_weighting(sample_rate: f64) -> [Biquad; 2] {
+    let shelf = {
+        let f0 = 1_681.974_450_955_533;
+        let gain_db = 3.999_843_853_973_347;
+        let q = 0.707_175_236_955_419_6;
+        let k = (PI * f0 / sample_rate).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.499_666_774_154_541_6);
+        let a0 = 1.0 + k / q + k * k;
+        Biquad {
+            b: [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            z: [0.0; 2],
+        }
+    };
+    let high_pass = {
+        let f0 = 38.135_470_876_024_44;
+        let q = 0.500_327_037_323_877_3;
+        let k = (PI * f0 / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        Biquad {
+            b: [1.0, -2.0, 1.0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            z: [0.0; 2],
+        }
+    };
+    [shelf, high_pass]
+}
+
+/// BS.1770 channel weight; surround channels of a 5.1 stream count more, LFE not at all
+fn channel_weight(channel: usize, channels: usize) -> f64 {
+    match (channels, channel) {
+        (6, 3) => 0.0,
+        (6, 4) | (6, 5) => 1.41,
+        _ => 1.0,
+    }
+}
+
+struct LoudnessMeter {
+    channels: usize,
+    filters: Vec<[Biquad; 2]>,
+    weights: Vec<f64>,
+    sub_block_frames: usize,
+    frames_in_sub_block: usize,
+    sub_block_energy: f64,
+    sub_blocks: Vec<f64>,
+    peak: TruePeak,
+}
+
+impl LoudnessMeter {
+    fn new(channels: u16, sample_rate: u32) -> Self {
+        let channels = channels.max(1) as usize;
+        Self {
+            channels,
+            filters: vec![k_weighting(sample_rate as f64); channels],
+            weights: (0..channels).map(|c| channel_weight(c, channels)).collect(),
+            sub_block_frames: (sample_rate as usize / 10).max(1),
+            frames_in_sub_block: 0,
+            sub_block_energy: 0.0,
+            sub_blocks: Vec::new(),
+            peak: TruePeak::new(channels),
+        }
+    }
+
+    fn process(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            for (c, &sample) in frame.iter().enumerate() {
+                let x = sample as f64;
+                self.peak.process(c, x);
+                let [shelf, high_pass] = &mut self.filters[c];
+                let y = high_pass.process(shelf.process(x));
+                self.sub_block_energy += self.weights[c] * y * y;
+            }
+            self.frames_in_sub_block += 1;
+            if self.frames_in_sub_block == self.sub_block_frames {
+                self.sub_blocks.push(self.sub_block_energy);
+                self.sub_block_energy = 0.0;
+                self.frames_in_sub_block = 0;
+            }
+        }
+    }
+
+    fn finish(self) -> TrackLoudness {
+        let block_frames = (self.sub_block_frames * SUB_BLOCKS_PER_BLOCK) as f64;
+        let block_powers: Vec<f64> = self
+            .sub_blocks
+            .windows(SUB_BLOCKS_PER_BLOCK)
+            .map(|w| w.iter().sum::<f64>() / block_frames)
+            .collect();
+        TrackLoudness {
+            integrated_lufs: gated_loudness(&block_powers),
+            true_peak: self.peak.max,
+            block_powers,
+        }
+    }
+}
+
+/// Inter-sample peak estimate from a 4x oversampled signal (BS.1770 Annex 2)
+struct TruePeak {
+    /// `phases[p][k]` is the tap applied to the k-th most recent input for output phase p
+    phases: [[f64; TAPS_PER_PHASE]; OVERSAMPLE],
+    history: Vec<[f64; TAPS_PER_PHASE]>,
+    max: f64,
+}
+
+impl TruePeak {
+    fn new(channels: usize) -> Self {
+        // Windowed-sinc low-pass at the original Nyquist frequency
+        let taps = OVERSAMPLE * TAPS_PER_PHASE;
+        let center = (taps - 1) as f64 / 2.0;
+        let mut phases = [[0.0; TAPS_PER_PHASE]; OVERSAMPLE];
+        for n in 0..taps {
+            let t = (n as f64 - center) / OVERSAMPLE as f64;
+            let sinc = if t == 0.0 {
+                1.0
+            } else {
+                (PI * t).sin() / (PI * t)
+            };
+            let window = 0.5 - 0.5 * (2.0 * PI * n as f64 / (taps - 1) as f64).cos();
+            phases[n % OVERSAMPLE][n / OVERSAMPLE] = sinc * window;
+        }
+        Self {
+            phases,
+            history: vec![[0.0; TAPS_PER_PHASE]; channels],
+            max: 0.0,
+        }
+    }
+
+    fn process(&mut self, channel: usize, x: f64) {
+        let history = &mut self.history[channel];
+        history.copy_within(0..TAPS_PER_PHASE - 1, 1);
+        history[0] = x;
+        self.max = self.max.max(x.abs());
+        for phase in &self.phases {
+            let y: f64 = phase.iter().zip(history.iter()).map(|(h, s)| h * s).sum();
+            self.max = self.max.max(y.abs());
+        }
+    }
+}
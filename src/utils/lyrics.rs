@@ -0,0 +1,207 @@
+// Lyrics Utility
+// Plain and synchronized lyrics, and the LRC sidecar format
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// One line of synchronized lyrics, shown from `time` on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LyricLine {
+    pub time: Duration,
+    pub text: String,
+}
+
+/// Lyrics of a file: USLT / LYRICS text and SYLT lines
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lyrics {
+    pub unsynced: String,
+    pub synced: Vec<LyricLine>,
+}
+
+/// The `.lrc` file next to an audio file
+pub fn lrc_path(audio_file: &Path) -> PathBuf {
+    audio_file.with_extension("lrc")
+}
+
+/// Parse LRC text. Lines may carry several timestamps (`[00:12.00][01:30.50]Chorus`);
+/// ID tags like `[ar:...]` are skipped and `[offset:+/-ms]` is applied.
+pub fn parse_lrc(content: &str) -> Vec<LyricLine> {
+    let mut offset_ms: i64 = 0;
+    let mut lines = Vec::new();
+
+    for raw in content.lines() {
+        let mut rest = raw.trim();
+        let mut times = Vec::new();
+        while let Some(tag) = rest.strip_prefix('[') {
+            let Some(end) = tag.find(']') else {
+                break;
+            };
+            let tag_content = &tag[..end];
+            if let Some(time) = parse_timestamp(tag_content) {
+                times.push(time);
+            } else if let Some(offset) = tag_content.strip_prefix("offset:") {
+                offset_ms = offset.trim().parse().unwrap_or(offset_ms);
+            }
+            rest = tag[end + 1..].trim_start();
+        }
+        for time in times {
+            lines.push(LyricLine {
+                time,
+                text: rest.trim_end().to_string(),
+            });
+        }
+    }
+
+    // A positive offset shows lyrics sooner
+    for line in &mut lines {
+        let ms = line.time.as_millis() as i64 - offset_ms;
+        line.time = Duration::from_millis(ms.max(0) as u64);
+    }
+    lines.sort_by_key(|l| l.time);
+    lines
+}
+
+/// Render lines as LRC, with artist and title ID tags when known
+pub fn to_lrc(lines: &[LyricLine], artist: &str, title: &str) -> String {
+    let mut out = String::new();
+    if !artist.trim().is_empty() {
+        out.push_str(&format!("[ar:{}]\n", artist.trim()));
+    }
+    if !title.trim().is_empty() {
+        out.push_str(&format!("[ti:{}]\n", title.trim()));
+    }
+    for line in lines {
+        out.push_str(&format!("[{}]{}\n", format_timestamp(line.time), line.text));
+    }
+    out
+}
+
+/// `mm:ss.xx`, as used by LRC files
+pub fn format_timestamp(time: Duration) -> String {
+    let centis = time.as_millis() / 10;
+    format!(
+        "{:02}:{:02}.{:02}",
+        centis / 6000,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
+
+/// Parse `mm:ss`, `mm:ss.xx`, `mm:ss.xxx` or `mm:ss:xx`
+pub fn parse_timestamp(value: &str) -> Option<Duration> {
+    let (minutes, rest) = value.trim().split_once(':')?;
+    let (seconds, fraction) = match rest.split_once(['.', ':']) {
+        Some((s, f)) => (s, f),
+        None => (rest, ""),
+    };
+    let minutes: u64 = minutes.parse().ok()?;
+    let seconds: u64 = seconds.parse().ok()?;
+    if seconds >= 60 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let millis = match fraction.len() {
+        0 => 0,
+        1 => fraction.parse::<u64>().ok()? * 100,
+        2 => fraction.parse::<u64>().ok()? * 10,
+        _ => fraction[..3].parse::<u64>().ok()?,
+    };
+    Some(Duration::from_millis(
+        (minutes * 60 + seconds) * 1000 + millis,
+    ))
+}
+
+/// Unsynchronized lyrics turned into lines waiting for timestamps, skipping blank lines
+pub fn lines_from_text(text: &str) -> Vec<LyricLine> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| LyricLine {
+            time: Duration::ZERO,
+            text: l.to_string(),
+        })
+        .collect()
+}
+
+/// Index of the line showing at `position`
+pub fn current_line(lines: &[LyricLine], position: Duration) -> Option<usize> {
+    lines.iter().rposition(|l| l.time <= position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(ms: u64, text: &str) -> LyricLine {
+        LyricLine {
+            time: Duration::from_millis(ms),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn timestamps_take_two_or_three_fraction_digits() {
+        assert_eq!(
+            parse_timestamp("01:02.34"),
+            Some(Duration::from_millis(62_340))
+        );
+        assert_eq!(
+            parse_timestamp("01:02.345"),
+            Some(Duration::from_millis(62_345))
+        );
+        assert_eq!(
+            parse_timestamp("01:02.3"),
+            Some(Duration::from_millis(62_300))
+        );
+        assert_eq!(
+            parse_timestamp("01:02:34"),
+            Some(Duration::from_millis(62_340))
+        );
+        assert_eq!(parse_timestamp("1:02"), Some(Duration::from_millis(62_000)));
+        assert_eq!(parse_timestamp("61:00.00"), Some(Duration::from_secs(3660)));
+    }
+
+    #[test]
+    fn tags_that_are_not_timestamps_are_rejected() {
+        for value in ["ar:Artist", "00:60.00", "00:1x.00", "00:10.-5", "", "10"] {
+            assert_eq!(parse_timestamp(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn lines_with_several_timestamps_repeat_in_order() {
+        let lines = parse_lrc(
+            "[ar:Artist]\n[ti:Title]\n[00:12.00][01:30.50]Chorus\n[00:20.123] Verse \n[00:40.00]\n",
+        );
+        assert_eq!(
+            lines,
+            [
+                line(12_000, "Chorus"),
+                line(20_123, "Verse"),
+                line(40_000, ""),
+                line(90_500, "Chorus"),
+            ]
+        );
+    }
+
+    #[test]
+    fn offset_moves_every_line() {
+        // Positive shows lyrics sooner, never before the start
+        let sooner = parse_lrc("[offset:+500]\n[00:00.20]One\n[00:10.00]Two\n");
+        assert_eq!(sooner, [line(0, "One"), line(9_500, "Two")]);
+        // The tag applies wherever it appears
+        let later = parse_lrc("[00:10.00]Two\n[offset:-250]\n");
+        assert_eq!(later, [line(10_250, "Two")]);
+    }
+
+    #[test]
+    fn lrc_round_trips_at_centisecond_precision() {
+        let lines = [line(5_000, "One"), line(83_450, "Two")];
+        let lrc = to_lrc(&lines, "Artist", "Title");
+        assert!(lrc.starts_with("[ar:Artist]\n[ti:Title]\n[00:05.00]One\n"));
+        assert_eq!(parse_lrc(&lrc), lines);
+        assert_eq!(
+            format_timestamp(Duration::from_millis(3_723_456)),
+            "62:03.45"
+        );
+    }
+}
@@ -1,6 +1,22 @@
 // Utility modules for Music Tools
 pub mod audio_converter;
+pub mod audio_decode;
+pub mod audio_output;
 pub mod audio_player;
+pub mod download_queue;
+pub mod folder_watcher;
+pub mod fuzzy;
+pub mod genres;
+pub mod http_stream;
+pub mod integrity;
+pub mod loudness;
+pub mod lyrics;
 pub mod metadata_editor;
 pub mod music_downloader;
+pub mod quality;
 pub mod scraper;
+pub mod spectrum;
+#[cfg(test)]
+pub mod test_server;
+pub mod text_rules;
+pub mod track_numbering;
@@ -1,6 +1,12 @@
-use crate::utils::scraper::{Artist, Song, SongHubScraper};
+use crate::metadata::{set_metadata, TagUpdate};
+use crate::utils::download_queue::DownloadQueue;
+use crate::utils::scraper::{Artist, Song, SongHubScraper, SongPage};
 use anyhow::Result;
-use std::path::PathBuf;
+use futures::future::AbortHandle;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
 #[derive(Debug, Clone)]
 pub struct MusicDownloaderState {
     pub artist_search_query: String,
@@ -17,6 +23,17 @@ pub struct MusicDownloaderState {
     pub download_path: Option<PathBuf>,
     pub playing_song_index: Option<usize>,
     pub streaming_url: Option<String>,
+    /// Songs queued for preview, as indices into `search_results`
+    pub play_queue: Vec<usize>,
+    /// Entry of `play_queue` playing, while playback follows the queue
+    pub queue_position: Option<usize>,
+    pub shuffle: bool,
+    /// Queued song after the playing one and its streaming URL, already handed to the player
+    pub next_stream: Option<(usize, String)>,
+    /// Saved download queue
+    pub downloads: DownloadQueue,
+    /// Handles to stop the running downloads, by item id
+    pub download_tasks: HashMap<u64, AbortHandle>,
 }
 
 impl Default for MusicDownloaderState {
@@ -36,6 +53,12 @@ impl Default for MusicDownloaderState {
             download_path: None,
             playing_song_index: None,
             streaming_url: None,
+            play_queue: Vec::new(),
+            queue_position: None,
+            shuffle: false,
+            next_stream: None,
+            downloads: DownloadQueue::default(),
+            download_tasks: HashMap::new(),
         }
     }
 }
@@ -101,6 +124,67 @@ impl MusicDownloaderState {
         self.status = format!("{} artist(s) found", self.filtered_artists.len());
     }
 
+    /// Add a song to the preview queue, or take it out if it is already queued
+    pub fn toggle_queued(&mut self, index: usize) {
+        match self.play_queue.iter().position(|&i| i == index) {
+            Some(entry) => {
+                self.play_queue.remove(entry);
+                self.queue_position = match self.queue_position {
+                    Some(p) if p == entry => None,
+                    Some(p) if p > entry => Some(p - 1),
+                    p => p,
+                };
+            }
+            None => self.play_queue.push(index),
+        }
+    }
+
+    /// Queue the checked songs that are not queued yet, in list order
+    pub fn queue_selected(&mut self) {
+        let mut selected = self.selected_songs.clone();
+        selected.sort_unstable();
+        for index in selected {
+            if !self.play_queue.contains(&index) {
+                self.play_queue.push(index);
+            }
+        }
+        if self.shuffle {
+            let from = self.queue_position.map_or(0, |p| p + 1);
+            shuffle(&mut self.play_queue[from..]);
+        }
+    }
+
+    pub fn clear_queue(&mut self) {
+        self.play_queue.clear();
+        self.queue_position = None;
+    }
+
+    /// Shuffle the songs still to come, or put them back in list order
+    pub fn set_shuffle(&mut self, shuffle_on: bool) {
+        self.shuffle = shuffle_on;
+        let from = self
+            .queue_position
+            .map_or(0, |p| p + 1)
+            .min(self.play_queue.len());
+        if shuffle_on {
+            shuffle(&mut self.play_queue[from..]);
+        } else {
+            self.play_queue[from..].sort_unstable();
+        }
+    }
+
+    /// Song index of the queue entry after the playing one
+    pub fn next_queued(&self) -> Option<usize> {
+        let next = self.queue_position.map_or(0, |p| p + 1);
+        self.play_queue.get(next).copied()
+    }
+
+    /// Song index of the queue entry before the playing one
+    pub fn previous_queued(&self) -> Option<usize> {
+        let previous = self.queue_position?.checked_sub(1)?;
+        self.play_queue.get(previous).copied()
+    }
+
     /// Get all songs for the selected artist
     pub async fn load_artist_songs(&mut self) -> Result<()> {
         let artist = match &self.selected_artist {
@@ -123,6 +207,7 @@ impl MusicDownloaderState {
 
         self.search_results = songs;
         self.selected_songs.clear();
+        self.clear_queue();
         self.loading_songs = false;
         self.status = format!(
             "Found {} song(s) for {}",
@@ -162,12 +247,8 @@ impl MusicDownloaderState {
             self.status = format!("Downloading: {}...", song.title);
             self.progress = (idx as f32 / total as f32) * 100.0;
 
-            let download_url = match scraper.get_download_url(&song.url).await {
-                Ok(Some(url)) => url,
-                Ok(None) => {
-                    errors.push(format!("No download URL found for: {}", song.title));
-                    continue;
-                }
+            let page = match scraper.get_song_page(&song.url).await {
+                Ok(page) => page,
                 Err(e) => {
                     errors.push(format!(
                         "Failed to get download URL for {}: {}",
@@ -176,13 +257,26 @@ impl MusicDownloaderState {
                     continue;
                 }
             };
+            let download_url = match page.download_url.clone() {
+                Some(url) => url,
+                None => {
+                    errors.push(format!("No download URL found for: {}", song.title));
+                    continue;
+                }
+            };
 
             let safe_title = sanitize_filename(&song.title);
             let filename = format!("{}.mp3", safe_title);
             let output_path = download_dir.join(&filename);
 
             match scraper.download_song(&download_url, &output_path).await {
-                Ok(_) => {}
+                Ok(_) => {
+                    if let Err(e) =
+                        tag_download(&scraper, &output_path, &song.title, &song.artist, &page).await
+                    {
+                        errors.push(format!("Failed to tag {}: {}", song.title, e));
+                    }
+                }
                 Err(e) => {
                     errors.push(format!("Failed to download {}: {}", song.title, e));
                 }
@@ -202,7 +296,59 @@ impl MusicDownloaderState {
     }
 }
 
-fn sanitize_filename(name: &str) -> String {
+/// Fisher-Yates with a time-seeded xorshift; a preview order needs nothing stronger
+fn shuffle(items: &mut [usize]) {
+    let mut state = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+        | 1;
+    for i in (1..items.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        items.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
+
+/// Write a song's title and artist into its downloaded file, with the album, year and
+/// cover image its page shows
+pub async fn tag_download(
+    scraper: &SongHubScraper,
+    output_path: &Path,
+    title: &str,
+    artist: &str,
+    page: &SongPage,
+) -> Result<(), String> {
+    // A cover that fails to load should not keep the other tags from being written.
+    // Each cover gets its own temp file, removed when `cover` drops.
+    let mut cover = None;
+    if let Some(url) = &page.cover_url {
+        if let Ok((image, extension)) = scraper.fetch_image(url).await {
+            cover = tempfile::Builder::new()
+                .prefix("music-tools-cover-")
+                .suffix(&format!(".{}", extension))
+                .tempfile()
+                .ok()
+                .filter(|file| std::fs::write(file.path(), &image).is_ok());
+        }
+    }
+
+    let update = TagUpdate {
+        title: Some(title.to_string()),
+        artist: Some(artist.to_string()),
+        album: page.album.clone(),
+        year: page.year,
+        album_art: cover.as_ref().map(|file| file.path().to_path_buf()),
+        ..Default::default()
+    };
+    let path = output_path.to_path_buf();
+    tokio::task::spawn_blocking(move || set_metadata(path, update))
+        .await
+        .unwrap_or_else(|e| Err(format!("Task error: {}", e)))
+}
+
+pub fn sanitize_filename(name: &str) -> String {
     name.chars()
         .map(|c| match c {
             '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
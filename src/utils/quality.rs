@@ -0,0 +1,263 @@
+// Quality Utility
+// Estimates a file's true source quality from the frequency where its spectrum is cut off
+
+use crate::metadata::read_file_metadata;
+use crate::utils::spectrum::{self, SpectrumAnalysis};
+use std::fmt;
+use std::path::Path;
+
+/// Bins averaged on each side when smoothing the spectrum
+const SMOOTHING_BINS: usize = 3;
+/// Lowest cutoff looked for; below this the content itself is band-limited
+const MIN_CUTOFF_HZ: f32 = 8_000.0;
+/// Drop across a cutoff that marks it as an encoder's low-pass rather than natural roll-off
+const BRICK_WALL_DB: f32 = 25.0;
+/// Width of the bands compared on each side of a cutoff
+const EDGE_BAND_HZ: f32 = 800.0;
+/// Cutoffs this close to Nyquist count as full bandwidth
+const FULL_BAND_MARGIN_HZ: f32 = 1_000.0;
+/// Lossy encoders low-pass below this; a lossless file cut off higher up is not lossy, though
+/// a high-resolution one may have been resampled from CD audio (cutoff near 22 kHz)
+const LOSSY_CUTOFF_HZ: f32 = 20_000.0;
+/// Nyquist of the highest common sample rate below high resolution (48 kHz)
+const STANDARD_NYQUIST_HZ: f32 = 24_000.0;
+
+/// Typical encoder low-pass frequency for each MP3 bitrate
+const LOWPASS_TABLE: [(f32, u32); 6] = [
+    (13_000.0, 64),
+    (15_000.0, 96),
+    (16_500.0, 128),
+    (17_800.0, 160),
+    (19_500.0, 192),
+    (20_300.0, 256),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityVerdict {
+    /// Bandwidth matches what the format and bitrate promise
+    Genuine,
+    /// FLAC, WAV or AIFF with a lossy encoder's low-pass
+    FakeLossless,
+    /// Lossy file labeled with a higher bitrate than its bandwidth supports
+    FakeBitrate,
+    /// High-resolution lossless file with no content above a lower sample rate's Nyquist
+    Upsampled,
+}
+
+#[derive(Debug, Clone)]
+pub struct QualityReport {
+    pub verdict: QualityVerdict,
+    /// Sharp cutoff frequency; `None` for content up to Nyquist
+    pub cutoff_hz: Option<f32>,
+    /// Bitrate of the lossy source the cutoff points to
+    pub source_kbps: Option<u32>,
+    pub bitrate: Option<u32>,
+}
+
+impl QualityReport {
+    pub fn is_suspect(&self) -> bool {
+        self.verdict != QualityVerdict::Genuine
+    }
+
+    /// Short label for the file list
+    pub fn badge(&self) -> String {
+        match (self.verdict, self.source_kbps) {
+            (QualityVerdict::Genuine, _) => match self.cutoff_hz {
+                Some(hz) => format!("{:.1}k", hz / 1000.0),
+                None => "Full".to_string(),
+            },
+            (QualityVerdict::Upsampled, _) => "Upsampled".to_string(),
+            (_, Some(kbps)) => format!("~{}k", kbps),
+            (_, None) => "Fake".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for QualityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cutoff = match self.cutoff_hz {
+            Some(hz) => format!("cutoff at {:.1} kHz", hz / 1000.0),
+            None => "full bandwidth".to_string(),
+        };
+        let source = self
+            .source_kbps
+            .map(|kbps| format!("~{} kbps", kbps))
+            .unwrap_or_else(|| "a lossy".to_string());
+        match self.verdict {
+            QualityVerdict::Genuine => write!(f, "Looks genuine, {}", cutoff),
+            QualityVerdict::FakeLossless => {
+                write!(
+                    f,
+                    "Lossless file transcoded from {} source, {}",
+                    source, cutoff
+                )
+            }
+            QualityVerdict::Upsampled => {
+                write!(f, "Possibly upsampled from a lower sample rate, {}", cutoff)
+            }
+            QualityVerdict::FakeBitrate => write!(
+                f,
+                "Labeled {} kbps but transcoded from {} source, {}",
+                self.bitrate.unwrap_or(0),
+                source,
+                cutoff
+            ),
+        }
+    }
+}
+
+/// Decode `path` and judge whether its bandwidth fits its format and bitrate
+pub fn analyze_quality(path: &Path) -> Result<QualityReport, String> {
+    let metadata = read_file_metadata(path.to_path_buf())?;
+    let analysis = spectrum::analyze_file(path)?;
+    let cutoff_hz = find_cutoff(&analysis);
+
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let lossless = matches!(extension.as_str(), "flac" | "wav" | "aiff" | "aif");
+    let (verdict, source_kbps) =
+        judge(lossless, cutoff_hz, analysis.nyquist_hz(), metadata.bitrate);
+
+    Ok(QualityReport {
+        verdict,
+        cutoff_hz,
+        source_kbps,
+        bitrate: metadata.bitrate,
+    })
+}
+
+/// Verdict for a file's cutoff, with the lossy source bitrate it points to
+fn judge(
+    lossless: bool,
+    cutoff_hz: Option<f32>,
+    nyquist_hz: f32,
+    bitrate: Option<u32>,
+) -> (QualityVerdict, Option<u32>) {
+    if lossless && cutoff_hz.is_some_and(|hz| hz >= LOSSY_CUTOFF_HZ) {
+        let verdict = if nyquist_hz > STANDARD_NYQUIST_HZ {
+            QualityVerdict::Upsampled
+        } else {
+            QualityVerdict::Genuine
+        };
+        return (verdict, None);
+    }
+    let source_kbps = cutoff_hz.map(source_bitrate);
+    let verdict = match (lossless, source_kbps, bitrate) {
+        (true, Some(_), _) => QualityVerdict::FakeLossless,
+        // Allow some headroom: VBR and encoder settings move the low-pass around
+        (false, Some(source), Some(bitrate)) if bitrate as f32 > source as f32 * 1.4 => {
+            QualityVerdict::FakeBitrate
+        }
+        _ => QualityVerdict::Genuine,
+    };
+    (verdict, source_kbps)
+}
+
+/// Frequency of a brick-wall low-pass in the average spectrum, if there is one: the point
+/// with the steepest drop, where nothing above it comes back up
+fn find_cutoff(analysis: &SpectrumAnalysis) -> Option<f32> {
+    let db: Vec<f32> = analysis
+        .average
+        .iter()
+        .map(|p| 10.0 * p.max(1e-20).log10())
+        .collect();
+    let bin_hz = analysis.bin_hz(1);
+    if db.is_empty() || bin_hz <= 0.0 {
+        return None;
+    }
+    let smoothed: Vec<f32> = (0..db.len())
+        .map(|i| {
+            let from = i.saturating_sub(SMOOTHING_BINS);
+            let to = (i + SMOOTHING_BINS + 1).min(db.len());
+            db[from..to].iter().sum::<f32>() / (to - from) as f32
+        })
+        .collect();
+
+    let bin = |hz: f32| ((hz.max(0.0) / bin_hz) as usize).min(smoothed.len() - 1);
+    let mean = |from: usize, to: usize| {
+        let values = &smoothed[from..=to.max(from)];
+        values.iter().sum::<f32>() / values.len() as f32
+    };
+    let edge = bin(EDGE_BAND_HZ);
+    let gap = (edge / 4).max(1);
+
+    let first = bin(MIN_CUTOFF_HZ);
+    let last = bin(analysis.nyquist_hz() - FULL_BAND_MARGIN_HZ);
+    let (cutoff, drop) = (first..last)
+        .filter(|&i| i >= edge && i + edge < smoothed.len())
+        .map(|i| (i, mean(i - edge, i - gap) - mean(i + gap, i + edge)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if drop < BRICK_WALL_DB {
+        return None;
+    }
+
+    // Real music rolls off unevenly; an encoder's low-pass leaves nothing above it
+    let below = mean(cutoff - edge, cutoff - gap);
+    let above_peak = smoothed[cutoff + gap..]
+        .iter()
+        .copied()
+        .fold(f32::NEG_INFINITY, f32::max);
+    (above_peak < below - BRICK_WALL_DB / 2.0).then(|| analysis.bin_hz(cutoff))
+}
+
+/// The MP3 bitrate whose usual low-pass sits at `cutoff_hz`
+fn source_bitrate(cutoff_hz: f32) -> u32 {
+    LOWPASS_TABLE
+        .iter()
+        .find(|(limit, _)| cutoff_hz < *limit)
+        .map(|(_, kbps)| *kbps)
+        .unwrap_or(320)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lossless_with_an_encoder_lowpass_is_fake() {
+        assert_eq!(
+            judge(true, Some(16_000.0), 22_050.0, None),
+            (QualityVerdict::FakeLossless, Some(128))
+        );
+        assert_eq!(
+            judge(true, Some(19_800.0), 22_050.0, None),
+            (QualityVerdict::FakeLossless, Some(256))
+        );
+    }
+
+    #[test]
+    fn lossless_cut_off_near_cd_bandwidth_is_upsampled() {
+        // A 96 kHz file made from 44.1 kHz audio stops just below 22.05 kHz
+        assert_eq!(
+            judge(true, Some(21_900.0), 48_000.0, None),
+            (QualityVerdict::Upsampled, None)
+        );
+        assert_eq!(
+            judge(true, None, 48_000.0, None),
+            (QualityVerdict::Genuine, None)
+        );
+        // Not lossy, and a CD-rate file has no lower rate to come from
+        assert_eq!(
+            judge(true, Some(20_500.0), 22_050.0, None),
+            (QualityVerdict::Genuine, None)
+        );
+    }
+
+    #[test]
+    fn lossy_bitrate_above_its_bandwidth_is_fake() {
+        assert_eq!(
+            judge(false, Some(16_000.0), 22_050.0, Some(320)),
+            (QualityVerdict::FakeBitrate, Some(128))
+        );
+        assert_eq!(
+            judge(false, Some(16_000.0), 22_050.0, Some(128)),
+            (QualityVerdict::Genuine, Some(128))
+        );
+        assert_eq!(
+            judge(false, Some(21_000.0), 22_050.0, Some(320)),
+            (QualityVerdict::Genuine, Some(320))
+        );
+    }
+}
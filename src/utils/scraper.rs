@@ -1,9 +1,13 @@
 use anyhow::{Context, Result};
 use regex::Regex;
+use reqwest::header::{CONTENT_RANGE, CONTENT_TYPE, RANGE};
+use reqwest::StatusCode;
 use scraper::{Html, Selector};
 use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 use tokio::sync::Semaphore;
 use tokio::time;
 use url::Url;
@@ -13,6 +17,8 @@ const USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/
 const MAX_CONCURRENT_REQUESTS: usize = 8;
 const PAGE_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
 const RATE_LIMIT_DELAY: Duration = Duration::from_millis(100);
+/// Passes at a download before giving up, resuming where the previous one stopped
+const DOWNLOAD_ATTEMPTS: u32 = 4;
 
 #[derive(Debug, Clone)]
 pub struct Song {
@@ -186,13 +192,7 @@ impl SongHubScraper {
                     continue;
                 }
 
-                let mut name = element.text().collect::<String>().trim().to_string();
-                name = name
-                    .replace(" mp3 songs", "")
-                    .replace(" mp3 song", "")
-                    .replace(" mp3", "")
-                    .trim()
-                    .to_string();
+                let mut name = strip_mp3_markers(element.text().collect::<String>().trim());
 
                 let parts: Vec<&str> = name.split_whitespace().collect();
                 if parts.len() > 2 {
@@ -392,7 +392,7 @@ impl SongHubScraper {
         }
 
         // Sort songs by title in ascending order (case-insensitive)
-        songs.sort_by(|a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()));
+        songs.sort_by_key(|a| a.title.to_lowercase());
 
         Ok(songs)
     }
@@ -402,6 +402,11 @@ impl SongHubScraper {
     }
 
     pub async fn get_download_url(&self, song_url: &str) -> Result<Option<String>> {
+        Ok(self.get_song_page(song_url).await?.download_url)
+    }
+
+    /// Fetch a song page for its download link and any album, year and cover it shows
+    pub async fn get_song_page(&self, song_url: &str) -> Result<SongPage> {
         tokio::time::sleep(Duration::from_millis(300)).await;
 
         let url = Url::parse(song_url).context("Invalid song URL")?;
@@ -425,73 +430,312 @@ impl SongHubScraper {
             .await
             .context("Failed to read response body")?;
 
-        let mp3_regex = Regex::new(r#"https?://[^"'\s]+\.mp3"#)?;
-        if let Some(captures) = mp3_regex.find(&html) {
-            return Ok(Some(captures.as_str().to_string()));
-        }
-
         let document = Html::parse_document(&html);
-        let download_selector = Selector::parse(
-            "a[href*='download'], a[href*='.mp3'], button[data-url], a[data-download]",
+        let lines: Vec<&str> = document
+            .root_element()
+            .text()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .collect();
+        let year_regex = Regex::new(r"\b(19|20)\d{2}\b")?;
+        let year = meta_content(&document, "meta[property='music:release_date']")
+            .or_else(|| labelled_value(&lines, &["year", "released", "release date"]))
+            .and_then(|value| year_regex.find(&value)?.as_str().parse().ok());
+        let cover_url = meta_content(
+            &document,
+            "meta[property='og:image'], meta[name='twitter:image']",
         )
-        .unwrap_or_else(|_| Selector::parse("a").unwrap());
-
-        for element in document.select(&download_selector) {
-            if let Some(href) = element.value().attr("href") {
-                if href.contains(".mp3") || href.contains("download") {
-                    let full_url = if href.starts_with("http") {
-                        href.to_string()
-                    } else {
-                        format!("{}{}", BASE_URL, href)
-                    };
-                    return Ok(Some(full_url));
-                }
-            }
+        .filter(|src| !src.to_lowercase().contains("logo"))
+        .and_then(|src| Url::parse(BASE_URL).ok()?.join(&src).ok())
+        .map(String::from);
+
+        Ok(SongPage {
+            download_url: find_download_url(&html, &document)?,
+            album: labelled_value(&lines, &["album"]),
+            year,
+            cover_url,
+        })
+    }
 
-            if let Some(data_url) = element.value().attr("data-url") {
-                if data_url.contains(".mp3") {
-                    let full_url = if data_url.starts_with("http") {
-                        data_url.to_string()
-                    } else {
-                        format!("{}{}", BASE_URL, data_url)
-                    };
-                    return Ok(Some(full_url));
-                }
-            }
+    /// Download an image, returning its bytes and a file extension for its type
+    pub async fn fetch_image(&self, url: &str) -> Result<(Vec<u8>, &'static str)> {
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .context("Failed to fetch image")?;
+        if !response.status().is_success() {
+            anyhow::bail!("Image request failed with status: {}", response.status());
+        }
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("image/jpeg")
+            .to_lowercase();
+        if !content_type.starts_with("image/") {
+            anyhow::bail!("Not an image: {}", content_type);
         }
+        let extension = match content_type.as_str() {
+            "image/png" => "png",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            _ => "jpg",
+        };
+        let bytes = response.bytes().await.context("Failed to read image")?;
+        Ok((bytes.to_vec(), extension))
+    }
 
-        Ok(None)
+    /// Download to `<file>.part`, resuming from a partial file left by an earlier attempt,
+    /// and move it into place once its size matches what the server announced
+    pub async fn download_song(&self, download_url: &str, output_path: &Path) -> Result<()> {
+        self.download_song_with_progress(download_url, output_path, |_, _| {})
+            .await
     }
 
-    pub async fn download_song(
+    /// `download_song`, calling `on_progress` with the bytes on disk and the expected total
+    /// as data arrives
+    pub async fn download_song_with_progress<F>(
         &self,
         download_url: &str,
-        output_path: &std::path::Path,
-    ) -> Result<()> {
+        output_path: &Path,
+        mut on_progress: F,
+    ) -> Result<()>
+    where
+        F: FnMut(u64, Option<u64>) + Send,
+    {
         let url = Url::parse(download_url).context("Invalid download URL")?;
+        let part_path = part_path(output_path);
 
-        let response = self
-            .client
-            .get(url)
-            .send()
-            .await
-            .context("Failed to download file")?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Download failed with status: {}", response.status());
+        let mut last_error = None;
+        for attempt in 0..DOWNLOAD_ATTEMPTS {
+            if attempt > 0 {
+                time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+            }
+            match self.fetch_part(&url, &part_path, &mut on_progress).await? {
+                Attempt::Complete => {
+                    tokio::fs::rename(&part_path, output_path)
+                        .await
+                        .context("Failed to move finished download into place")?;
+                    return Ok(());
+                }
+                Attempt::Retry(e) => last_error = Some(e),
+            }
         }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Download failed")))
+    }
 
-        let bytes = response
-            .bytes()
+    /// One pass at completing a `.part` file. Errors that another pass may fix, like a
+    /// dropped connection, come back as `Attempt::Retry`.
+    async fn fetch_part<F>(
+        &self,
+        url: &Url,
+        part_path: &Path,
+        on_progress: &mut F,
+    ) -> Result<Attempt>
+    where
+        F: FnMut(u64, Option<u64>) + Send,
+    {
+        let offset = tokio::fs::metadata(part_path)
             .await
-            .context("Failed to read download response")?;
+            .map(|m| m.len())
+            .unwrap_or(0);
+        let mut request = self.client.get(url.clone());
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                return Ok(Attempt::Retry(
+                    anyhow::Error::new(e).context("Failed to download file"),
+                ))
+            }
+        };
+
+        let status = response.status();
+        let (resume, total) = match status {
+            StatusCode::PARTIAL_CONTENT => match content_range(&response) {
+                Some((start, total)) if start == offset => (true, total),
+                _ => {
+                    // Not the bytes asked for: start over
+                    let _ = tokio::fs::remove_file(part_path).await;
+                    return Ok(Attempt::Retry(anyhow::anyhow!(
+                        "Server did not resume the download at byte {}",
+                        offset
+                    )));
+                }
+            },
+            StatusCode::RANGE_NOT_SATISFIABLE => {
+                // Either the partial file is already whole, or the file changed on the server
+                if content_range(&response).and_then(|(_, total)| total) == Some(offset) {
+                    return Ok(Attempt::Complete);
+                }
+                let _ = tokio::fs::remove_file(part_path).await;
+                return Ok(Attempt::Retry(anyhow::anyhow!(
+                    "Partial download no longer matches the file"
+                )));
+            }
+            // A full response: the server ignored the range, or there was nothing to resume
+            s if s.is_success() => (false, response.content_length()),
+            s if s.is_server_error() => {
+                return Ok(Attempt::Retry(anyhow::anyhow!(
+                    "Download failed with status: {}",
+                    s
+                )))
+            }
+            s => anyhow::bail!("Download failed with status: {}", s),
+        };
 
-        tokio::fs::write(output_path, bytes)
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resume)
+            .truncate(!resume)
+            .open(part_path)
             .await
-            .context("Failed to write file")?;
+            .context("Failed to create file")?;
+        let mut written = if resume { offset } else { 0 };
+        let expected = total.or_else(|| response.content_length().map(|len| written + len));
+        on_progress(written, expected);
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    file.write_all(&chunk)
+                        .await
+                        .context("Failed to write file")?;
+                    written += chunk.len() as u64;
+                    on_progress(written, expected);
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    file.flush().await.context("Failed to write file")?;
+                    return Ok(Attempt::Retry(
+                        anyhow::Error::new(e).context("Download interrupted"),
+                    ));
+                }
+            }
+        }
+        file.flush().await.context("Failed to write file")?;
+
+        match expected {
+            Some(expected) if written != expected => Ok(Attempt::Retry(anyhow::anyhow!(
+                "Incomplete download: {} of {} bytes",
+                written,
+                expected
+            ))),
+            _ if written == 0 => {
+                let _ = tokio::fs::remove_file(part_path).await;
+                anyhow::bail!("Downloaded file is empty")
+            }
+            _ => Ok(Attempt::Complete),
+        }
+    }
+}
+
+/// Details of a song page beyond its title and artist
+#[derive(Debug, Clone, Default)]
+pub struct SongPage {
+    pub download_url: Option<String>,
+    pub album: Option<String>,
+    pub year: Option<u32>,
+    pub cover_url: Option<String>,
+}
+
+/// Direct MP3 link in the page source, or the first download link or button
+fn find_download_url(html: &str, document: &Html) -> Result<Option<String>> {
+    let mp3_regex = Regex::new(r#"https?://[^"'\s]+\.mp3"#)?;
+    if let Some(captures) = mp3_regex.find(html) {
+        return Ok(Some(captures.as_str().to_string()));
+    }
+
+    let download_selector =
+        Selector::parse("a[href*='download'], a[href*='.mp3'], button[data-url], a[data-download]")
+            .unwrap_or_else(|_| Selector::parse("a").unwrap());
+
+    for element in document.select(&download_selector) {
+        if let Some(href) = element.value().attr("href") {
+            if href.contains(".mp3") || href.contains("download") {
+                let full_url = if href.starts_with("http") {
+                    href.to_string()
+                } else {
+                    format!("{}{}", BASE_URL, href)
+                };
+                return Ok(Some(full_url));
+            }
+        }
 
-        Ok(())
+        if let Some(data_url) = element.value().attr("data-url") {
+            if data_url.contains(".mp3") {
+                let full_url = if data_url.starts_with("http") {
+                    data_url.to_string()
+                } else {
+                    format!("{}{}", BASE_URL, data_url)
+                };
+                return Ok(Some(full_url));
+            }
+        }
     }
+
+    Ok(None)
+}
+
+fn meta_content(document: &Html, selector: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    document
+        .select(&selector)
+        .find_map(|element| element.value().attr("content"))
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty())
+}
+
+/// Value shown for a label like `Album:` in the page text, after the colon or in the next text
+fn labelled_value(lines: &[&str], labels: &[&str]) -> Option<String> {
+    lines.iter().enumerate().find_map(|(i, line)| {
+        let (label, value) = line.split_once(':').unwrap_or((line, ""));
+        if !labels.iter().any(|l| label.trim().eq_ignore_ascii_case(l)) {
+            return None;
+        }
+        let value = match value.trim() {
+            "" => lines.get(i + 1)?.trim(),
+            value => value,
+        };
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// Outcome of one pass at a download
+enum Attempt {
+    Complete,
+    Retry(anyhow::Error),
+}
+
+/// Remove the " mp3 songs", " mp3 song" and " mp3" markers the site puts in artist names
+pub fn strip_mp3_markers(name: &str) -> String {
+    name.replace(" mp3 songs", "")
+        .replace(" mp3 song", "")
+        .replace(" mp3", "")
+        .trim()
+        .to_string()
+}
+
+/// Where a download is written until it is complete
+pub fn part_path(output_path: &Path) -> PathBuf {
+    let mut name = output_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    output_path.with_file_name(name)
+}
+
+/// Start and total length from a `Content-Range: bytes a-b/total` (or `bytes */total`) header
+fn content_range(response: &reqwest::Response) -> Option<(u64, Option<u64>)> {
+    let value = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let start = match range {
+        "*" => 0,
+        range => range.split_once('-')?.0.trim().parse().ok()?,
+    };
+    Some((start, total.trim().parse().ok()))
 }
 
 impl Default for SongHubScraper {
@@ -499,3 +743,95 @@ impl Default for SongHubScraper {
         Self::new().expect("Failed to create scraper")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_server::{Reply, TestServer};
+
+    fn body() -> Vec<u8> {
+        (0..100_000u32).map(|i| (i * 7 % 253) as u8).collect()
+    }
+
+    fn paths() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("song.mp3");
+        let part = part_path(&output);
+        (dir, output, part)
+    }
+
+    async fn fetch(server: &TestServer, part: &Path) -> Result<Attempt> {
+        let url = Url::parse(&server.url).unwrap();
+        SongHubScraper::new()
+            .unwrap()
+            .fetch_part(&url, part, &mut |_, _| {})
+            .await
+    }
+
+    #[tokio::test]
+    async fn downloads_a_fresh_file() {
+        let (_dir, output, part) = paths();
+        let server = TestServer::start(body(), Reply::Ranges);
+        let scraper = SongHubScraper::new().unwrap();
+        scraper.download_song(&server.url, &output).await.unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), body());
+        assert!(!part.exists());
+        assert_eq!(server.ranges(), vec![None]);
+    }
+
+    #[tokio::test]
+    async fn resumes_from_the_part_file() {
+        let (_dir, output, part) = paths();
+        std::fs::write(&part, &body()[..40_000]).unwrap();
+        let server = TestServer::start(body(), Reply::Ranges);
+        let scraper = SongHubScraper::new().unwrap();
+        scraper.download_song(&server.url, &output).await.unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), body());
+        assert_eq!(server.ranges(), vec![Some("bytes=40000-".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn resume_at_the_wrong_byte_discards_the_part() {
+        let (_dir, _output, part) = paths();
+        std::fs::write(&part, &body()[..40_000]).unwrap();
+        let server = TestServer::start(body(), Reply::WrongStart);
+        assert!(matches!(fetch(&server, &part).await, Ok(Attempt::Retry(_))));
+        assert!(!part.exists());
+    }
+
+    #[tokio::test]
+    async fn unsatisfiable_range_on_a_whole_part_completes() {
+        let (_dir, output, part) = paths();
+        std::fs::write(&part, body()).unwrap();
+        let server = TestServer::start(body(), Reply::Ranges);
+        let scraper = SongHubScraper::new().unwrap();
+        scraper.download_song(&server.url, &output).await.unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), body());
+        assert_eq!(server.ranges(), vec![Some("bytes=100000-".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn ignored_range_rewrites_the_part() {
+        let (_dir, output, part) = paths();
+        std::fs::write(&part, vec![0xAA; 40_000]).unwrap();
+        let server = TestServer::start(body(), Reply::IgnoresRange);
+        let scraper = SongHubScraper::new().unwrap();
+        scraper.download_song(&server.url, &output).await.unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), body());
+    }
+
+    #[tokio::test]
+    async fn short_body_is_retried_without_moving_the_part() {
+        let (_dir, output, part) = paths();
+        let server = TestServer::start(body(), Reply::CutsOffAt(30_000));
+        assert!(matches!(fetch(&server, &part).await, Ok(Attempt::Retry(_))));
+        assert!(!output.exists());
+        assert_eq!(std::fs::read(&part).unwrap(), body()[..30_000]);
+
+        // The next pass picks up where the short one stopped
+        server.set_reply(Reply::Ranges);
+        assert!(matches!(fetch(&server, &part).await, Ok(Attempt::Complete)));
+        assert_eq!(std::fs::read(&part).unwrap(), body());
+        assert_eq!(server.ranges()[1], Some("bytes=30000-".to_string()));
+    }
+}
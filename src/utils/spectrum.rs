@@ -0,0 +1,327 @@
+// Spectrum Utility
+// Waveform overview and spectrogram of a decoded file, rendered as SVG for the file details
+
+use crate::utils::audio_decode::decode_file;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use std::f32::consts::PI;
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+pub const FFT_SIZE: usize = 2048;
+/// Spectra are taken every 1/8 s
+const SPECTRA_PER_SECOND: u32 = 8;
+/// Waveform min/max blocks are 10 ms long
+const WAVEFORM_BLOCKS_PER_SECOND: u32 = 100;
+/// Columns kept while decoding; neighbours are merged whenever twice this many pile up
+const WAVEFORM_COLUMNS: usize = 480;
+const SPECTROGRAM_COLUMNS: usize = 320;
+/// Frequency bands drawn in the spectrogram
+const SPECTROGRAM_ROWS: usize = 96;
+/// Dynamic range shown in the spectrogram, below the loudest band
+const SPECTROGRAM_RANGE_DB: f32 = 90.0;
+
+const WAVEFORM_COLOR: &str = "#6b9fff";
+/// Heat map from silence to full level
+const HEAT_STOPS: [(f32, [u8; 3]); 6] = [
+    (0.0, [8, 8, 20]),
+    (0.25, [60, 15, 110]),
+    (0.5, [180, 40, 90]),
+    (0.7, [240, 110, 30]),
+    (0.88, [250, 210, 60]),
+    (1.0, [255, 255, 230]),
+];
+
+#[derive(Debug, Clone)]
+pub struct SpectrumAnalysis {
+    pub sample_rate: u32,
+    /// Minimum and maximum mono sample of each waveform column
+    pub waveform: Vec<(f32, f32)>,
+    /// Power per FFT bin (`FFT_SIZE / 2` bins from 0 Hz to Nyquist) for each column
+    pub spectrogram: Vec<Vec<f32>>,
+    /// Mean power per FFT bin over the whole file
+    pub average: Vec<f32>,
+}
+
+impl SpectrumAnalysis {
+    pub fn nyquist_hz(&self) -> f32 {
+        self.sample_rate as f32 / 2.0
+    }
+
+    /// Frequency at the center of FFT bin `bin`
+    pub fn bin_hz(&self, bin: usize) -> f32 {
+        bin as f32 * self.sample_rate as f32 / FFT_SIZE as f32
+    }
+}
+
+/// Decode `path` and compute its waveform overview and spectrogram
+pub fn analyze_file(path: &Path) -> Result<SpectrumAnalysis, String> {
+    let mut analyzer: Option<Analyzer> = None;
+    let summary = decode_file(path, |samples, channels, sample_rate| {
+        analyzer
+            .get_or_insert_with(|| Analyzer::new(sample_rate))
+            .process(samples, channels as usize);
+    })?;
+    let analyzer = analyzer.ok_or_else(|| "No audio data decoded".to_string())?;
+    let spectra = analyzer.spectra.max(1) as f32;
+    Ok(SpectrumAnalysis {
+        sample_rate: summary.sample_rate,
+        waveform: analyzer.waveform.finish(),
+        spectrogram: analyzer.spectrogram.finish(),
+        average: analyzer.power_sum.iter().map(|p| p / spectra).collect(),
+    })
+}
+
+/// Column accumulator that halves its resolution whenever it holds twice `target` columns,
+/// so memory stays bounded without knowing the length up front
+struct Columns<T> {
+    target: usize,
+    columns: Vec<T>,
+    /// Inputs merged into each column
+    per_column: usize,
+    current: Option<T>,
+    in_current: usize,
+    merge: fn(&mut T, &T),
+}
+
+impl<T> Columns<T> {
+    fn new(target: usize, merge: fn(&mut T, &T)) -> Self {
+        Self {
+            target,
+            columns: Vec::new(),
+            per_column: 1,
+            current: None,
+            in_current: 0,
+            merge,
+        }
+    }
+
+    fn push(&mut self, value: T) {
+        match &mut self.current {
+            Some(current) => (self.merge)(current, &value),
+            None => self.current = Some(value),
+        }
+        self.in_current += 1;
+        if self.in_current < self.per_column {
+            return;
+        }
+        self.columns.extend(self.current.take());
+        self.in_current = 0;
+
+        if self.columns.len() >= self.target * 2 {
+            let mut merged = Vec::with_capacity(self.target);
+            let mut pairs = std::mem::take(&mut self.columns).into_iter();
+            while let Some(mut first) = pairs.next() {
+                if let Some(second) = pairs.next() {
+                    (self.merge)(&mut first, &second);
+                }
+                merged.push(first);
+            }
+            self.columns = merged;
+            self.per_column *= 2;
+        }
+    }
+
+    fn finish(mut self) -> Vec<T> {
+        self.columns.extend(self.current.take());
+        self.columns
+    }
+}
+
+struct Analyzer {
+    waveform: Columns<(f32, f32)>,
+    block_frames: usize,
+    block: Option<(f32, f32)>,
+    frames_in_block: usize,
+    spectrogram: Columns<Vec<f32>>,
+    power_sum: Vec<f32>,
+    spectra: usize,
+    hop_frames: usize,
+    frames_since_spectrum: usize,
+    /// Last `FFT_SIZE` mono samples, oldest first once `filled`
+    history: Vec<f32>,
+    history_pos: usize,
+    filled: bool,
+    fft: Fft,
+}
+
+impl Analyzer {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            waveform: Columns::new(WAVEFORM_COLUMNS, |a, b| {
+                a.0 = a.0.min(b.0);
+                a.1 = a.1.max(b.1);
+            }),
+            block_frames: (sample_rate / WAVEFORM_BLOCKS_PER_SECOND).max(1) as usize,
+            block: None,
+            frames_in_block: 0,
+            spectrogram: Columns::new(SPECTROGRAM_COLUMNS, |a, b| {
+                // Keep the loudest moment, so short bursts of treble stay visible
+                for (x, y) in a.iter_mut().zip(b) {
+                    *x = x.max(*y);
+                }
+            }),
+            power_sum: vec![0.0; FFT_SIZE / 2],
+            spectra: 0,
+            hop_frames: (sample_rate / SPECTRA_PER_SECOND).max(1) as usize,
+            frames_since_spectrum: 0,
+            history: vec![0.0; FFT_SIZE],
+            history_pos: 0,
+            filled: false,
+            fft: Fft::new(FFT_SIZE),
+        }
+    }
+
+    fn process(&mut self, samples: &[f32], channels: usize) {
+        for frame in samples.chunks_exact(channels.max(1)) {
+            let mono = frame.iter().sum::<f32>() / frame.len() as f32;
+
+            let block = self.block.get_or_insert((mono, mono));
+            block.0 = block.0.min(mono);
+            block.1 = block.1.max(mono);
+            self.frames_in_block += 1;
+            if self.frames_in_block == self.block_frames {
+                self.waveform.push(self.block.take().unwrap_or_default());
+                self.frames_in_block = 0;
+            }
+
+            self.history[self.history_pos] = mono;
+            self.history_pos = (self.history_pos + 1) % FFT_SIZE;
+            self.filled |= self.history_pos == 0;
+            self.frames_since_spectrum += 1;
+            if self.filled && self.frames_since_spectrum >= self.hop_frames {
+                self.frames_since_spectrum = 0;
+                let (older, newer) = self.history.split_at(self.history_pos);
+                let window: Vec<f32> = newer.iter().chain(older).copied().collect();
+                let spectrum = self.fft.power_spectrum(&window);
+                for (sum, power) in self.power_sum.iter_mut().zip(&spectrum) {
+                    *sum += power;
+                }
+                self.spectra += 1;
+                self.spectrogram.push(spectrum);
+            }
+        }
+    }
+}
+
+/// FFT of Hann-windowed blocks
+struct Fft {
+    plan: Arc<dyn rustfft::Fft<f32>>,
+    window: Vec<f32>,
+    buffer: Vec<Complex<f32>>,
+}
+
+impl Fft {
+    fn new(size: usize) -> Self {
+        let window = (0..size)
+            .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / (size - 1) as f32).cos())
+            .collect();
+        Self {
+            plan: FftPlanner::new().plan_fft_forward(size),
+            window,
+            buffer: vec![Complex::default(); size],
+        }
+    }
+
+    /// Power of bins `0..size / 2` of the windowed `samples`
+    fn power_spectrum(&mut self, samples: &[f32]) -> Vec<f32> {
+        for (bin, (sample, weight)) in self.buffer.iter_mut().zip(samples.iter().zip(&self.window))
+        {
+            *bin = Complex::new(sample * weight, 0.0);
+        }
+        self.plan.process(&mut self.buffer);
+        self.buffer
+            .iter()
+            .take(self.buffer.len() / 2)
+            .map(|bin| bin.norm_sqr())
+            .collect()
+    }
+}
+
+/// Waveform overview as an SVG to be stretched over its widget
+pub fn waveform_svg(waveform: &[(f32, f32)]) -> String {
+    let mut path = String::new();
+    for (x, (min, max)) in waveform.iter().enumerate() {
+        let top = 50.0 - max.clamp(-1.0, 1.0) * 50.0;
+        // Silence still shows as a thin line
+        let bottom = (50.0 - min.clamp(-1.0, 1.0) * 50.0).max(top + 0.5);
+        let _ = write!(path, "M{}.5 {:.1}V{:.1}", x, top, bottom);
+    }
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} 100" preserveAspectRatio="none"><path d="{}" stroke="{}" stroke-width="1" fill="none"/></svg>"#,
+        waveform.len().max(1),
+        path,
+        WAVEFORM_COLOR
+    )
+}
+
+/// Spectrogram as an SVG heat map, low frequencies at the bottom. The pixels are embedded as
+/// a PNG, which renders far faster than one shape per cell.
+pub fn spectrogram_svg(spectrogram: &[Vec<f32>]) -> String {
+    let width = spectrogram.len().max(1);
+    let bands: Vec<Vec<f32>> = spectrogram
+        .iter()
+        .map(|spectrum| band_db(spectrum))
+        .collect();
+    let loudest = bands
+        .iter()
+        .flatten()
+        .copied()
+        .fold(f32::NEG_INFINITY, f32::max);
+
+    let mut pixels = Vec::with_capacity(width * SPECTROGRAM_ROWS * 3);
+    for row in (0..SPECTROGRAM_ROWS).rev() {
+        for x in 0..width {
+            let value = bands
+                .get(x)
+                .map(|column| 1.0 - (loudest - column[row]) / SPECTROGRAM_RANGE_DB)
+                .unwrap_or(0.0);
+            pixels.extend_from_slice(&heat_color(value.clamp(0.0, 1.0)));
+        }
+    }
+
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" viewBox="0 0 {w} {h}" preserveAspectRatio="none"><image width="{w}" height="{h}" preserveAspectRatio="none" xlink:href="data:image/png;base64,{png}"/></svg>"#,
+        w = width,
+        h = SPECTROGRAM_ROWS,
+        png = STANDARD.encode(encode_png(width, SPECTROGRAM_ROWS, &pixels).unwrap_or_default())
+    )
+}
+
+/// Average FFT bins into `SPECTROGRAM_ROWS` bands, in dB
+fn band_db(spectrum: &[f32]) -> Vec<f32> {
+    (0..SPECTROGRAM_ROWS)
+        .map(|band| {
+            let start = band * spectrum.len() / SPECTROGRAM_ROWS;
+            let end = ((band + 1) * spectrum.len() / SPECTROGRAM_ROWS).max(start + 1);
+            let bins = &spectrum[start..end.min(spectrum.len())];
+            let mean = bins.iter().sum::<f32>() / bins.len().max(1) as f32;
+            10.0 * mean.max(1e-12).log10()
+        })
+        .collect()
+}
+
+fn heat_color(value: f32) -> [u8; 3] {
+    let upper = HEAT_STOPS
+        .iter()
+        .position(|(stop, _)| *stop >= value)
+        .unwrap_or(HEAT_STOPS.len() - 1)
+        .max(1);
+    let (low_stop, low) = HEAT_STOPS[upper - 1];
+    let (high_stop, high) = HEAT_STOPS[upper];
+    let t = ((value - low_stop) / (high_stop - low_stop)).clamp(0.0, 1.0);
+    let channel = |i: usize| (low[i] as f32 + (high[i] as f32 - low[i] as f32) * t).round() as u8;
+    [channel(0), channel(1), channel(2)]
+}
+
+fn encode_png(width: usize, height: usize, rgb: &[u8]) -> Result<Vec<u8>, png::EncodingError> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(rgb)?;
+    Ok(png)
+}
@@ -0,0 +1,140 @@
+// Test Server Utility
+// Minimal HTTP server on 127.0.0.1 for exercising range requests in tests
+
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// How the server answers
+#[derive(Debug, Clone, Copy)]
+pub enum Reply {
+    /// Answers `Range` with 206, or 416 past the end
+    Ranges,
+    /// Sends the whole body with 200 whatever the request
+    IgnoresRange,
+    /// Like `IgnoresRange`, but drops the connection after this many body bytes
+    CutsOffAt(usize),
+    /// Answers `Range` with a 206 of the whole body, starting at byte 0
+    WrongStart,
+}
+
+struct State {
+    body: Vec<u8>,
+    reply: Reply,
+    /// `Range` header of each request, in order
+    ranges: Vec<Option<String>>,
+}
+
+pub struct TestServer {
+    pub url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl TestServer {
+    /// Serve `body` on a background runtime until the process exits
+    pub fn start(body: Vec<u8>, reply: Reply) -> Self {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = format!("http://{}/song.mp3", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(State {
+            body,
+            reply,
+            ranges: Vec::new(),
+        }));
+
+        let shared = state.clone();
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            runtime.block_on(async move {
+                let listener = TcpListener::from_std(listener).unwrap();
+                while let Ok((socket, _)) = listener.accept().await {
+                    tokio::spawn(respond(socket, shared.clone()));
+                }
+            });
+        });
+        Self { url, state }
+    }
+
+    pub fn set_reply(&self, reply: Reply) {
+        self.state.lock().unwrap().reply = reply;
+    }
+
+    /// `Range` header of each request so far
+    pub fn ranges(&self) -> Vec<Option<String>> {
+        self.state.lock().unwrap().ranges.clone()
+    }
+}
+
+async fn respond(mut socket: TcpStream, state: Arc<Mutex<State>>) {
+    let mut request = Vec::new();
+    let mut byte = [0u8; 1];
+    while !request.ends_with(b"\r\n\r\n") {
+        if socket.read(&mut byte).await.unwrap_or(0) == 0 {
+            return;
+        }
+        request.push(byte[0]);
+    }
+    let request = String::from_utf8_lossy(&request).to_lowercase();
+    let range_header = request
+        .lines()
+        .find_map(|line| line.strip_prefix("range: "))
+        .map(|range| range.trim().to_string());
+
+    let (head, data) = {
+        let mut state = state.lock().unwrap();
+        state.ranges.push(range_header.clone());
+        let len = state.body.len();
+        let range = range_header
+            .as_deref()
+            .and_then(|range| range.strip_prefix("bytes="))
+            .and_then(|range| range.split_once('-'))
+            .map(|(start, end)| {
+                let start: usize = start.parse().unwrap();
+                let end = end.parse().map_or(len, |end: usize| end + 1);
+                (start, end.min(len))
+            });
+
+        let full = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n", len);
+        let (head, data) = match (state.reply, range) {
+            (Reply::Ranges, Some((start, _))) if start >= len => (
+                format!(
+                    "HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */{}\r\n\
+                     Content-Length: 0\r\n",
+                    len
+                ),
+                &state.body[..0],
+            ),
+            (Reply::Ranges, Some((start, end))) => (
+                format!(
+                    "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\n\
+                     Content-Length: {}\r\n",
+                    start,
+                    end - 1,
+                    len,
+                    end - start
+                ),
+                &state.body[start..end],
+            ),
+            (Reply::WrongStart, Some(_)) => (
+                format!(
+                    "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 0-{}/{}\r\n\
+                     Content-Length: {}\r\n",
+                    len - 1,
+                    len,
+                    len
+                ),
+                &state.body[..],
+            ),
+            (Reply::CutsOffAt(cut), _) => (full, &state.body[..cut]),
+            _ => (full, &state.body[..]),
+        };
+        (head, data.to_vec())
+    };
+    let head = format!("{}Connection: close\r\n\r\n", head);
+    let _ = socket.write_all(head.as_bytes()).await;
+    let _ = socket.write_all(&data).await;
+    let _ = socket.shutdown().await;
+}
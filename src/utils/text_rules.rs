@@ -0,0 +1,358 @@
+// Text Rules Utility
+// Bulk cleanup rules for tag text: case conversion, whitespace, suffixes, "feat." and regex replace
+
+use crate::utils::scraper::strip_mp3_markers;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Words kept lowercase by title case unless they start or end the value
+const SMALL_WORDS: [&str; 17] = [
+    "a", "an", "and", "as", "at", "but", "by", "feat.", "for", "in", "of", "on", "or", "the", "to",
+    "vs", "vs.",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CaseRule {
+    #[default]
+    Unchanged,
+    TitleCase,
+    SentenceCase,
+}
+
+impl CaseRule {
+    pub const ALL: [CaseRule; 3] = [
+        CaseRule::Unchanged,
+        CaseRule::TitleCase,
+        CaseRule::SentenceCase,
+    ];
+}
+
+impl fmt::Display for CaseRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaseRule::Unchanged => write!(f, "Keep Case"),
+            CaseRule::TitleCase => write!(f, "Title Case"),
+            CaseRule::SentenceCase => write!(f, "Sentence case"),
+        }
+    }
+}
+
+/// On/off rules that can be toggled individually
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextRule {
+    Trim,
+    CollapseSpaces,
+    UnderscoresToSpaces,
+    StripMp3Suffix,
+    NormalizeFeat,
+}
+
+/// Cleanup applied to the text fields written by `set_metadata` (title, artist, album, genre)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextRules {
+    pub trim: bool,
+    pub collapse_spaces: bool,
+    pub underscores_to_spaces: bool,
+    pub strip_mp3_suffix: bool,
+    pub normalize_feat: bool,
+    pub case: CaseRule,
+    /// Regular expression to search for; empty disables find/replace
+    pub find: String,
+    /// Replacement text, may use `$1`-style capture references
+    pub replace: String,
+}
+
+impl Default for TextRules {
+    fn default() -> Self {
+        Self {
+            trim: true,
+            collapse_spaces: true,
+            underscores_to_spaces: false,
+            strip_mp3_suffix: false,
+            normalize_feat: false,
+            case: CaseRule::Unchanged,
+            find: String::new(),
+            replace: String::new(),
+        }
+    }
+}
+
+impl TextRules {
+    pub fn is_enabled(&self, rule: TextRule) -> bool {
+        match rule {
+            TextRule::Trim => self.trim,
+            TextRule::CollapseSpaces => self.collapse_spaces,
+            TextRule::UnderscoresToSpaces => self.underscores_to_spaces,
+            TextRule::StripMp3Suffix => self.strip_mp3_suffix,
+            TextRule::NormalizeFeat => self.normalize_feat,
+        }
+    }
+
+    pub fn set_enabled(&mut self, rule: TextRule, enabled: bool) {
+        match rule {
+            TextRule::Trim => self.trim = enabled,
+            TextRule::CollapseSpaces => self.collapse_spaces = enabled,
+            TextRule::UnderscoresToSpaces => self.underscores_to_spaces = enabled,
+            TextRule::StripMp3Suffix => self.strip_mp3_suffix = enabled,
+            TextRule::NormalizeFeat => self.normalize_feat = enabled,
+        }
+    }
+
+    /// Compile the rules once so they can be applied to many values
+    pub fn compile(&self) -> Result<CompiledTextRules, String> {
+        let find = if self.find.is_empty() {
+            None
+        } else {
+            Some(Regex::new(&self.find).map_err(|e| format!("Invalid find pattern: {}", e))?)
+        };
+        let feat = if self.normalize_feat {
+            Some(Regex::new(r"(?i)\b(?:featuring|feat\.?|ft\.?)(\s+)").map_err(|e| e.to_string())?)
+        } else {
+            None
+        };
+        Ok(CompiledTextRules {
+            rules: self.clone(),
+            find,
+            feat,
+        })
+    }
+}
+
+pub struct CompiledTextRules {
+    rules: TextRules,
+    find: Option<Regex>,
+    feat: Option<Regex>,
+}
+
+impl CompiledTextRules {
+    pub fn apply(&self, value: &str) -> String {
+        let rules = &self.rules;
+        let mut value = value.to_string();
+
+        if rules.underscores_to_spaces {
+            value = value.replace('_', " ");
+        }
+        // Same markers the scraper removes from artist names
+        if rules.strip_mp3_suffix {
+            value = strip_mp3_markers(&value);
+        }
+        if let Some(find) = &self.find {
+            value = find
+                .replace_all(&value, rules.replace.as_str())
+                .into_owned();
+        }
+        if let Some(feat) = &self.feat {
+            value = feat.replace_all(&value, "feat.$1").into_owned();
+        }
+        value = match rules.case {
+            CaseRule::Unchanged => value,
+            CaseRule::TitleCase => title_case(&value),
+            CaseRule::SentenceCase => sentence_case(&value),
+        };
+        if rules.collapse_spaces {
+            value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        if rules.trim {
+            value = value.trim().to_string();
+        }
+        value
+    }
+}
+
+/// Capitalize every word, keeping acronyms like "AC/DC" and small words like "of" as they are.
+/// All-caps values are treated as shouting and lowercased first.
+fn title_case(value: &str) -> String {
+    let shouting = !value.chars().any(char::is_lowercase);
+    let words: Vec<&str> = value.split(' ').collect();
+    let last = words.len().saturating_sub(1);
+
+    words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| {
+            let is_acronym = !shouting
+                && word.chars().any(char::is_alphabetic)
+                && !word.chars().any(char::is_lowercase);
+            if is_acronym {
+                return word.to_string();
+            }
+            let lower = word.to_lowercase();
+            let bare = lower.trim_start_matches(|c: char| !c.is_alphanumeric());
+            if i != 0 && i != last && SMALL_WORDS.contains(&bare) {
+                lower
+            } else {
+                capitalize_first(&lower)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn sentence_case(value: &str) -> String {
+    let lower = value.to_lowercase();
+    let sentence = capitalize_first(&lower);
+    // A lone "i" is always a capital
+    sentence
+        .split(' ')
+        .map(|word| if word == "i" { "I" } else { word })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Uppercase the first letter, skipping leading punctuation such as "(" or "'"
+fn capitalize_first(word: &str) -> String {
+    match word.char_indices().find(|(_, c)| c.is_alphanumeric()) {
+        Some((i, c)) => {
+            let mut result = String::with_capacity(word.len());
+            result.push_str(&word[..i]);
+            result.extend(c.to_uppercase());
+            result.push_str(&word[i + c.len_utf8()..]);
+            result
+        }
+        None => word.to_string(),
+    }
+}
+
+/// Tag text fields the rules run on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagField {
+    Title,
+    Artist,
+    Album,
+    Genre,
+}
+
+impl TagField {
+    pub const ALL: [TagField; 4] = [
+        TagField::Title,
+        TagField::Artist,
+        TagField::Album,
+        TagField::Genre,
+    ];
+}
+
+impl fmt::Display for TagField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagField::Title => write!(f, "Title"),
+            TagField::Artist => write!(f, "Artist"),
+            TagField::Album => write!(f, "Album"),
+            TagField::Genre => write!(f, "Genre"),
+        }
+    }
+}
+
+/// A single field whose value the rules would change
+#[derive(Debug, Clone)]
+pub struct FieldChange {
+    pub field: TagField,
+    pub before: String,
+    pub after: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(rules: TextRules, value: &str) -> String {
+        rules.compile().unwrap().apply(value)
+    }
+
+    fn with_case(case: CaseRule) -> TextRules {
+        TextRules {
+            case,
+            ..TextRules::default()
+        }
+    }
+
+    #[test]
+    fn title_case_keeps_acronyms_and_small_words() {
+        let rules = with_case(CaseRule::TitleCase);
+        assert_eq!(
+            apply(rules.clone(), "back in black by AC/DC"),
+            "Back in Black by AC/DC"
+        );
+        assert_eq!(
+            apply(rules.clone(), "the end of the road"),
+            "The End of the Road"
+        );
+        // All caps is shouting, not an acronym
+        assert_eq!(apply(rules.clone(), "LOVE ME DO"), "Love Me Do");
+        assert_eq!(apply(rules, "(don't) stop me now"), "(Don't) Stop Me Now");
+    }
+
+    #[test]
+    fn sentence_case_lowercases_all_but_the_first_letter() {
+        let rules = with_case(CaseRule::SentenceCase);
+        assert_eq!(apply(rules.clone(), "SOMEBODY TO LOVE"), "Somebody to love");
+        assert_eq!(apply(rules, "what i Want"), "What I want");
+    }
+
+    #[test]
+    fn feat_variants_become_feat() {
+        let rules = TextRules {
+            normalize_feat: true,
+            ..TextRules::default()
+        };
+        assert_eq!(
+            apply(rules.clone(), "Song ft. Someone"),
+            "Song feat. Someone"
+        );
+        assert_eq!(
+            apply(rules.clone(), "Song Featuring Someone"),
+            "Song feat. Someone"
+        );
+        assert_eq!(
+            apply(rules.clone(), "Song (FEAT Someone)"),
+            "Song (feat. Someone)"
+        );
+        // Only whole words
+        assert_eq!(apply(rules, "Left Feet"), "Left Feet");
+    }
+
+    #[test]
+    fn mp3_markers_match_the_scraper() {
+        let rules = TextRules {
+            strip_mp3_suffix: true,
+            ..TextRules::default()
+        };
+        for value in [
+            "Artist mp3 songs",
+            "Artist mp3 song",
+            "Artist mp3",
+            "Artist",
+        ] {
+            assert_eq!(apply(rules.clone(), value), "Artist");
+        }
+        assert_eq!(apply(rules.clone(), "Artist mp3 Live"), "Artist Live");
+        assert_eq!(
+            apply(rules, "Artist mp3 songs"),
+            strip_mp3_markers("Artist mp3 songs")
+        );
+    }
+
+    #[test]
+    fn regex_replace_uses_capture_groups() {
+        let rules = TextRules {
+            find: r"^(\d+)\s*-\s*".to_string(),
+            replace: "$1. ".to_string(),
+            ..TextRules::default()
+        };
+        assert_eq!(apply(rules, "01 - Intro"), "01. Intro");
+        let invalid = TextRules {
+            find: "(".to_string(),
+            ..TextRules::default()
+        };
+        assert!(invalid.compile().is_err());
+    }
+
+    #[test]
+    fn defaults_only_tidy_whitespace() {
+        assert_eq!(
+            apply(TextRules::default(), "  Some   Song_Name "),
+            "Some Song_Name"
+        );
+    }
+}
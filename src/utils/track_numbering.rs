@@ -0,0 +1,316 @@
+// Track Numbering Utility
+// Assigns track numbers and totals to a set of files, with disc numbers for CD1/CD2 folders
+
+use crate::config::SUPPORTED_FORMATS;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Order in which files of one disc receive their track numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrackOrder {
+    /// The order of the file list. The list has no sort of its own: it shows files in the
+    /// order the folder scan returned them (or they were picked), so this is both the
+    /// current sort and the scan order.
+    #[default]
+    ListOrder,
+    /// Natural sort on the file name, so `2 - b.mp3` comes before `10 - a.mp3`
+    FileName,
+}
+
+impl TrackOrder {
+    pub const ALL: [TrackOrder; 2] = [TrackOrder::ListOrder, TrackOrder::FileName];
+}
+
+impl fmt::Display for TrackOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrackOrder::ListOrder => write!(f, "List Order"),
+            TrackOrder::FileName => write!(f, "File Name"),
+        }
+    }
+}
+
+/// Numbers assigned to a single file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackNumber {
+    pub track: u32,
+    pub track_total: u32,
+    /// Only set when the album spans disc folders
+    pub disc: Option<u32>,
+    pub disc_total: Option<u32>,
+}
+
+/// Disc number of a folder named like `CD1`, `CD 2`, `Disc 3` or `disk-4`
+pub fn disc_number(dir: &Path) -> Option<u32> {
+    let name = dir.file_name()?.to_string_lossy().trim().to_lowercase();
+    let rest = ["disc", "disk", "cd"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))?;
+    let digits = rest.trim_start_matches([' ', '-', '_', '.']);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// The folder that represents the album: the parent of a disc folder, else the file's folder
+pub fn album_dir(file: &Path) -> Option<&Path> {
+    let dir = file.parent()?;
+    match (disc_number(dir), dir.parent()) {
+        (Some(_), Some(parent)) => Some(parent),
+        _ => Some(dir),
+    }
+}
+
+/// Audio files on disk that belong to the album in `dir`: its own and those in its disc folders
+pub fn album_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return files;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            if disc_number(&path).is_some() {
+                if let Ok(disc) = std::fs::read_dir(&path) {
+                    files.extend(
+                        disc.flatten()
+                            .map(|entry| entry.path())
+                            .filter(|p| is_audio_file(p)),
+                    );
+                }
+            }
+        } else if is_audio_file(&path) {
+            files.push(path);
+        }
+    }
+    files
+}
+
+fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| SUPPORTED_FORMATS.contains(&ext.as_str()))
+}
+
+/// Number `files` per album folder, restarting at 1 on every disc.
+///
+/// Files in `CD1`/`CD2`-style subfolders of the same album also get their disc number
+/// and the album's disc total; files directly in an album folder get no disc values.
+pub fn assign_track_numbers(files: &[PathBuf], order: TrackOrder) -> Vec<(PathBuf, TrackNumber)> {
+    // album folder -> disc number (0 = not a disc folder) -> files in list order
+    let mut albums: Vec<(PathBuf, BTreeMap<u32, Vec<PathBuf>>)> = Vec::new();
+    for file in files {
+        let Some(album) = album_dir(file) else {
+            continue;
+        };
+        let disc = file.parent().and_then(disc_number).unwrap_or(0);
+        let index = match albums.iter().position(|(dir, _)| dir == album) {
+            Some(index) => index,
+            None => {
+                albums.push((album.to_path_buf(), BTreeMap::new()));
+                albums.len() - 1
+            }
+        };
+        albums[index].1.entry(disc).or_default().push(file.clone());
+    }
+
+    let mut numbered = Vec::with_capacity(files.len());
+    for (_, discs) in albums {
+        let disc_total = discs.keys().filter(|d| **d > 0).max().copied();
+        for (disc, mut disc_files) in discs {
+            if order == TrackOrder::FileName {
+                disc_files.sort_by(|a, b| natural_cmp(&file_name(a), &file_name(b)));
+            }
+            let track_total = disc_files.len() as u32;
+            for (i, file) in disc_files.into_iter().enumerate() {
+                numbered.push((
+                    file,
+                    TrackNumber {
+                        track: i as u32 + 1,
+                        track_total,
+                        disc: (disc > 0).then_some(disc),
+                        disc_total: disc_total.filter(|_| disc > 0),
+                    },
+                ));
+            }
+        }
+    }
+    numbered
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Compare strings treating runs of digits as numbers
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = chars.peek().copied().filter(char::is_ascii_digit) {
+                        digits.push(c);
+                        chars.next();
+                    }
+                    digits
+                };
+                let x = take_number(&mut a);
+                let y = take_number(&mut b);
+                let x_trimmed = x.trim_start_matches('0');
+                let y_trimmed = y.trim_start_matches('0');
+                let ordering = x_trimmed
+                    .len()
+                    .cmp(&y_trimmed.len())
+                    .then_with(|| x_trimmed.cmp(y_trimmed));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(names: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+        names.sort_by(|a, b| natural_cmp(a, b));
+        names
+    }
+
+    #[test]
+    fn natural_cmp_orders_numbers_by_value() {
+        assert_eq!(
+            sorted(&["10 - a.mp3", "2 - b.mp3", "1 - c.mp3"]),
+            ["1 - c.mp3", "2 - b.mp3", "10 - a.mp3"]
+        );
+        assert_eq!(
+            sorted(&["track 10", "track 9", "track 1"]),
+            ["track 1", "track 9", "track 10"]
+        );
+        assert_eq!(natural_cmp("007", "7"), Ordering::Equal);
+        assert_eq!(natural_cmp("02 b", "2 a"), Ordering::Greater);
+        // Longer than any integer type still compares by value
+        assert_eq!(
+            natural_cmp("99999999999999999999999", "100000000000000000000000"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn natural_cmp_falls_back_to_characters() {
+        assert_eq!(natural_cmp("a", "b"), Ordering::Less);
+        assert_eq!(natural_cmp("ab", "a"), Ordering::Greater);
+        assert_eq!(natural_cmp("a1", "a"), Ordering::Greater);
+        assert_eq!(natural_cmp("1a", "a"), Ordering::Less);
+        assert_eq!(natural_cmp("", ""), Ordering::Equal);
+    }
+
+    #[test]
+    fn disc_number_reads_disc_folder_names() {
+        for (name, disc) in [
+            ("CD1", 1),
+            ("cd 2", 2),
+            ("Disc 3", 3),
+            ("disk-4", 4),
+            ("CD_05", 5),
+            (" Disc.12 ", 12),
+        ] {
+            assert_eq!(disc_number(Path::new(name)), Some(disc), "{}", name);
+        }
+        assert_eq!(disc_number(Path::new("Album/CD2")), Some(2));
+    }
+
+    #[test]
+    fn disc_number_ignores_other_folders() {
+        for name in ["CD", "CDs", "Disco 2", "CD1 Bonus", "Album", "2", "Disc -"] {
+            assert_eq!(disc_number(Path::new(name)), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn disc_folders_number_per_disc_under_one_album() {
+        let files: Vec<PathBuf> = ["A/CD2/01.mp3", "A/CD1/02.mp3", "A/CD1/01.mp3", "B/x.mp3"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        let numbered = assign_track_numbers(&files, TrackOrder::FileName);
+        let number = |file: &str| {
+            numbered
+                .iter()
+                .find(|(f, _)| f == Path::new(file))
+                .map(|(_, n)| *n)
+                .unwrap()
+        };
+        assert_eq!(
+            number("A/CD1/02.mp3"),
+            TrackNumber {
+                track: 2,
+                track_total: 2,
+                disc: Some(1),
+                disc_total: Some(2),
+            }
+        );
+        assert_eq!(
+            number("A/CD2/01.mp3"),
+            TrackNumber {
+                track: 1,
+                track_total: 1,
+                disc: Some(2),
+                disc_total: Some(2),
+            }
+        );
+        assert_eq!(
+            number("B/x.mp3"),
+            TrackNumber {
+                track: 1,
+                track_total: 1,
+                disc: None,
+                disc_total: None,
+            }
+        );
+        assert_eq!(album_dir(Path::new("A/CD1/01.mp3")), Some(Path::new("A")));
+    }
+
+    #[test]
+    fn album_files_include_disc_folders_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let album = dir.path();
+        for name in [
+            "01.mp3",
+            "cover.jpg",
+            "CD1/01.flac",
+            "CD2/01.mp3",
+            "Scans/02.mp3",
+        ] {
+            let path = album.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+        let mut files = album_files(album);
+        files.sort();
+        assert_eq!(
+            files,
+            ["01.mp3", "CD1/01.flac", "CD2/01.mp3"].map(|name| album.join(name))
+        );
+    }
+}
@@ -1,15 +1,26 @@
 use crate::message::Message;
-use crate::model::{AppState, Screen};
+use crate::model::{AlbumGroup, AppState, EditorPanel, Screen};
 use crate::theme::{
     get_colors, CardStyle, DangerButtonStyle, FileItemStyle, HeaderStyle, PlayButtonStyle,
     PrimaryButtonStyle, ProcessingButtonStyle, SecondaryButtonStyle, TextInputStyle, ThemeMode,
     ToggleStyle, TransparentButtonStyle, WarningButtonStyle,
 };
+use crate::utils::audio_player::{PlaybackState, PlayerSource};
+use crate::utils::download_queue::{DownloadState, MAX_ATTEMPTS};
+use crate::utils::genres::{find_genre_variants, suggest_genres};
+use crate::utils::loudness::REFERENCE_LUFS;
+use crate::utils::lyrics::{current_line, format_timestamp};
+use crate::utils::music_downloader::MusicDownloaderState;
+use crate::utils::text_rules::{CaseRule, TextRule};
+use crate::utils::track_numbering::TrackOrder;
 use iced::widget::{
-    button, checkbox, column, container, row, scrollable, text, text_input, Column, Space,
+    button, checkbox, column, container, pick_list, progress_bar, row, scrollable, slider, svg,
+    text, text_editor, text_input, Column, Space,
 };
-use iced::{Alignment, Color, Element, Length, Theme};
+use iced::{Alignment, Color, ContentFit, Element, Length, Theme};
 use iced_aw::core::icons::bootstrap::{icon_to_text, Bootstrap};
+use std::path::Path;
+use std::time::Duration;
 
 pub fn build_view(state: &AppState, theme_mode: ThemeMode) -> Element<'_, Message> {
     let colors = get_colors(theme_mode);
@@ -19,6 +30,7 @@ pub fn build_view(state: &AppState, theme_mode: ThemeMode) -> Element<'_, Messag
         Screen::MetadataEditor => build_metadata_editor(state, theme_mode),
         Screen::MusicDownloader => build_music_downloader(state, theme_mode),
         Screen::AudioConverter => build_audio_converter(state, theme_mode),
+        Screen::FolderWatcher => build_folder_watcher(state, theme_mode),
     };
 
     let bg_primary = colors.bg_primary;
@@ -103,7 +115,7 @@ fn build_home_screen(theme_mode: ThemeMode) -> Element<'static, Message> {
             Screen::MetadataEditor,
             theme_mode,
         ),
-        Space::with_width(20),
+        Space::with_width(16),
         build_utility_card(
             Bootstrap::CloudArrowDown,
             "Music Downloader",
@@ -112,7 +124,7 @@ fn build_home_screen(theme_mode: ThemeMode) -> Element<'static, Message> {
             Screen::MusicDownloader,
             theme_mode,
         ),
-        Space::with_width(20),
+        Space::with_width(16),
         build_utility_card(
             Bootstrap::ArrowRepeat,
             "Audio Converter",
@@ -121,6 +133,15 @@ fn build_home_screen(theme_mode: ThemeMode) -> Element<'static, Message> {
             Screen::AudioConverter,
             theme_mode,
         ),
+        Space::with_width(16),
+        build_utility_card(
+            Bootstrap::Eye,
+            "Folder Watcher",
+            "Auto-tag new audio files as they arrive in a folder",
+            Color::from_rgb(0.7, 0.5, 0.95),
+            Screen::FolderWatcher,
+            theme_mode,
+        ),
     ]
     .spacing(0)
     .align_items(Alignment::Center);
@@ -137,7 +158,7 @@ fn build_home_screen(theme_mode: ThemeMode) -> Element<'static, Message> {
         container(utility_cards)
             .width(Length::Fill)
             .center_x()
-            .padding([0, 40, 0, 40]),
+            .padding([0, 20, 0, 20]),
         Space::with_height(20),
         hint_text,
         Space::with_height(Length::Fill),
@@ -193,8 +214,8 @@ fn build_utility_card(
             .align_items(Alignment::Center)
             .width(Length::Fill),
         )
-        .width(Length::Fixed(220.0))
-        .padding([24, 20, 24, 20])
+        .width(Length::Fixed(180.0))
+        .padding([24, 16, 24, 16])
         .style(iced::theme::Container::Custom(Box::new(CardStyle {
             mode: theme_mode,
         }))),
@@ -371,7 +392,16 @@ fn build_metadata_editor(state: &AppState, theme_mode: ThemeMode) -> Element<'_,
         theme_mode,
     );
     let file_panel = build_file_panel(state, theme_mode);
-    let metadata_panel = build_metadata_panel(state, theme_mode);
+    let metadata_panel = match state.editor_panel {
+        EditorPanel::FileDetails => build_metadata_panel(state, theme_mode),
+        EditorPanel::TextCleanup => build_text_rules_panel(state, theme_mode),
+        EditorPanel::Presets => build_presets_panel(state, theme_mode),
+        EditorPanel::Genres => build_genres_panel(state, theme_mode),
+        EditorPanel::Loudness => build_loudness_panel(state, theme_mode),
+        EditorPanel::Integrity => build_integrity_panel(state, theme_mode),
+        EditorPanel::Quality => build_quality_panel(state, theme_mode),
+        EditorPanel::Lyrics => build_lyrics_panel(state, theme_mode),
+    };
     let edit_panel = build_edit_panel(state, theme_mode);
 
     let main_content = row![
@@ -484,96 +514,29 @@ fn build_file_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static,
     } else {
         let mut file_column = Column::new().spacing(3).width(Length::Fill);
 
-        for (index, file) in state.files.iter().enumerate() {
-            let file_name = file
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("Unknown")
-                .to_string();
-
-            let max_length = 32;
-            let display_name = if file_name.len() > max_length {
-                format!("{}...", &file_name[..max_length.saturating_sub(3)])
-            } else {
-                file_name
-            };
-
-            let is_selected = state.selected_file_index == Some(index);
-            let item_bg = if is_selected {
-                if theme_mode == ThemeMode::Dark {
-                    Color::from_rgba(0.45, 0.65, 1.0, 0.18)
-                } else {
-                    Color::from_rgba(0.4, 0.6, 1.0, 0.18)
+        if state.group_by_album {
+            for (group_index, indices) in state.group_file_indices().into_iter().enumerate() {
+                if indices.is_empty() {
+                    continue;
                 }
-            } else {
-                colors.bg_secondary
-            };
-
-            let file_item = container(
-                row![
-                    button(
-                        container(
-                            text(display_name)
-                                .size(13)
-                                .style(iced::theme::Text::Color(if is_selected {
-                                    colors.cosmic_accent
-                                } else {
-                                    colors.text_primary
-                                }))
-                                .width(Length::Fill)
-                                .shaping(iced::widget::text::Shaping::Advanced)
-                        )
-                        .width(Length::Fill)
-                        .padding([6, 10, 6, 10])
-                        .clip(true)
-                    )
-                    .style(iced::theme::Button::Custom(Box::new(
-                        TransparentButtonStyle {
-                            mode: theme_mode,
-                            is_selected
-                        }
-                    )))
-                    .on_press(Message::FileSelected(index))
-                    .width(Length::Fill),
-                    button(
-                        container(text("×").size(16).width(Length::Shrink))
+                file_column = file_column.push(build_album_group_header(
+                    state,
+                    group_index,
+                    &indices,
+                    theme_mode,
+                ));
+                for index in indices {
+                    file_column = file_column.push(
+                        container(build_file_item(state, index, theme_mode))
                             .width(Length::Fill)
-                            .height(Length::Fill)
-                            .center_x()
-                            .center_y()
-                    )
-                    .style(iced::theme::Button::Custom(Box::new(WarningButtonStyle {
-                        mode: theme_mode
-                    })))
-                    .on_press(Message::RemoveFile(index))
-                    .padding(0)
-                    .width(Length::Fixed(24.0))
-                    .height(Length::Fixed(24.0)),
-                ]
-                .spacing(4)
-                .align_items(Alignment::Center)
-                .width(Length::Fill),
-            )
-            .width(Length::Fill)
-            .padding([0, 4, 0, 0])
-            .style(iced::theme::Container::Custom(Box::new(
-                move |_theme: &Theme| iced::widget::container::Appearance {
-                    text_color: Some(colors.text_secondary),
-                    background: Some(iced::Background::Color(item_bg)),
-                    border: iced::Border {
-                        color: if is_selected {
-                            colors.cosmic_accent
-                        } else {
-                            Color::TRANSPARENT
-                        },
-                        width: if is_selected { 1.0 } else { 0.0 },
-                        radius: 4.0.into(),
-                    },
-                    shadow: Default::default(),
-                },
-            )));
-
-            file_column = file_column.push(file_item);
+                            .padding([0, 0, 0, 14]),
+                    );
+                }
+            }
+        } else {
+            for index in 0..state.files.len() {
+                file_column = file_column.push(build_file_item(state, index, theme_mode));
+            }
         }
 
         scrollable(
@@ -593,10 +556,22 @@ fn build_file_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static,
                     .size(14)
                     .style(iced::theme::Text::Color(colors.text_primary)),
                 Space::with_width(Length::Fill),
+                text("Albums")
+                    .size(11)
+                    .style(iced::theme::Text::Color(colors.text_secondary)),
+                checkbox("", state.group_by_album)
+                    .on_toggle(Message::GroupByAlbumToggled)
+                    .style(iced::theme::Checkbox::Custom(Box::new(ToggleStyle {
+                        mode: theme_mode
+                    }))),
                 container(
-                    text(format!("{}", state.files.len()))
-                        .size(11)
-                        .style(iced::theme::Text::Color(colors.text_secondary))
+                    text(if state.checked_files.is_empty() {
+                        format!("{}", state.files.len())
+                    } else {
+                        format!("{} / {}", state.checked_files.len(), state.files.len())
+                    })
+                    .size(11)
+                    .style(iced::theme::Text::Color(colors.text_secondary))
                 )
                 .padding([3, 10])
                 .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
@@ -626,7 +601,9 @@ fn build_file_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static,
             ]
             .spacing(0)
             .width(Length::Fill),
-            Space::with_height(10),
+            Space::with_height(8),
+            build_selection_bar(state, theme_mode),
+            Space::with_height(8),
             container(file_list)
                 .width(Length::Fill)
                 .height(Length::Fill)
@@ -659,6 +636,244 @@ fn build_file_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static,
     .into()
 }
 
+fn build_file_item(
+    state: &AppState,
+    index: usize,
+    theme_mode: ThemeMode,
+) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let file = &state.files[index];
+    let file_name = file
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("Unknown")
+        .to_string();
+
+    let max_length = 32;
+    let display_name = if file_name.len() > max_length {
+        format!("{}...", &file_name[..max_length.saturating_sub(3)])
+    } else {
+        file_name
+    };
+
+    let is_selected = state.selected_file_index == Some(index);
+    let is_checked = state.checked_files.contains(&index);
+    let item_bg = if is_selected {
+        if theme_mode == ThemeMode::Dark {
+            Color::from_rgba(0.45, 0.65, 1.0, 0.18)
+        } else {
+            Color::from_rgba(0.4, 0.6, 1.0, 0.18)
+        }
+    } else {
+        colors.bg_secondary
+    };
+
+    // Quality check column, filled in once the file has been analyzed
+    let quality_badge: Element<'static, Message> = match state.quality_results.get(file) {
+        Some(result) => {
+            let (label, color) = match result {
+                Ok(report) if report.is_suspect() => (report.badge(), colors.warning),
+                Ok(report) => (report.badge(), colors.text_disabled),
+                Err(_) => ("?".to_string(), colors.error),
+            };
+            text(label)
+                .size(10)
+                .style(iced::theme::Text::Color(color))
+                .width(Length::Fixed(40.0))
+                .horizontal_alignment(iced::alignment::Horizontal::Right)
+                .into()
+        }
+        None => Space::with_width(0).into(),
+    };
+
+    container(
+        row![
+            checkbox("", is_checked)
+                .on_toggle(move |_| Message::ToggleFileChecked(index))
+                .style(iced::theme::Checkbox::Custom(Box::new(ToggleStyle {
+                    mode: theme_mode
+                }))),
+            button(
+                container(
+                    text(display_name)
+                        .size(13)
+                        .style(iced::theme::Text::Color(if is_selected {
+                            colors.cosmic_accent
+                        } else {
+                            colors.text_primary
+                        }))
+                        .width(Length::Fill)
+                        .shaping(iced::widget::text::Shaping::Advanced)
+                )
+                .width(Length::Fill)
+                .padding([6, 10, 6, 10])
+                .clip(true)
+            )
+            .style(iced::theme::Button::Custom(Box::new(
+                TransparentButtonStyle {
+                    mode: theme_mode,
+                    is_selected
+                }
+            )))
+            .on_press(Message::FileSelected(index))
+            .width(Length::Fill),
+            quality_badge,
+            button(
+                container(text("×").size(16).width(Length::Shrink))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .center_x()
+                    .center_y()
+            )
+            .style(iced::theme::Button::Custom(Box::new(WarningButtonStyle {
+                mode: theme_mode
+            })))
+            .on_press(Message::RemoveFile(index))
+            .padding(0)
+            .width(Length::Fixed(24.0))
+            .height(Length::Fixed(24.0)),
+        ]
+        .spacing(4)
+        .align_items(Alignment::Center)
+        .width(Length::Fill),
+    )
+    .width(Length::Fill)
+    .padding([0, 4, 0, 6])
+    .style(iced::theme::Container::Custom(Box::new(
+        move |_theme: &Theme| iced::widget::container::Appearance {
+            text_color: Some(colors.text_secondary),
+            background: Some(iced::Background::Color(item_bg)),
+            border: iced::Border {
+                color: if is_selected {
+                    colors.cosmic_accent
+                } else {
+                    Color::TRANSPARENT
+                },
+                width: if is_selected { 1.0 } else { 0.0 },
+                radius: 4.0.into(),
+            },
+            shadow: Default::default(),
+        },
+    )))
+    .into()
+}
+
+fn build_album_group_header(
+    state: &AppState,
+    group_index: usize,
+    indices: &[usize],
+    theme_mode: ThemeMode,
+) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let group = &state.album_groups[group_index];
+    let is_selected = state.selected_group == Some(group_index);
+    let all_checked = indices.iter().all(|i| state.checked_files.contains(i));
+
+    let summary = [&group.artist, &group.album, &group.year]
+        .into_iter()
+        .filter_map(|v| v.as_deref())
+        .filter(|v| !v.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join(" • ");
+
+    container(
+        row![
+            checkbox("", all_checked)
+                .on_toggle(move |_| Message::ToggleGroupChecked(group_index))
+                .style(iced::theme::Checkbox::Custom(Box::new(ToggleStyle {
+                    mode: theme_mode
+                }))),
+            button(
+                row![
+                    icon_to_text(Bootstrap::FolderFill)
+                        .size(13.0)
+                        .style(iced::theme::Text::Color(Color::from_rgb(0.95, 0.75, 0.3))),
+                    Space::with_width(8),
+                    column![
+                        text(group.display_name())
+                            .size(13)
+                            .style(iced::theme::Text::Color(if is_selected {
+                                colors.cosmic_accent
+                            } else {
+                                colors.text_primary
+                            }))
+                            .shaping(iced::widget::text::Shaping::Advanced),
+                        text(if summary.is_empty() {
+                            format!("{} file(s)", indices.len())
+                        } else {
+                            format!("{} file(s) • {}", indices.len(), summary)
+                        })
+                        .size(11)
+                        .style(iced::theme::Text::Color(colors.text_disabled))
+                        .shaping(iced::widget::text::Shaping::Advanced),
+                    ]
+                    .spacing(2)
+                    .width(Length::Fill),
+                ]
+                .spacing(0)
+                .align_items(Alignment::Center)
+            )
+            .style(iced::theme::Button::Custom(Box::new(
+                TransparentButtonStyle {
+                    mode: theme_mode,
+                    is_selected
+                }
+            )))
+            .on_press(Message::SelectAlbumGroup(group_index))
+            .padding([6, 10])
+            .width(Length::Fill),
+        ]
+        .spacing(4)
+        .align_items(Alignment::Center)
+        .width(Length::Fill),
+    )
+    .width(Length::Fill)
+    .padding([4, 4, 0, 6])
+    .into()
+}
+
+fn build_selection_bar(state: &AppState, theme_mode: ThemeMode) -> Element<'static, Message> {
+    let all_checked = !state.files.is_empty() && state.checked_files.len() == state.files.len();
+
+    row![
+        pick_list(
+            state.known_albums(),
+            None::<String>,
+            Message::CheckFilesByAlbum
+        )
+        .placeholder("Select album...")
+        .text_size(12)
+        .padding([6, 10])
+        .width(Length::Fill),
+        Space::with_width(6),
+        button(text("All").size(12))
+            .style(iced::theme::Button::Custom(Box::new(
+                SecondaryButtonStyle { mode: theme_mode }
+            )))
+            .on_press_maybe(if state.files.is_empty() || all_checked {
+                None
+            } else {
+                Some(Message::CheckAllFiles)
+            })
+            .padding([6, 10]),
+        Space::with_width(6),
+        button(text("None").size(12))
+            .style(iced::theme::Button::Custom(Box::new(
+                SecondaryButtonStyle { mode: theme_mode }
+            )))
+            .on_press_maybe(if state.checked_files.is_empty() {
+                None
+            } else {
+                Some(Message::UncheckAllFiles)
+            })
+            .padding([6, 10]),
+    ]
+    .spacing(0)
+    .align_items(Alignment::Center)
+    .width(Length::Fill)
+    .into()
+}
+
 fn build_metadata_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static, Message> {
     let colors = get_colors(theme_mode);
 
@@ -727,6 +942,10 @@ fn build_metadata_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'sta
                     .size(11)
                     .style(iced::theme::Text::Color(colors.text_disabled))
                     .width(Length::Fill),
+                Space::with_height(12),
+                build_spectrum_view(state, &state.files[selected_idx], theme_mode),
+                Space::with_height(8),
+                build_preview_controls(state, selected_idx, metadata.duration, theme_mode),
                 Space::with_height(18),
                 text("Metadata")
                     .size(14)
@@ -944,193 +1163,2056 @@ fn build_metadata_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'sta
     .into()
 }
 
-fn build_metadata_row(
-    label: &str,
-    value: &str,
-    theme_mode: ThemeMode,
-) -> Element<'static, Message> {
+/// Text cleanup rules with a before/after preview of every value they would change
+fn build_text_rules_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static, Message> {
     let colors = get_colors(theme_mode);
-    let value_owned = value.to_string();
-    row![
-        text(label)
-            .size(12)
-            .style(iced::theme::Text::Color(colors.text_secondary))
-            .width(Length::Fixed(60.0)),
-        container(
-            text(value_owned)
-                .size(13)
-                .style(iced::theme::Text::Color(colors.text_primary))
-                .width(Length::Fill)
-        )
-        .width(Length::Fill)
-        .padding([8, 12])
-        .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
-            mode: theme_mode
-        }))),
-    ]
-    .spacing(10)
-    .align_items(Alignment::Center)
-    .width(Length::Fill)
-    .into()
-}
+    let rules = &state.text_rules;
 
-fn build_metadata_field(
-    label: &str,
-    value: &str,
-    theme_mode: ThemeMode,
-) -> Element<'static, Message> {
-    let colors = get_colors(theme_mode);
-    let value_owned = value.to_string();
-    column![
-        text(label)
-            .size(11)
-            .style(iced::theme::Text::Color(colors.text_secondary))
+    let toggle = |label: &str, rule: TextRule, on_toggle: fn(bool) -> Message| {
+        build_rule_toggle(label, rules.is_enabled(rule), on_toggle, theme_mode)
+    };
+
+    let options = row![
+        column![
+            toggle("Trim whitespace", TextRule::Trim, |v| {
+                Message::TextRuleToggled(TextRule::Trim, v)
+            }),
+            Space::with_height(8),
+            toggle("Collapse double spaces", TextRule::CollapseSpaces, |v| {
+                Message::TextRuleToggled(TextRule::CollapseSpaces, v)
+            }),
+            Space::with_height(8),
+            toggle(
+                "Replace _ with spaces",
+                TextRule::UnderscoresToSpaces,
+                |v| { Message::TextRuleToggled(TextRule::UnderscoresToSpaces, v) }
+            ),
+        ]
+        .spacing(0)
+        .width(Length::Fill),
+        Space::with_width(10),
+        column![
+            toggle(
+                "Strip \" mp3 song(s)\" / \" mp3\"",
+                TextRule::StripMp3Suffix,
+                |v| { Message::TextRuleToggled(TextRule::StripMp3Suffix, v) }
+            ),
+            Space::with_height(8),
+            toggle("Normalize feat.", TextRule::NormalizeFeat, |v| {
+                Message::TextRuleToggled(TextRule::NormalizeFeat, v)
+            }),
+            Space::with_height(4),
+            pick_list(
+                CaseRule::ALL.to_vec(),
+                Some(rules.case),
+                Message::TextCaseChanged
+            )
+            .text_size(12)
+            .padding([4, 10])
             .width(Length::Fill),
-        Space::with_height(5),
-        container(
-            text(value_owned)
-                .size(13)
-                .style(iced::theme::Text::Color(colors.text_primary))
-                .width(Length::Fill)
-        )
-        .width(Length::Fill)
-        .padding([8, 12])
-        .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
-            mode: theme_mode
-        }))),
+        ]
+        .spacing(0)
+        .width(Length::Fill),
     ]
     .spacing(0)
-    .width(Length::Fill)
-    .into()
-}
+    .width(Length::Fill);
 
-fn build_edit_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static, Message> {
-    let colors = get_colors(theme_mode);
+    let find_replace = row![
+        text_input("Find (regex)", &rules.find)
+            .on_input(Message::TextFindChanged)
+            .width(Length::Fill)
+            .padding(8)
+            .size(12)
+            .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
+                mode: theme_mode
+            }))),
+        Space::with_width(8),
+        text_input("Replace with", &rules.replace)
+            .on_input(Message::TextReplaceChanged)
+            .width(Length::Fill)
+            .padding(8)
+            .size(12)
+            .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
+                mode: theme_mode
+            }))),
+    ]
+    .spacing(0)
+    .width(Length::Fill);
+
+    // Cap the preview so huge libraries stay responsive; apply still covers every file
+    const PREVIEW_LIMIT: usize = 200;
+    let changes = state.text_rule_changes();
+    let change_count = changes.as_ref().map(|c| c.len()).unwrap_or(0);
+
+    let preview: Element<Message> = match changes {
+        Err(e) => text(e)
+            .size(12)
+            .style(iced::theme::Text::Color(colors.error))
+            .width(Length::Fill)
+            .into(),
+        Ok(changes) if changes.is_empty() => text(if state.file_metadata.is_empty() {
+            "Load files to preview changes"
+        } else {
+            "No values would change"
+        })
+        .size(12)
+        .style(iced::theme::Text::Color(colors.text_disabled))
+        .width(Length::Fill)
+        .into(),
+        Ok(changes) => {
+            let mut list = Column::new().spacing(6).width(Length::Fill);
+            for (index, fields) in changes.iter().take(PREVIEW_LIMIT) {
+                let file_name = state.files[*index]
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let mut item = Column::new().spacing(4).width(Length::Fill).push(
+                    text(file_name)
+                        .size(12)
+                        .style(iced::theme::Text::Color(colors.cosmic_accent))
+                        .shaping(iced::widget::text::Shaping::Advanced),
+                );
+                for change in fields {
+                    item = item.push(
+                        row![
+                            text(change.field.to_string())
+                                .size(11)
+                                .style(iced::theme::Text::Color(colors.text_secondary))
+                                .width(Length::Fixed(44.0)),
+                            text(change.before.clone())
+                                .size(11)
+                                .style(iced::theme::Text::Color(colors.text_disabled))
+                                .width(Length::Fill)
+                                .shaping(iced::widget::text::Shaping::Advanced),
+                            icon_to_text(Bootstrap::ArrowRight)
+                                .size(11.0)
+                                .style(iced::theme::Text::Color(colors.text_secondary)),
+                            Space::with_width(6),
+                            text(change.after.clone())
+                                .size(11)
+                                .style(iced::theme::Text::Color(colors.success))
+                                .width(Length::Fill)
+                                .shaping(iced::widget::text::Shaping::Advanced),
+                        ]
+                        .spacing(4)
+                        .align_items(Alignment::Center)
+                        .width(Length::Fill),
+                    );
+                }
+                list = list.push(container(item).width(Length::Fill).padding([8, 10]).style(
+                    iced::theme::Container::Custom(Box::new(FileItemStyle { mode: theme_mode })),
+                ));
+            }
+            if changes.len() > PREVIEW_LIMIT {
+                list = list.push(
+                    text(format!("...and {} more", changes.len() - PREVIEW_LIMIT))
+                        .size(11)
+                        .style(iced::theme::Text::Color(colors.text_disabled)),
+                );
+            }
+            scrollable(list).height(Length::Fill).into()
+        }
+    };
+
+    container(
+        column![
+            row![
+                text("Text Cleanup")
+                    .size(14)
+                    .style(iced::theme::Text::Color(colors.text_primary))
+                    .width(Length::Fill),
+                button(text("Close").size(12))
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode }
+                    )))
+                    .on_press(Message::ShowEditorPanel(EditorPanel::FileDetails))
+                    .padding([4, 10]),
+            ]
+            .spacing(0)
+            .align_items(Alignment::Center)
+            .width(Length::Fill),
+            Space::with_height(12),
+            options,
+            Space::with_height(10),
+            find_replace,
+            Space::with_height(14),
+            text(format!("Preview ({} file(s) will change)", change_count))
+                .size(11)
+                .style(iced::theme::Text::Color(colors.text_secondary))
+                .width(Length::Fill),
+            Space::with_height(6),
+            container(preview).width(Length::Fill).height(Length::Fill),
+            Space::with_height(10),
+            button(
+                text(format!("Apply Cleanup to {} File(s)", change_count))
+                    .size(13)
+                    .width(Length::Fill)
+                    .horizontal_alignment(iced::alignment::Horizontal::Center)
+            )
+            .style(iced::theme::Button::Custom(Box::new(PrimaryButtonStyle {
+                mode: theme_mode
+            })))
+            .on_press_maybe(if state.processing || change_count == 0 {
+                None
+            } else {
+                Some(Message::ApplyTextRules)
+            })
+            .width(Length::Fill)
+            .padding([10, 16]),
+        ]
+        .spacing(0)
+        .width(Length::Fill)
+        .height(Length::Fill),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .padding([12, 14, 12, 14])
+    .style(iced::theme::Container::Custom(Box::new(CardStyle {
+        mode: theme_mode,
+    })))
+    .into()
+}
+
+/// Saved edit panel values, with controls to save, load, rename and delete them
+fn build_presets_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let name = state.preset_name.trim();
+
+    let save_row = row![
+        text_input("Preset name", &state.preset_name)
+            .on_input(Message::PresetNameChanged)
+            .on_submit(Message::SavePreset)
+            .width(Length::Fill)
+            .padding(8)
+            .size(12)
+            .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
+                mode: theme_mode
+            }))),
+        Space::with_width(8),
+        button(text("Save Current").size(12))
+            .style(iced::theme::Button::Custom(Box::new(PrimaryButtonStyle {
+                mode: theme_mode
+            })))
+            .on_press_maybe((!name.is_empty()).then_some(Message::SavePreset))
+            .padding([8, 12]),
+    ]
+    .spacing(0)
+    .align_items(Alignment::Center)
+    .width(Length::Fill);
+
+    let list: Element<Message> = if state.presets.is_empty() {
+        container(
+            text("No presets yet. Fill in the edit panel, name it above and save.")
+                .size(12)
+                .style(iced::theme::Text::Color(colors.text_disabled))
+                .width(Length::Fill)
+                .horizontal_alignment(iced::alignment::Horizontal::Center),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_y()
+        .into()
+    } else {
+        let mut list = Column::new().spacing(6).width(Length::Fill);
+        for (index, preset) in state.presets.iter().enumerate() {
+            let summary: Vec<&str> = [
+                preset.artist.as_str(),
+                preset.album.as_str(),
+                preset.genre.as_str(),
+                preset.year.as_str(),
+            ]
+            .into_iter()
+            .filter(|v| !v.trim().is_empty())
+            .collect();
+            let summary = if summary.is_empty() {
+                "No values".to_string()
+            } else {
+                summary.join(" • ")
+            };
+            let can_rename = !name.is_empty() && name != preset.name;
+
+            list = list.push(
+                container(
+                    row![
+                        column![
+                            text(preset.name.clone())
+                                .size(13)
+                                .style(iced::theme::Text::Color(colors.cosmic_accent))
+                                .shaping(iced::widget::text::Shaping::Advanced),
+                            Space::with_height(2),
+                            text(summary)
+                                .size(11)
+                                .style(iced::theme::Text::Color(colors.text_secondary))
+                                .shaping(iced::widget::text::Shaping::Advanced),
+                        ]
+                        .spacing(0)
+                        .width(Length::Fill),
+                        button(text("Load").size(11))
+                            .style(iced::theme::Button::Custom(Box::new(PrimaryButtonStyle {
+                                mode: theme_mode
+                            })))
+                            .on_press(Message::LoadPreset(index))
+                            .padding([4, 10]),
+                        Space::with_width(4),
+                        button(text("Rename").size(11))
+                            .style(iced::theme::Button::Custom(Box::new(
+                                SecondaryButtonStyle { mode: theme_mode }
+                            )))
+                            .on_press_maybe(can_rename.then_some(Message::RenamePreset(index)))
+                            .padding([4, 10]),
+                        Space::with_width(4),
+                        button(text("Delete").size(11))
+                            .style(iced::theme::Button::Custom(Box::new(DangerButtonStyle {
+                                mode: theme_mode
+                            })))
+                            .on_press(Message::DeletePreset(index))
+                            .padding([4, 10]),
+                    ]
+                    .spacing(0)
+                    .align_items(Alignment::Center)
+                    .width(Length::Fill),
+                )
+                .width(Length::Fill)
+                .padding([8, 10])
+                .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+                    mode: theme_mode,
+                }))),
+            );
+        }
+        scrollable(list).height(Length::Fill).into()
+    };
+
+    container(
+        column![
+            row![
+                text("Tagging Presets")
+                    .size(14)
+                    .style(iced::theme::Text::Color(colors.text_primary))
+                    .width(Length::Fill),
+                button(text("Close").size(12))
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode }
+                    )))
+                    .on_press(Message::ShowEditorPanel(EditorPanel::FileDetails))
+                    .padding([4, 10]),
+            ]
+            .spacing(0)
+            .align_items(Alignment::Center)
+            .width(Length::Fill),
+            Space::with_height(12),
+            save_row,
+            Space::with_height(4),
+            text("Saving under an existing name updates it; Rename uses the name above")
+                .size(11)
+                .style(iced::theme::Text::Color(colors.text_disabled))
+                .width(Length::Fill),
+            Space::with_height(12),
+            list,
+            Space::with_height(10),
+            text(state.status.clone())
+                .size(12)
+                .style(iced::theme::Text::Color(colors.text_secondary))
+                .width(Length::Fill),
+        ]
+        .spacing(0)
+        .width(Length::Fill)
+        .height(Length::Fill),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .padding([12, 14, 12, 14])
+    .style(iced::theme::Container::Custom(Box::new(CardStyle {
+        mode: theme_mode,
+    })))
+    .into()
+}
+
+/// Clickable completions shown under an input; empty when there is nothing to suggest
+fn build_suggestions(
+    suggestions: Vec<String>,
+    on_pick: fn(String) -> Message,
+    theme_mode: ThemeMode,
+) -> Element<'static, Message> {
+    if suggestions.is_empty() {
+        return Space::with_height(0).into();
+    }
+    let mut chips = row![].spacing(4).width(Length::Fill);
+    for suggestion in suggestions {
+        chips = chips.push(
+            button(
+                text(suggestion.clone())
+                    .size(11)
+                    .shaping(iced::widget::text::Shaping::Advanced),
+            )
+            .style(iced::theme::Button::Custom(Box::new(
+                SecondaryButtonStyle { mode: theme_mode },
+            )))
+            .on_press(on_pick(suggestion))
+            .padding([3, 8]),
+        );
+    }
+    column![Space::with_height(5), chips]
+        .spacing(0)
+        .width(Length::Fill)
+        .into()
+}
+
+/// Custom genre vocabulary and library-wide merging of genre spelling variants
+fn build_genres_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let vocabulary = state.genre_vocabulary();
+
+    let section = |label: &str| {
+        text(label.to_string())
+            .size(11)
+            .style(iced::theme::Text::Color(colors.text_secondary))
+            .width(Length::Fill)
+    };
+
+    // Custom vocabulary
+    let add_row = row![
+        text_input("Add a custom genre", &state.custom_genre_input)
+            .on_input(Message::CustomGenreInputChanged)
+            .on_submit(Message::AddCustomGenre)
+            .width(Length::Fill)
+            .padding(8)
+            .size(12)
+            .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
+                mode: theme_mode
+            }))),
+        Space::with_width(8),
+        button(text("Add").size(12))
+            .style(iced::theme::Button::Custom(Box::new(PrimaryButtonStyle {
+                mode: theme_mode
+            })))
+            .on_press_maybe(
+                (!state.custom_genre_input.trim().is_empty()).then_some(Message::AddCustomGenre)
+            )
+            .padding([8, 12]),
+    ]
+    .spacing(0)
+    .align_items(Alignment::Center)
+    .width(Length::Fill);
+
+    let mut custom_list = Column::new().spacing(4).width(Length::Fill);
+    if state.custom_genres.is_empty() {
+        custom_list = custom_list.push(
+            text("None yet; the standard ID3 genre list is always offered")
+                .size(11)
+                .style(iced::theme::Text::Color(colors.text_disabled)),
+        );
+    }
+    for (index, genre) in state.custom_genres.iter().enumerate() {
+        custom_list = custom_list.push(
+            container(
+                row![
+                    text(genre.clone())
+                        .size(12)
+                        .style(iced::theme::Text::Color(colors.text_primary))
+                        .width(Length::Fill)
+                        .shaping(iced::widget::text::Shaping::Advanced),
+                    button(text("×").size(14))
+                        .style(iced::theme::Button::Custom(Box::new(WarningButtonStyle {
+                            mode: theme_mode
+                        })))
+                        .on_press(Message::RemoveCustomGenre(index))
+                        .padding([0, 8]),
+                ]
+                .spacing(0)
+                .align_items(Alignment::Center)
+                .width(Length::Fill),
+            )
+            .width(Length::Fill)
+            .padding([4, 10])
+            .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+                mode: theme_mode,
+            }))),
+        );
+    }
+
+    // Spelling variants detected in the loaded files
+    let groups = find_genre_variants(
+        state.file_metadata.values().map(|m| m.genre.as_str()),
+        &vocabulary,
+    );
+    let mut variants_list = Column::new().spacing(4).width(Length::Fill);
+    if groups.is_empty() {
+        variants_list = variants_list.push(
+            text(if state.file_metadata.is_empty() {
+                "Load files to find genre variants"
+            } else {
+                "No spelling variants found"
+            })
+            .size(11)
+            .style(iced::theme::Text::Color(colors.text_disabled)),
+        );
+    }
+    for group in &groups {
+        let variants = group
+            .variants
+            .iter()
+            .map(|(genre, count)| format!("{} ({})", genre, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        variants_list = variants_list.push(
+            container(
+                row![
+                    column![
+                        text(group.canonical.clone())
+                            .size(12)
+                            .style(iced::theme::Text::Color(colors.cosmic_accent))
+                            .shaping(iced::widget::text::Shaping::Advanced),
+                        text(variants)
+                            .size(11)
+                            .style(iced::theme::Text::Color(colors.text_secondary))
+                            .shaping(iced::widget::text::Shaping::Advanced),
+                    ]
+                    .spacing(2)
+                    .width(Length::Fill),
+                    button(text("Merge").size(11))
+                        .style(iced::theme::Button::Custom(Box::new(
+                            SecondaryButtonStyle { mode: theme_mode }
+                        )))
+                        .on_press_maybe((!state.processing).then(|| {
+                            Message::MergeGenres(group.to_rewrite(), group.canonical.clone())
+                        }))
+                        .padding([4, 10]),
+                ]
+                .spacing(0)
+                .align_items(Alignment::Center)
+                .width(Length::Fill),
+            )
+            .width(Length::Fill)
+            .padding([6, 10])
+            .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+                mode: theme_mode,
+            }))),
+        );
+    }
+
+    // Merge any genre into any other
+    let manual_merge = column![
+        row![
+            pick_list(
+                state.library_genres(),
+                state.merge_source.clone(),
+                Message::MergeSourceSelected
+            )
+            .placeholder("Genre in library...")
+            .text_size(12)
+            .padding([6, 10])
+            .width(Length::Fill),
+            Space::with_width(6),
+            text_input("Merge into", &state.merge_target)
+                .on_input(Message::MergeTargetChanged)
+                .width(Length::Fill)
+                .padding(6)
+                .size(12)
+                .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
+                    mode: theme_mode
+                }))),
+            Space::with_width(6),
+            button(text("Merge").size(12))
+                .style(iced::theme::Button::Custom(Box::new(PrimaryButtonStyle {
+                    mode: theme_mode
+                })))
+                .on_press_maybe(match &state.merge_source {
+                    Some(source) if !state.processing && !state.merge_target.trim().is_empty() => {
+                        Some(Message::MergeGenres(
+                            vec![source.clone()],
+                            state.merge_target.clone(),
+                        ))
+                    }
+                    _ => None,
+                })
+                .padding([6, 12]),
+        ]
+        .spacing(0)
+        .align_items(Alignment::Center)
+        .width(Length::Fill),
+        build_suggestions(
+            suggest_genres(&state.merge_target, &vocabulary, 3),
+            Message::MergeTargetChanged,
+            theme_mode
+        ),
+    ]
+    .spacing(0)
+    .width(Length::Fill);
+
+    container(
+        column![
+            row![
+                text("Genres")
+                    .size(14)
+                    .style(iced::theme::Text::Color(colors.text_primary))
+                    .width(Length::Fill),
+                button(text("Close").size(12))
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode }
+                    )))
+                    .on_press(Message::ShowEditorPanel(EditorPanel::FileDetails))
+                    .padding([4, 10]),
+            ]
+            .spacing(0)
+            .align_items(Alignment::Center)
+            .width(Length::Fill),
+            Space::with_height(12),
+            scrollable(
+                column![
+                    section("Custom Vocabulary"),
+                    Space::with_height(6),
+                    add_row,
+                    Space::with_height(6),
+                    custom_list,
+                    Space::with_height(14),
+                    section("Spelling Variants in Library"),
+                    Space::with_height(6),
+                    variants_list,
+                    Space::with_height(14),
+                    section("Merge Genres"),
+                    Space::with_height(6),
+                    manual_merge,
+                ]
+                .spacing(0)
+                .width(Length::Fill)
+                .padding([0, 8, 0, 0])
+            )
+            .height(Length::Fill),
+            Space::with_height(10),
+            text(state.status.clone())
+                .size(12)
+                .style(iced::theme::Text::Color(colors.text_secondary))
+                .width(Length::Fill),
+        ]
+        .spacing(0)
+        .width(Length::Fill)
+        .height(Length::Fill),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .padding([12, 14, 12, 14])
+    .style(iced::theme::Container::Custom(Box::new(CardStyle {
+        mode: theme_mode,
+    })))
+    .into()
+}
+
+/// EBU R128 loudness of the target files and the ReplayGain values that would be written
+fn build_loudness_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let target_count = state.target_files().len();
+    let measured = state
+        .loudness_results
+        .iter()
+        .filter(|(_, r)| r.is_ok())
+        .count();
+    let to_db = |linear: f64| 20.0 * linear.max(1e-9).log10();
+
+    let actions = row![
+        if state.analyzing_loudness {
+            button(text("Stop").size(12))
+                .style(iced::theme::Button::Custom(Box::new(DangerButtonStyle {
+                    mode: theme_mode,
+                })))
+                .on_press(Message::StopLoudnessAnalysis)
+                .padding([8, 12])
+                .width(Length::Fill)
+        } else {
+            button(text(format!("Analyze {} File(s)", target_count)).size(12))
+                .style(iced::theme::Button::Custom(Box::new(PrimaryButtonStyle {
+                    mode: theme_mode,
+                })))
+                .on_press_maybe(
+                    (!state.processing && target_count > 0).then_some(Message::AnalyzeLoudness),
+                )
+                .padding([8, 12])
+                .width(Length::Fill)
+        },
+        Space::with_width(8),
+        button(text("Write ReplayGain Tags").size(12))
+            .style(iced::theme::Button::Custom(Box::new(
+                SecondaryButtonStyle { mode: theme_mode }
+            )))
+            .on_press_maybe(
+                (!state.processing && !state.analyzing_loudness && measured > 0)
+                    .then_some(Message::WriteReplayGain)
+            )
+            .padding([8, 12])
+            .width(Length::Fill),
+    ]
+    .spacing(0)
+    .width(Length::Fill);
+
+    let mut results = Column::new().spacing(4).width(Length::Fill);
+    if state.loudness_results.is_empty() {
+        results = results.push(
+            text(format!(
+                "Track gain brings each file to {} LUFS. Album gain is written per folder \
+                 when Albums grouping is on and every file in the folder was analyzed.",
+                REFERENCE_LUFS
+            ))
+            .size(11)
+            .style(iced::theme::Text::Color(colors.text_disabled)),
+        );
+    }
+    for (file, result) in &state.loudness_results {
+        let file_name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (detail, detail_color) = match result {
+            Ok(loudness) => match (loudness.integrated_lufs, loudness.gain_db()) {
+                (Some(lufs), Some(gain)) => (
+                    format!(
+                        "{:.1} LUFS • peak {:.1} dBTP • gain {:+.2} dB",
+                        lufs,
+                        to_db(loudness.true_peak),
+                        gain
+                    ),
+                    colors.text_secondary,
+                ),
+                _ => ("Silent, nothing to write".to_string(), colors.text_disabled),
+            },
+            Err(e) => (e.clone(), colors.error),
+        };
+        results = results.push(
+            container(
+                column![
+                    text(file_name)
+                        .size(12)
+                        .style(iced::theme::Text::Color(colors.text_primary))
+                        .shaping(iced::widget::text::Shaping::Advanced),
+                    text(detail)
+                        .size(11)
+                        .style(iced::theme::Text::Color(detail_color)),
+                ]
+                .spacing(2)
+                .width(Length::Fill),
+            )
+            .width(Length::Fill)
+            .padding([6, 10])
+            .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+                mode: theme_mode,
+            }))),
+        );
+    }
+
+    container(
+        column![
+            row![
+                text("Loudness (ReplayGain)")
+                    .size(14)
+                    .style(iced::theme::Text::Color(colors.text_primary))
+                    .width(Length::Fill),
+                button(text("Close").size(12))
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode }
+                    )))
+                    .on_press(Message::ShowEditorPanel(EditorPanel::FileDetails))
+                    .padding([4, 10]),
+            ]
+            .spacing(0)
+            .align_items(Alignment::Center)
+            .width(Length::Fill),
+            Space::with_height(12),
+            actions,
+            Space::with_height(12),
+            scrollable(container(results).width(Length::Fill).padding([0, 8, 0, 0]))
+                .height(Length::Fill),
+            Space::with_height(10),
+            text(state.status.clone())
+                .size(12)
+                .style(iced::theme::Text::Color(colors.text_secondary))
+                .width(Length::Fill),
+        ]
+        .spacing(0)
+        .width(Length::Fill)
+        .height(Length::Fill),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .padding([12, 14, 12, 14])
+    .style(iced::theme::Container::Custom(Box::new(CardStyle {
+        mode: theme_mode,
+    })))
+    .into()
+}
+
+/// Full-decode verification of the target files; only files with problems are listed
+fn build_integrity_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let target_count = state.target_files().len();
+    let problems: Vec<_> = state
+        .verify_results
+        .iter()
+        .filter(|(_, check)| !check.is_ok())
+        .collect();
+    let ok_count = state.verify_results.len() - problems.len();
+
+    let actions = row![
+        if state.verifying {
+            button(text("Stop").size(12))
+                .style(iced::theme::Button::Custom(Box::new(DangerButtonStyle {
+                    mode: theme_mode,
+                })))
+                .on_press(Message::StopVerification)
+                .padding([8, 12])
+                .width(Length::Fill)
+        } else {
+            button(text(format!("Verify {} File(s)", target_count)).size(12))
+                .style(iced::theme::Button::Custom(Box::new(PrimaryButtonStyle {
+                    mode: theme_mode,
+                })))
+                .on_press_maybe(
+                    (!state.processing && target_count > 0).then_some(Message::VerifyFiles),
+                )
+                .padding([8, 12])
+                .width(Length::Fill)
+        },
+        Space::with_width(8),
+        button(text("Select Problem Files").size(12))
+            .style(iced::theme::Button::Custom(Box::new(
+                SecondaryButtonStyle { mode: theme_mode }
+            )))
+            .on_press_maybe((!problems.is_empty()).then_some(Message::CheckProblemFiles))
+            .padding([8, 12])
+            .width(Length::Fill),
+    ]
+    .spacing(0)
+    .width(Length::Fill);
+
+    let mut results = Column::new().spacing(4).width(Length::Fill);
+    if state.verify_results.is_empty() {
+        results = results.push(
+            text(
+                "Decodes every file from start to end to find empty, truncated, corrupt \
+                 or misnamed files, and lengths that disagree with the file's headers. \
+                 WMA and Opus files cannot be decoded and are listed as not verified.",
+            )
+            .size(11)
+            .style(iced::theme::Text::Color(colors.text_disabled)),
+        );
+    } else {
+        results = results.push(
+            text(format!(
+                "{} file(s) OK, {} with problems",
+                ok_count,
+                problems.len()
+            ))
+            .size(12)
+            .style(iced::theme::Text::Color(if problems.is_empty() {
+                colors.success
+            } else {
+                colors.text_secondary
+            })),
+        );
+    }
+    for (file, check) in problems {
+        let file_name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut details = column![text(file_name)
+            .size(12)
+            .style(iced::theme::Text::Color(colors.text_primary))
+            .shaping(iced::widget::text::Shaping::Advanced)]
+        .spacing(2)
+        .width(Length::Fill);
+        for issue in &check.issues {
+            details = details.push(
+                text(issue.to_string())
+                    .size(11)
+                    .style(iced::theme::Text::Color(colors.error)),
+            );
+        }
+        results = results.push(
+            container(details)
+                .width(Length::Fill)
+                .padding([6, 10])
+                .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+                    mode: theme_mode,
+                }))),
+        );
+    }
+
+    container(
+        column![
+            row![
+                text("Verify Files")
+                    .size(14)
+                    .style(iced::theme::Text::Color(colors.text_primary))
+                    .width(Length::Fill),
+                button(text("Close").size(12))
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode }
+                    )))
+                    .on_press(Message::ShowEditorPanel(EditorPanel::FileDetails))
+                    .padding([4, 10]),
+            ]
+            .spacing(0)
+            .align_items(Alignment::Center)
+            .width(Length::Fill),
+            Space::with_height(12),
+            actions,
+            Space::with_height(12),
+            scrollable(container(results).width(Length::Fill).padding([0, 8, 0, 0]))
+                .height(Length::Fill),
+            Space::with_height(10),
+            text(state.status.clone())
+                .size(12)
+                .style(iced::theme::Text::Color(colors.text_secondary))
+                .width(Length::Fill),
+        ]
+        .spacing(0)
+        .width(Length::Fill)
+        .height(Length::Fill),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .padding([12, 14, 12, 14])
+    .style(iced::theme::Container::Custom(Box::new(CardStyle {
+        mode: theme_mode,
+    })))
+    .into()
+}
+
+/// Transcode check of the target files, suspect files listed first
+fn build_quality_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let target_count = state.target_files().len();
+    let mut reports: Vec<_> = state.quality_results.iter().collect();
+    reports.sort_by_key(|(file, result)| {
+        let genuine = matches!(result, Ok(report) if !report.is_suspect());
+        (genuine, file.file_name().map(|n| n.to_os_string()))
+    });
+    let suspect_count = reports
+        .iter()
+        .filter(|(_, result)| matches!(result, Ok(report) if report.is_suspect()))
+        .count();
+    let failed_count = reports.iter().filter(|(_, result)| result.is_err()).count();
+
+    let actions = row![
+        if state.analyzing_quality {
+            button(text("Stop").size(12))
+                .style(iced::theme::Button::Custom(Box::new(DangerButtonStyle {
+                    mode: theme_mode,
+                })))
+                .on_press(Message::StopQualityAnalysis)
+                .padding([8, 12])
+                .width(Length::Fill)
+        } else {
+            button(text(format!("Check {} File(s)", target_count)).size(12))
+                .style(iced::theme::Button::Custom(Box::new(PrimaryButtonStyle {
+                    mode: theme_mode,
+                })))
+                .on_press_maybe(
+                    (!state.processing && target_count > 0).then_some(Message::AnalyzeQuality),
+                )
+                .padding([8, 12])
+                .width(Length::Fill)
+        },
+        Space::with_width(8),
+        button(text("Select Suspect Files").size(12))
+            .style(iced::theme::Button::Custom(Box::new(
+                SecondaryButtonStyle { mode: theme_mode }
+            )))
+            .on_press_maybe((suspect_count > 0).then_some(Message::CheckSuspectFiles))
+            .padding([8, 12])
+            .width(Length::Fill),
+    ]
+    .spacing(0)
+    .width(Length::Fill);
+
+    let mut results = Column::new().spacing(4).width(Length::Fill);
+    if reports.is_empty() {
+        results = results.push(
+            text(
+                "Looks for the low-pass a lossy encoder leaves in the spectrum, to find \
+                 lossless files made from MP3s and MP3s re-encoded at a higher bitrate.",
+            )
+            .size(11)
+            .style(iced::theme::Text::Color(colors.text_disabled)),
+        );
+    } else {
+        results = results.push(
+            text(format!(
+                "{} file(s) checked, {} look transcoded or upsampled, {} could not be analyzed",
+                reports.len(),
+                suspect_count,
+                failed_count
+            ))
+            .size(12)
+            .style(iced::theme::Text::Color(if suspect_count == 0 {
+                colors.success
+            } else {
+                colors.text_secondary
+            })),
+        );
+    }
+    for (file, result) in reports {
+        let file_name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (summary, color) = match result {
+            Ok(report) if report.is_suspect() => (report.to_string(), colors.warning),
+            Ok(report) => (report.to_string(), colors.text_secondary),
+            Err(e) => (e.clone(), colors.error),
+        };
+        results = results.push(
+            container(
+                column![
+                    text(file_name)
+                        .size(12)
+                        .style(iced::theme::Text::Color(colors.text_primary))
+                        .shaping(iced::widget::text::Shaping::Advanced),
+                    text(summary)
+                        .size(11)
+                        .style(iced::theme::Text::Color(color)),
+                ]
+                .spacing(2)
+                .width(Length::Fill),
+            )
+            .width(Length::Fill)
+            .padding([6, 10])
+            .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+                mode: theme_mode,
+            }))),
+        );
+    }
+
+    container(
+        column![
+            row![
+                text("Quality Check")
+                    .size(14)
+                    .style(iced::theme::Text::Color(colors.text_primary))
+                    .width(Length::Fill),
+                button(text("Close").size(12))
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode }
+                    )))
+                    .on_press(Message::ShowEditorPanel(EditorPanel::FileDetails))
+                    .padding([4, 10]),
+            ]
+            .spacing(0)
+            .align_items(Alignment::Center)
+            .width(Length::Fill),
+            Space::with_height(12),
+            actions,
+            Space::with_height(12),
+            scrollable(container(results).width(Length::Fill).padding([0, 8, 0, 0]))
+                .height(Length::Fill),
+            Space::with_height(10),
+            text(state.status.clone())
+                .size(12)
+                .style(iced::theme::Text::Color(colors.text_secondary))
+                .width(Length::Fill),
+        ]
+        .spacing(0)
+        .width(Length::Fill)
+        .height(Length::Fill),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .padding([12, 14, 12, 14])
+    .style(iced::theme::Container::Custom(Box::new(CardStyle {
+        mode: theme_mode,
+    })))
+    .into()
+}
+
+/// Plain and synced lyrics of the selected file, with a tap-along timestamp editor
+fn build_lyrics_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'_, Message> {
+    let colors = get_colors(theme_mode);
+
+    let section = |label: &str| {
+        text(label.to_string())
+            .size(11)
+            .style(iced::theme::Text::Color(colors.text_secondary))
+            .width(Length::Fill)
+    };
+    let small_button = |label: &str, message: Option<Message>| {
+        button(text(label.to_string()).size(12))
+            .style(iced::theme::Button::Custom(Box::new(
+                SecondaryButtonStyle { mode: theme_mode },
+            )))
+            .on_press_maybe(message)
+            .padding([6, 10])
+    };
+
+    let header = row![
+        text("Lyrics")
+            .size(14)
+            .style(iced::theme::Text::Color(colors.text_primary))
+            .width(Length::Fill),
+        button(text("Close").size(12))
+            .style(iced::theme::Button::Custom(Box::new(
+                SecondaryButtonStyle { mode: theme_mode }
+            )))
+            .on_press(Message::ShowEditorPanel(EditorPanel::FileDetails))
+            .padding([4, 10]),
+    ]
+    .spacing(0)
+    .align_items(Alignment::Center)
+    .width(Length::Fill);
+
+    let Some(file) = &state.lyrics_file else {
+        return container(
+            column![
+                header,
+                Space::with_height(12),
+                text("Select a file to edit its lyrics")
+                    .size(12)
+                    .style(iced::theme::Text::Color(colors.text_disabled)),
+            ]
+            .spacing(0)
+            .width(Length::Fill),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding([12, 14, 12, 14])
+        .style(iced::theme::Container::Custom(Box::new(CardStyle {
+            mode: theme_mode,
+        })))
+        .into();
+    };
+    let file_name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = file
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let embeds_synced = matches!(extension.as_str(), "mp3" | "aiff" | "aif");
+
+    let plain = text_editor(&state.lyrics_text)
+        .on_action(Message::LyricsTextEdited)
+        .height(120)
+        .padding(8);
+
+    let playing = state.lyrics_playing();
+    let transport = row![
+        if playing {
+            small_button("Stop", Some(Message::StopLyricsPlayback))
+        } else {
+            small_button("Play", Some(Message::PlayLyricsFrom(Duration::ZERO)))
+        },
+        Space::with_width(6),
+        button(text("Stamp").size(12))
+            .style(iced::theme::Button::Custom(Box::new(PrimaryButtonStyle {
+                mode: theme_mode,
+            })))
+            .on_press_maybe(
+                (playing && state.lyrics_cursor < state.lyrics_lines.len())
+                    .then_some(Message::StampLyricLine)
+            )
+            .padding([6, 10]),
+        Space::with_width(8),
+        text(format_timestamp(state.lyrics_position()))
+            .size(12)
+            .style(iced::theme::Text::Color(colors.text_secondary))
+            .width(Length::Fill),
+        small_button(
+            "From Plain Text",
+            (!state.lyrics_text.text().trim().is_empty()).then_some(Message::LyricLinesFromText)
+        ),
+        Space::with_width(6),
+        small_button("Add Line", Some(Message::AddLyricLine)),
+    ]
+    .spacing(0)
+    .align_items(Alignment::Center)
+    .width(Length::Fill);
+
+    let showing = playing
+        .then(|| current_line(&state.lyrics_lines, state.lyrics_position()))
+        .flatten();
+    let mut lines = Column::new().spacing(4).width(Length::Fill);
+    if state.lyrics_lines.is_empty() {
+        lines = lines.push(
+            text(
+                "No synced lyrics. Use From Plain Text, then play the track and press \
+                 Stamp as each line starts.",
+            )
+            .size(11)
+            .style(iced::theme::Text::Color(colors.text_disabled)),
+        );
+    }
+    for (index, line) in state.lyrics_lines.iter().enumerate() {
+        let is_selected = index == state.lyrics_cursor;
+        let time_color = if showing == Some(index) {
+            colors.cosmic_accent
+        } else {
+            colors.text_secondary
+        };
+        lines = lines.push(
+            row![
+                button(
+                    text(format_timestamp(line.time))
+                        .size(12)
+                        .style(iced::theme::Text::Color(time_color))
+                )
+                .style(iced::theme::Button::Custom(Box::new(
+                    TransparentButtonStyle {
+                        mode: theme_mode,
+                        is_selected
+                    }
+                )))
+                .on_press(Message::SelectLyricLine(index))
+                .padding([6, 8]),
+                button(text("−").size(12))
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode }
+                    )))
+                    .on_press(Message::NudgeLyricLine(index, -100))
+                    .padding([4, 6]),
+                button(text("+").size(12))
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode }
+                    )))
+                    .on_press(Message::NudgeLyricLine(index, 100))
+                    .padding([4, 6]),
+                Space::with_width(6),
+                text_input("Lyric line", &line.text)
+                    .on_input(move |value| Message::LyricLineTextChanged(index, value))
+                    .width(Length::Fill)
+                    .padding(6)
+                    .size(12)
+                    .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
+                        mode: theme_mode
+                    }))),
+                button(text("▶").size(11))
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode }
+                    )))
+                    .on_press(Message::PlayLyricsFrom(line.time))
+                    .padding([4, 6]),
+                button(text("×").size(14))
+                    .style(iced::theme::Button::Custom(Box::new(WarningButtonStyle {
+                        mode: theme_mode
+                    })))
+                    .on_press(Message::RemoveLyricLine(index))
+                    .padding([0, 8]),
+            ]
+            .spacing(2)
+            .align_items(Alignment::Center)
+            .width(Length::Fill),
+        );
+    }
+
+    let mut content = column![
+        header,
+        text(file_name)
+            .size(11)
+            .style(iced::theme::Text::Color(colors.text_disabled))
+            .shaping(iced::widget::text::Shaping::Advanced),
+        Space::with_height(10),
+        section("Plain lyrics (USLT / LYRICS)"),
+        Space::with_height(6),
+        plain,
+        Space::with_height(12),
+        section("Synced lyrics (SYLT / .lrc)"),
+        Space::with_height(6),
+        transport,
+        Space::with_height(8),
+        scrollable(container(lines).width(Length::Fill).padding([0, 8, 0, 0])).height(Length::Fill),
+    ]
+    .spacing(0)
+    .width(Length::Fill)
+    .height(Length::Fill);
+    if !embeds_synced {
+        content = content.push(
+            text("Synced lyrics are only embedded in MP3 and AIFF files; export an .lrc for this one")
+                .size(11)
+                .style(iced::theme::Text::Color(colors.text_disabled)),
+        );
+    }
+    content = content.push(Space::with_height(10)).push(
+        row![
+            small_button("Import .lrc", Some(Message::ImportLrc)),
+            Space::with_width(6),
+            small_button(
+                "Export .lrc",
+                (!state.lyrics_lines.is_empty()).then_some(Message::ExportLrc)
+            ),
+            Space::with_width(Length::Fill),
+            button(text("Save to File").size(12))
+                .style(iced::theme::Button::Custom(Box::new(PrimaryButtonStyle {
+                    mode: theme_mode,
+                })))
+                .on_press_maybe((!state.processing).then_some(Message::SaveLyrics))
+                .padding([6, 12]),
+        ]
+        .spacing(0)
+        .align_items(Alignment::Center)
+        .width(Length::Fill),
+    );
+
+    container(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding([12, 14, 12, 14])
+        .style(iced::theme::Container::Custom(Box::new(CardStyle {
+            mode: theme_mode,
+        })))
+        .into()
+}
+
+/// Waveform overview and spectrogram of the selected file, once computed
+fn build_spectrum_view(
+    state: &AppState,
+    file: &Path,
+    theme_mode: ThemeMode,
+) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let placeholder = |message: String| -> Element<'static, Message> {
+        container(
+            text(message)
+                .size(11)
+                .style(iced::theme::Text::Color(colors.text_disabled)),
+        )
+        .width(Length::Fill)
+        .height(Length::Fixed(60.0))
+        .center_x()
+        .center_y()
+        .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+            mode: theme_mode,
+        })))
+        .into()
+    };
+
+    let view = match &state.spectrum {
+        Some(Ok(view)) if state.spectrum_file.as_deref() == Some(file) => view,
+        Some(Err(e)) if state.spectrum_file.as_deref() == Some(file) => {
+            return placeholder(format!("No waveform: {}", e));
+        }
+        _ => return placeholder("Analyzing audio...".to_string()),
+    };
+
+    let label = |value: String| {
+        text(value)
+            .size(10)
+            .style(iced::theme::Text::Color(colors.text_disabled))
+    };
+
+    column![
+        container(
+            svg(view.waveform.clone())
+                .width(Length::Fill)
+                .height(Length::Fixed(48.0))
+                .content_fit(ContentFit::Fill)
+        )
+        .padding(4)
+        .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+            mode: theme_mode,
+        }))),
+        Space::with_height(6),
+        row![
+            svg(view.spectrogram.clone())
+                .width(Length::Fill)
+                .height(Length::Fixed(96.0))
+                .content_fit(ContentFit::Fill),
+            Space::with_width(6),
+            column![
+                label(format!("{:.0} kHz", view.nyquist_hz / 1000.0)),
+                Space::with_height(Length::Fill),
+                label(format!("{:.0} kHz", view.nyquist_hz / 2000.0)),
+                Space::with_height(Length::Fill),
+                label("0".to_string()),
+            ]
+            .height(Length::Fixed(96.0)),
+        ]
+        .spacing(0)
+        .width(Length::Fill),
+    ]
+    .spacing(0)
+    .width(Length::Fill)
+    .into()
+}
+
+/// Play, seek and step through the file list to hear the selected file
+fn build_preview_controls(
+    state: &AppState,
+    index: usize,
+    duration_secs: Option<u64>,
+    theme_mode: ThemeMode,
+) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let file = &state.files[index];
+    let active = state.playing_file() == Some(file.as_path());
+    let total = state
+        .player
+        .duration
+        .filter(|_| active)
+        .map(|d| d.as_secs())
+        .or(duration_secs);
+
+    let icon_button = |icon: Bootstrap, message: Option<Message>| {
+        button(
+            icon_to_text(icon)
+                .size(12.0)
+                .style(iced::theme::Text::Color(colors.text_primary)),
+        )
+        .style(iced::theme::Button::Custom(Box::new(
+            SecondaryButtonStyle { mode: theme_mode },
+        )))
+        .on_press_maybe(message)
+        .padding([6, 8])
+    };
+    let playing = active && state.player.state != PlaybackState::Paused;
+    let play_button = button(
+        icon_to_text(if playing {
+            Bootstrap::PauseFill
+        } else {
+            Bootstrap::PlayFill
+        })
+        .size(12.0)
+        .style(iced::theme::Text::Color(Color::WHITE)),
+    )
+    .style(iced::theme::Button::Custom(Box::new(PlayButtonStyle {
+        mode: theme_mode,
+        is_playing: false,
+    })))
+    .on_press(if playing {
+        Message::PlayerPause
+    } else if active {
+        Message::PlayerResume
+    } else {
+        Message::PreviewFile(index)
+    })
+    .padding([6, 10]);
+
+    let mut controls = column![row![
+        icon_button(
+            Bootstrap::SkipStartFill,
+            (index > 0).then_some(Message::PreviewPrevious)
+        ),
+        Space::with_width(4),
+        play_button,
+        Space::with_width(4),
+        icon_button(Bootstrap::StopFill, active.then_some(Message::StopPreview)),
+        Space::with_width(4),
+        icon_button(
+            Bootstrap::SkipEndFill,
+            (index + 1 < state.files.len()).then_some(Message::PreviewNext)
+        ),
+        Space::with_width(10),
+        build_seek_bar(state, active, total, theme_mode),
+        Space::with_width(10),
+        build_volume_control(state, theme_mode),
+    ]
+    .spacing(0)
+    .align_items(Alignment::Center)
+    .width(Length::Fill)]
+    .spacing(4)
+    .width(Length::Fill);
+    controls = controls.push(
+        row![
+            checkbox("Play through the list", state.play_through)
+                .on_toggle(Message::TogglePlayThrough)
+                .size(14)
+                .text_size(12)
+                .style(iced::theme::Checkbox::Custom(Box::new(ToggleStyle {
+                    mode: theme_mode
+                }))),
+            Space::with_width(Length::Fill),
+            build_crossfade_control(state, theme_mode),
+        ]
+        .spacing(0)
+        .align_items(Alignment::Center)
+        .width(Length::Fill),
+    );
+
+    // Another file keeps playing until stopped, so say which one
+    if let Some(other) = state.playing_file().filter(|f| *f != file.as_path()) {
+        controls = controls.push(
+            text(format!(
+                "Playing: {}",
+                other.file_name().unwrap_or_default().to_string_lossy()
+            ))
+            .size(11)
+            .style(iced::theme::Text::Color(colors.text_disabled))
+            .shaping(iced::widget::text::Shaping::Advanced),
+        );
+    }
+    controls.into()
+}
+
+/// Transport bar for the song streaming in the downloader, with the preview queue
+fn build_now_playing(state: &AppState, theme_mode: ThemeMode) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let downloader = &state.downloader_state;
+    let streaming = downloader
+        .streaming_url
+        .clone()
+        .map(PlayerSource::Url)
+        .is_some_and(|source| state.player.is_active(&source));
+    let paused = streaming && state.player.state == PlaybackState::Paused;
+    let controls_enabled = streaming && state.player.state != PlaybackState::Loading;
+    let title = downloader
+        .playing_song_index
+        .and_then(|i| downloader.search_results.get(i))
+        .map(|song| song.title.clone())
+        .unwrap_or_else(|| "Nothing playing".to_string());
+
+    let icon_button = |icon: Bootstrap, message: Option<Message>| {
+        button(
+            icon_to_text(icon)
+                .size(12.0)
+                .style(iced::theme::Text::Color(colors.text_primary)),
+        )
+        .style(iced::theme::Button::Custom(Box::new(
+            SecondaryButtonStyle { mode: theme_mode },
+        )))
+        .on_press_maybe(message)
+        .padding([6, 8])
+    };
+
+    let transport = row![
+        text(title)
+            .size(12)
+            .style(iced::theme::Text::Color(colors.text_primary))
+            .shaping(iced::widget::text::Shaping::Advanced)
+            .width(Length::FillPortion(2)),
+        Space::with_width(10),
+        icon_button(
+            if paused {
+                Bootstrap::PlayFill
+            } else {
+                Bootstrap::PauseFill
+            },
+            controls_enabled.then_some(if paused {
+                Message::PlayerResume
+            } else {
+                Message::PlayerPause
+            })
+        ),
+        Space::with_width(10),
+        container(build_seek_bar(
+            state,
+            controls_enabled,
+            state.player.duration.map(|d| d.as_secs()),
+            theme_mode,
+        ))
+        .width(Length::FillPortion(3)),
+        Space::with_width(10),
+        build_volume_control(state, theme_mode),
+    ]
+    .spacing(0)
+    .align_items(Alignment::Center)
+    .width(Length::Fill);
+
+    if downloader.play_queue.is_empty() {
+        return transport.into();
+    }
+
+    let queue_label = match downloader.queue_position {
+        Some(position) => format!("Queue {} / {}", position + 1, downloader.play_queue.len()),
+        None => format!("{} song(s) queued", downloader.play_queue.len()),
+    };
+    let queue = row![
+        icon_button(
+            Bootstrap::SkipStartFill,
+            downloader
+                .previous_queued()
+                .map(|_| Message::PlayPreviousQueued)
+        ),
+        Space::with_width(4),
+        icon_button(
+            Bootstrap::SkipEndFill,
+            downloader.next_queued().map(|_| Message::PlayNextQueued)
+        ),
+        Space::with_width(8),
+        checkbox("Shuffle", downloader.shuffle)
+            .on_toggle(Message::ToggleShuffle)
+            .size(14)
+            .text_size(12)
+            .style(iced::theme::Checkbox::Custom(Box::new(ToggleStyle {
+                mode: theme_mode
+            }))),
+        Space::with_width(10),
+        text(queue_label)
+            .size(12)
+            .style(iced::theme::Text::Color(colors.text_secondary))
+            .width(Length::Fill),
+        build_crossfade_control(state, theme_mode),
+        Space::with_width(10),
+        button(
+            row![
+                icon_to_text(Bootstrap::CollectionPlay)
+                    .size(12.0)
+                    .style(iced::theme::Text::Color(Color::WHITE)),
+                Space::with_width(6),
+                text("Play Queue").size(12),
+            ]
+            .spacing(0)
+            .align_items(Alignment::Center)
+        )
+        .style(iced::theme::Button::Custom(Box::new(PrimaryButtonStyle {
+            mode: theme_mode,
+        })))
+        .on_press_maybe(
+            downloader
+                .queue_position
+                .is_none()
+                .then_some(Message::PlayQueue)
+        )
+        .padding([6, 12]),
+        Space::with_width(6),
+        icon_button(Bootstrap::Trash, Some(Message::ClearQueue)),
+    ]
+    .spacing(0)
+    .align_items(Alignment::Center)
+    .width(Length::Fill);
+
+    column![transport, Space::with_height(8), queue]
+        .spacing(0)
+        .width(Length::Fill)
+        .into()
+}
+
+/// Seek slider with an elapsed / total readout; `active` when it controls the player
+fn build_seek_bar(
+    state: &AppState,
+    active: bool,
+    total_secs: Option<u64>,
+    theme_mode: ThemeMode,
+) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let total = total_secs.unwrap_or(0).max(1) as f32;
+    let position = if active {
+        state.player.position.as_secs_f32()
+    } else {
+        0.0
+    };
+    let shown = state.player_seek.unwrap_or(position).min(total);
+    let clock = |secs: f32| format!("{}:{:02}", secs as u64 / 60, secs as u64 % 60);
+    let total_label = match total_secs {
+        Some(_) => clock(total),
+        None => "—".to_string(),
+    };
+
+    row![
+        slider(0.0..=total, shown, Message::PlayerSeek)
+            .on_release(Message::PlayerSeekReleased)
+            .step(1.0)
+            .width(Length::Fill),
+        Space::with_width(10),
+        text(format!("{} / {}", clock(shown), total_label))
+            .size(11)
+            .style(iced::theme::Text::Color(colors.text_secondary)),
+    ]
+    .spacing(0)
+    .align_items(Alignment::Center)
+    .width(Length::Fill)
+    .into()
+}
+
+fn build_volume_control(state: &AppState, theme_mode: ThemeMode) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    row![
+        icon_to_text(if state.player.volume > 0.0 {
+            Bootstrap::VolumeUpFill
+        } else {
+            Bootstrap::VolumeMuteFill
+        })
+        .size(12.0)
+        .style(iced::theme::Text::Color(colors.text_secondary)),
+        Space::with_width(4),
+        slider(0.0..=1.0, state.player.volume, Message::PlayerVolume)
+            .step(0.05)
+            .width(Length::Fixed(70.0)),
+    ]
+    .spacing(0)
+    .align_items(Alignment::Center)
+    .into()
+}
+
+/// Overlap between consecutive tracks, from off to ten seconds
+fn build_crossfade_control(state: &AppState, theme_mode: ThemeMode) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let label = if state.crossfade_secs > 0.0 {
+        format!("Crossfade {:.0}s", state.crossfade_secs)
+    } else {
+        "Crossfade off".to_string()
+    };
+    row![
+        text(label)
+            .size(11)
+            .style(iced::theme::Text::Color(colors.text_secondary)),
+        Space::with_width(6),
+        slider(0.0..=10.0, state.crossfade_secs, Message::CrossfadeChanged)
+            .step(1.0)
+            .on_release(Message::CrossfadeReleased)
+            .width(Length::Fixed(70.0)),
+    ]
+    .spacing(0)
+    .align_items(Alignment::Center)
+    .into()
+}
+
+fn build_metadata_row(
+    label: &str,
+    value: &str,
+    theme_mode: ThemeMode,
+) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let value_owned = value.to_string();
+    row![
+        text(label)
+            .size(12)
+            .style(iced::theme::Text::Color(colors.text_secondary))
+            .width(Length::Fixed(60.0)),
+        container(
+            text(value_owned)
+                .size(13)
+                .style(iced::theme::Text::Color(colors.text_primary))
+                .width(Length::Fill)
+        )
+        .width(Length::Fill)
+        .padding([8, 12])
+        .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+            mode: theme_mode
+        }))),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center)
+    .width(Length::Fill)
+    .into()
+}
+
+fn build_metadata_field(
+    label: &str,
+    value: &str,
+    theme_mode: ThemeMode,
+) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let value_owned = value.to_string();
+    column![
+        text(label)
+            .size(11)
+            .style(iced::theme::Text::Color(colors.text_secondary))
+            .width(Length::Fill),
+        Space::with_height(5),
+        container(
+            text(value_owned)
+                .size(13)
+                .style(iced::theme::Text::Color(colors.text_primary))
+                .width(Length::Fill)
+        )
+        .width(Length::Fill)
+        .padding([8, 12])
+        .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+            mode: theme_mode
+        }))),
+    ]
+    .spacing(0)
+    .width(Length::Fill)
+    .into()
+}
 
-    container(
-        column![
+fn build_edit_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+
+    // With a folder group selected, the fields edit that album; guesses and shared values
+    // become hints
+    let group = state.editing_group();
+    let (artist, album, year, album_art_path) = match group {
+        Some(g) => (
+            g.artist.as_deref().unwrap_or_default(),
+            g.album.as_deref().unwrap_or_default(),
+            g.year.as_deref().unwrap_or_default(),
+            &g.album_art_path,
+        ),
+        None => (
+            state.artist.as_str(),
+            state.album.as_str(),
+            state.year.as_str(),
+            &state.album_art_path,
+        ),
+    };
+    // Untouched group fields hint at the folder name's guess, else the shared value
+    let hint = |guess: fn(&AlbumGroup) -> &str, shared: &str, fallback: &str| match group {
+        Some(g) if !guess(g).trim().is_empty() => guess(g).to_string(),
+        Some(_) if !shared.trim().is_empty() => shared.to_string(),
+        _ => fallback.to_string(),
+    };
+
+    // Other editor tools open in the middle column
+    let tools = || {
+        pick_list(
+            EditorPanel::ALL.to_vec(),
+            Some(state.editor_panel),
+            Message::ShowEditorPanel,
+        )
+        .text_size(12)
+        .padding([4, 8])
+        .width(Length::Fixed(120.0))
+    };
+
+    let title: Element<Message> = match (group, state.selected_group) {
+        (Some(g), Some(index)) => column![
+            row![
+                text("Edit Album Folder")
+                    .size(14)
+                    .style(iced::theme::Text::Color(colors.text_primary))
+                    .width(Length::Fill),
+                button(text("Done").size(12))
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode }
+                    )))
+                    .on_press(Message::SelectAlbumGroup(index))
+                    .padding([4, 10]),
+            ]
+            .spacing(0)
+            .align_items(Alignment::Center)
+            .width(Length::Fill),
+            Space::with_height(4),
+            row![
+                text(g.display_name())
+                    .size(11)
+                    .style(iced::theme::Text::Color(colors.cosmic_accent))
+                    .width(Length::Fill)
+                    .shaping(iced::widget::text::Shaping::Advanced),
+                tools(),
+            ]
+            .spacing(6)
+            .align_items(Alignment::Center)
+            .width(Length::Fill),
+        ]
+        .spacing(0)
+        .width(Length::Fill)
+        .into(),
+        _ => row![
             text("Edit Metadata")
                 .size(14)
                 .style(iced::theme::Text::Color(colors.text_primary))
                 .width(Length::Fill),
+            tools(),
+        ]
+        .spacing(0)
+        .align_items(Alignment::Center)
+        .width(Length::Fill)
+        .into(),
+    };
+
+    let genre_suggestions = build_suggestions(
+        suggest_genres(&state.genre, &state.genre_vocabulary(), 3),
+        Message::GenreChanged,
+        theme_mode,
+    );
+
+    // Offer sidecar images (cover.jpg, folder.jpg, ...) found next to the scanned files
+    let directory_covers: Element<Message> = if state.directory_covers.is_empty() {
+        Space::with_height(0).into()
+    } else {
+        column![
+            Space::with_height(8),
+            build_rule_toggle(
+                &format!(
+                    "Use folder images ({} folder(s))",
+                    state.directory_covers.len()
+                ),
+                state.use_directory_covers,
+                Message::UseDirectoryCoversToggled,
+                theme_mode
+            ),
+        ]
+        .spacing(0)
+        .width(Length::Fill)
+        .into()
+    };
+
+    container(
+        column![
+            title,
             Space::with_height(14),
-            text("Artist")
-                .size(11)
-                .style(iced::theme::Text::Color(colors.text_secondary))
-                .width(Length::Fill),
-            Space::with_height(5),
-            text_input("Enter artist name", &state.artist)
-                .on_input(Message::ArtistChanged)
-                .width(Length::Fill)
-                .padding(10)
-                .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
-                    mode: theme_mode
-                }))),
-            Space::with_height(12),
-            text("Album")
-                .size(11)
-                .style(iced::theme::Text::Color(colors.text_secondary))
-                .width(Length::Fill),
-            Space::with_height(5),
-            text_input("Enter album name", &state.album)
-                .on_input(Message::AlbumChanged)
-                .width(Length::Fill)
-                .padding(10)
-                .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
-                    mode: theme_mode
-                }))),
-            Space::with_height(12),
-            row![
+            scrollable(
                 column![
-                    text("Genre")
+                    text("Artist")
                         .size(11)
                         .style(iced::theme::Text::Color(colors.text_secondary))
                         .width(Length::Fill),
                     Space::with_height(5),
-                    text_input("Genre", &state.genre)
-                        .on_input(Message::GenreChanged)
-                        .width(Length::Fill)
-                        .padding(10)
-                        .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
-                            mode: theme_mode
-                        }))),
-                ]
-                .spacing(0)
-                .width(Length::FillPortion(2)),
-                Space::with_width(10),
-                column![
-                    text("Year")
+                    text_input(
+                        &hint(|g| &g.guessed_artist, &state.artist, "Enter artist name"),
+                        artist
+                    )
+                    .on_input(Message::ArtistChanged)
+                    .width(Length::Fill)
+                    .padding(10)
+                    .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
+                        mode: theme_mode
+                    }))),
+                    build_suggestions(
+                        state.artist_suggestions.for_value(artist),
+                        Message::ArtistChanged,
+                        theme_mode
+                    ),
+                    Space::with_height(12),
+                    text("Album")
+                        .size(11)
+                        .style(iced::theme::Text::Color(colors.text_secondary))
+                        .width(Length::Fill),
+                    Space::with_height(5),
+                    text_input(
+                        &hint(|g| &g.guessed_album, &state.album, "Enter album name"),
+                        album
+                    )
+                    .on_input(Message::AlbumChanged)
+                    .width(Length::Fill)
+                    .padding(10)
+                    .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
+                        mode: theme_mode
+                    }))),
+                    build_suggestions(
+                        state.album_suggestions.for_value(album),
+                        Message::AlbumChanged,
+                        theme_mode
+                    ),
+                    Space::with_height(12),
+                    row![
+                        column![
+                            row![
+                                text("Genre")
+                                    .size(11)
+                                    .style(iced::theme::Text::Color(colors.text_secondary))
+                                    .width(Length::Fill),
+                                button(text("Manage").size(11))
+                                    .style(iced::theme::Button::Custom(Box::new(
+                                        TransparentButtonStyle {
+                                            mode: theme_mode,
+                                            is_selected: false
+                                        }
+                                    )))
+                                    .on_press(Message::ShowEditorPanel(EditorPanel::Genres))
+                                    .padding(0),
+                            ]
+                            .spacing(0)
+                            .align_items(Alignment::Center)
+                            .width(Length::Fill),
+                            Space::with_height(5),
+                            text_input("Genre", &state.genre)
+                                .on_input(Message::GenreChanged)
+                                .width(Length::Fill)
+                                .padding(10)
+                                .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
+                                    mode: theme_mode
+                                }))),
+                        ]
+                        .spacing(0)
+                        .width(Length::FillPortion(2)),
+                        Space::with_width(10),
+                        column![
+                            text("Year")
+                                .size(11)
+                                .style(iced::theme::Text::Color(colors.text_secondary))
+                                .width(Length::Fill),
+                            Space::with_height(5),
+                            text_input(&hint(|g| &g.guessed_year, &state.year, "Year"), year)
+                                .on_input(Message::YearChanged)
+                                .width(Length::Fill)
+                                .padding(10)
+                                .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
+                                    mode: theme_mode
+                                }))),
+                        ]
+                        .spacing(0)
+                        .width(Length::FillPortion(1)),
+                    ]
+                    .spacing(0)
+                    .width(Length::Fill),
+                    genre_suggestions,
+                    Space::with_height(14),
+                    text("Album Art")
                         .size(11)
                         .style(iced::theme::Text::Color(colors.text_secondary))
                         .width(Length::Fill),
                     Space::with_height(5),
-                    text_input("Year", &state.year)
-                        .on_input(Message::YearChanged)
+                    row![button(if album_art_path.is_some() {
+                        "Change Image"
+                    } else {
+                        "Select Image"
+                    })
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode }
+                    )))
+                    .on_press(Message::SelectImage)
+                    .padding([8, 14])
+                    .width(Length::Fill),]
+                    .spacing(0)
+                    .width(Length::Fill),
+                    Space::with_height(6),
+                    container(
+                        text({
+                            if let Some(path) = album_art_path {
+                                let filename = path
+                                    .file_name()
+                                    .and_then(|n| n.to_str())
+                                    .unwrap_or("Image selected");
+                                // Truncate long filenames (max 40 chars, show ... if truncated)
+                                if filename.chars().count() > 40 {
+                                    let truncated: String = filename.chars().take(37).collect();
+                                    format!("{}...", truncated)
+                                } else {
+                                    filename.to_string()
+                                }
+                            } else {
+                                "No image selected".to_string()
+                            }
+                        })
+                        .size(11)
+                        .style(iced::theme::Text::Color(if album_art_path.is_some() {
+                            colors.success
+                        } else {
+                            colors.text_disabled
+                        }))
                         .width(Length::Fill)
-                        .padding(10)
-                        .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
-                            mode: theme_mode
-                        }))),
+                    )
+                    .width(Length::Fill)
+                    .padding([8, 10])
+                    .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+                        mode: theme_mode
+                    }))),
+                    directory_covers,
+                    Space::with_height(14),
+                    text("Track Numbers")
+                        .size(11)
+                        .style(iced::theme::Text::Color(colors.text_secondary))
+                        .width(Length::Fill),
+                    Space::with_height(5),
+                    row![
+                        pick_list(
+                            TrackOrder::ALL.to_vec(),
+                            Some(state.track_order),
+                            Message::TrackOrderChanged
+                        )
+                        .text_size(12)
+                        .padding([6, 10])
+                        .width(Length::Fill),
+                        Space::with_width(6),
+                        button(text("Number").size(12))
+                            .style(iced::theme::Button::Custom(Box::new(
+                                SecondaryButtonStyle { mode: theme_mode }
+                            )))
+                            .on_press_maybe(if state.processing || state.files.is_empty() {
+                                None
+                            } else {
+                                Some(Message::NumberTracks)
+                            })
+                            .padding([6, 12]),
+                    ]
+                    .spacing(0)
+                    .align_items(Alignment::Center)
+                    .width(Length::Fill),
                 ]
                 .spacing(0)
-                .width(Length::FillPortion(1)),
-            ]
-            .spacing(0)
-            .width(Length::Fill),
-            Space::with_height(14),
-            text("Album Art")
-                .size(11)
-                .style(iced::theme::Text::Color(colors.text_secondary))
-                .width(Length::Fill),
-            Space::with_height(5),
-            row![button(if state.album_art_path.is_some() {
-                "Change Image"
-            } else {
-                "Select Image"
-            })
-            .style(iced::theme::Button::Custom(Box::new(
-                SecondaryButtonStyle { mode: theme_mode }
-            )))
-            .on_press(Message::SelectImage)
-            .padding([8, 14])
-            .width(Length::Fill),]
-            .spacing(0)
-            .width(Length::Fill),
-            Space::with_height(6),
-            container(
-                text({
-                    if let Some(ref path) = state.album_art_path {
-                        let filename = path
-                            .file_name()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("Image selected");
-                        // Truncate long filenames (max 40 chars, show ... if truncated)
-                        if filename.chars().count() > 40 {
-                            let truncated: String = filename.chars().take(37).collect();
-                            format!("{}...", truncated)
-                        } else {
-                            filename.to_string()
-                        }
-                    } else {
-                        "No image selected".to_string()
-                    }
-                })
-                .size(11)
-                .style(iced::theme::Text::Color(
-                    if state.album_art_path.is_some() {
-                        colors.success
-                    } else {
-                        colors.text_disabled
-                    }
-                ))
                 .width(Length::Fill)
+                .padding([0, 8, 0, 0])
             )
-            .width(Length::Fill)
-            .padding([8, 10])
-            .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
-                mode: theme_mode
-            }))),
-            Space::with_height(Length::Fill),
+            .height(Length::Fill),
+            Space::with_height(10),
             button(if state.processing {
                 // Pulsing icon during processing
                 let pulse = ((state.loading_rotation * 3.0).sin() + 1.0) / 2.0;
@@ -1146,10 +3228,18 @@ fn build_edit_panel(state: &AppState, theme_mode: ThemeMode) -> Element<'static,
                 .align_items(Alignment::Center)
                 .width(Length::Fill)
             } else {
-                row![text("Apply to All Files")
+                row![
+                    text(if state.checked_files.is_empty() && state.group_by_album {
+                        format!("Apply to {} Album(s)", state.album_groups.len())
+                    } else if state.checked_files.is_empty() {
+                        "Apply to All Files".to_string()
+                    } else {
+                        format!("Apply to {} Selected", state.checked_files.len())
+                    })
                     .size(14)
                     .width(Length::Fill)
-                    .horizontal_alignment(iced::alignment::Horizontal::Center),]
+                    .horizontal_alignment(iced::alignment::Horizontal::Center),
+                ]
                 .width(Length::Fill)
             })
             .style(iced::theme::Button::Custom(Box::new(
@@ -1329,6 +3419,23 @@ fn build_music_downloader(state: &AppState, theme_mode: ThemeMode) -> Element<'_
                             .width(Length::Fill),
                         selection_info,
                         Space::with_width(8),
+                        button(
+                            row![
+                                icon_to_text(Bootstrap::ListUl)
+                                    .size(12.0)
+                                    .style(iced::theme::Text::Color(Color::WHITE)),
+                                Space::with_width(6),
+                                text("Queue Selected").size(12),
+                            ]
+                            .spacing(0)
+                            .align_items(Alignment::Center)
+                        )
+                        .style(iced::theme::Button::Custom(Box::new(
+                            SecondaryButtonStyle { mode: theme_mode }
+                        )))
+                        .on_press_maybe((selected_count > 0).then_some(Message::QueueSelectedSongs))
+                        .padding([6, 12]),
+                        Space::with_width(6),
                         button(
                             row![
                                 icon_to_text(Bootstrap::CheckCircle)
@@ -1414,6 +3521,58 @@ fn build_music_downloader(state: &AppState, theme_mode: ThemeMode) -> Element<'_
                         .padding([6, 10])
                     };
 
+                    // Queue position of the song, or a button to queue it
+                    let queued_at = downloader.play_queue.iter().position(|&i| i == index);
+                    let queue_button = match queued_at {
+                        Some(entry) => button(
+                            text(format!("#{}", entry + 1))
+                                .size(11)
+                                .style(iced::theme::Text::Color(colors.cosmic_accent)),
+                        ),
+                        None => button(
+                            icon_to_text(Bootstrap::PlusCircle)
+                                .size(14.0)
+                                .style(iced::theme::Text::Color(colors.text_secondary)),
+                        ),
+                    }
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode },
+                    )))
+                    .on_press(Message::ToggleQueued(index))
+                    .padding([6, 8]);
+
+                    // Streaming state of the playing song: connecting, stalled, or how much has arrived
+                    let stream_label = if !is_playing {
+                        None
+                    } else {
+                        let source = downloader.streaming_url.clone().map(PlayerSource::Url);
+                        match source.filter(|s| state.player.is_active(s)) {
+                            None => Some("Connecting...".to_string()),
+                            Some(_) if state.player.state == PlaybackState::Loading => {
+                                Some("Buffering...".to_string())
+                            }
+                            Some(_) => match state.player.buffer {
+                                Some(status) if status.buffering => {
+                                    Some("Buffering...".to_string())
+                                }
+                                Some(status) => status
+                                    .fraction()
+                                    .filter(|f| *f < 1.0)
+                                    .map(|f| format!("{:.0}% loaded", f * 100.0)),
+                                None => None,
+                            },
+                        }
+                    }
+                    .map(|label| {
+                        text(label)
+                            .size(11)
+                            .style(iced::theme::Text::Color(colors.text_secondary))
+                    })
+                    .map_or_else(
+                        || Element::from(Space::with_width(0)),
+                        |label| row![label, Space::with_width(8)].into(),
+                    );
+
                     let song_item = container(
                         row![
                             checkbox("", is_selected)
@@ -1427,6 +3586,9 @@ fn build_music_downloader(state: &AppState, theme_mode: ThemeMode) -> Element<'_
                                 .style(iced::theme::Text::Color(colors.text_primary))
                                 .width(Length::Fill),
                             Space::with_width(10),
+                            stream_label,
+                            queue_button,
+                            Space::with_width(6),
                             play_button,
                         ]
                         .spacing(0)
@@ -1442,7 +3604,7 @@ fn build_music_downloader(state: &AppState, theme_mode: ThemeMode) -> Element<'_
                     songs_list = songs_list.push(song_item);
                 }
 
-                column![
+                let mut songs_column = column![
                     container(songs_header).width(Length::Fill).style(
                         iced::theme::Container::Custom(Box::new(CardStyle { mode: theme_mode }))
                     ),
@@ -1459,8 +3621,19 @@ fn build_music_downloader(state: &AppState, theme_mode: ThemeMode) -> Element<'_
                 ]
                 .spacing(0)
                 .width(Length::Fill)
-                .height(Length::Fill)
-                .into()
+                .height(Length::Fill);
+                let playing = downloader.playing_song_index.is_some();
+                if playing || !downloader.play_queue.is_empty() {
+                    songs_column = songs_column.push(Space::with_height(8)).push(
+                        container(build_now_playing(state, theme_mode))
+                            .width(Length::Fill)
+                            .padding([8, 12])
+                            .style(iced::theme::Container::Custom(Box::new(CardStyle {
+                                mode: theme_mode,
+                            }))),
+                    );
+                }
+                songs_column.into()
             }
         } else {
             // Show artists list
@@ -1684,17 +3857,7 @@ fn build_music_downloader(state: &AppState, theme_mode: ThemeMode) -> Element<'_
                         .padding([10, 16])
                         .width(Length::Fill),
                     Space::with_width(10),
-                    button(if downloader.downloading {
-                        row![
-                            icon_to_text(Bootstrap::ArrowClockwise)
-                                .size(14.0)
-                                .style(iced::theme::Text::Color(Color::WHITE)),
-                            Space::with_width(8),
-                            text("Downloading...").size(14),
-                        ]
-                        .spacing(0)
-                        .align_items(Alignment::Center)
-                    } else {
+                    button(
                         row![
                             icon_to_text(Bootstrap::CloudArrowDown)
                                 .size(14.0)
@@ -1705,17 +3868,15 @@ fn build_music_downloader(state: &AppState, theme_mode: ThemeMode) -> Element<'_
                         ]
                         .spacing(0)
                         .align_items(Alignment::Center)
-                    })
+                    )
                     .style(iced::theme::Button::Custom(Box::new(PrimaryButtonStyle {
                         mode: theme_mode
                     })))
-                    .on_press_maybe(
-                        if downloader.downloading || downloader.selected_songs.is_empty() {
-                            None
-                        } else {
-                            Some(Message::DownloadSelectedSongs)
-                        }
-                    )
+                    .on_press_maybe(if downloader.selected_songs.is_empty() {
+                        None
+                    } else {
+                        Some(Message::DownloadSelectedSongs)
+                    })
                     .padding([10, 16])
                     .width(Length::Fill),
                 ]
@@ -1786,7 +3947,13 @@ fn build_music_downloader(state: &AppState, theme_mode: ThemeMode) -> Element<'_
                 }))),
             ]
             .spacing(0)
-            .width(Length::Fill),
+            .width(Length::Fill)
+            .push_maybe((!downloader.downloads.items.is_empty()).then(|| {
+                column![
+                    Space::with_height(8),
+                    build_download_list(downloader, theme_mode)
+                ]
+            })),
         )
         .width(Length::Fill)
         .padding([12, 14, 12, 14])
@@ -1795,8 +3962,9 @@ fn build_music_downloader(state: &AppState, theme_mode: ThemeMode) -> Element<'_
         })))
     } else {
         // Show simple status when no artist selected
+        // Downloads resumed from the last session show before any artist is picked
         container(
-            container(
+            column![container(
                 text(&downloader.status)
                     .size(12)
                     .style(iced::theme::Text::Color(colors.text_secondary))
@@ -1806,7 +3974,13 @@ fn build_music_downloader(state: &AppState, theme_mode: ThemeMode) -> Element<'_
             .padding([10, 12])
             .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
                 mode: theme_mode,
-            }))),
+            })))]
+            .push_maybe((!downloader.downloads.items.is_empty()).then(|| {
+                column![
+                    Space::with_height(8),
+                    build_download_list(downloader, theme_mode)
+                ]
+            })),
         )
         .width(Length::Fill)
         .padding([12, 14, 12, 14])
@@ -1853,6 +4027,154 @@ fn build_music_downloader(state: &AppState, theme_mode: ThemeMode) -> Element<'_
 
 // ============== AUDIO CONVERTER (PLACEHOLDER) ==============
 
+/// Live list of the download queue: state, bytes, speed and time left per song, with controls
+fn build_download_list(
+    downloader: &MusicDownloaderState,
+    theme_mode: ThemeMode,
+) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    let icon_button = |icon: Bootstrap, message: Message| {
+        button(
+            icon_to_text(icon)
+                .size(11.0)
+                .style(iced::theme::Text::Color(colors.text_secondary)),
+        )
+        .style(iced::theme::Button::Custom(Box::new(
+            SecondaryButtonStyle { mode: theme_mode },
+        )))
+        .on_press(message)
+        .padding([3, 6])
+    };
+    let mut list = Column::new().spacing(4).width(Length::Fill);
+    if downloader
+        .downloads
+        .items
+        .iter()
+        .any(|item| item.state == DownloadState::Done)
+    {
+        list = list.push(
+            row![
+                Space::with_width(Length::Fill),
+                button(text("Clear Completed").size(11))
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode }
+                    )))
+                    .on_press(Message::ClearFinishedDownloads)
+                    .padding([4, 10]),
+            ]
+            .width(Length::Fill),
+        );
+    }
+    for item in &downloader.downloads.items {
+        let (icon, icon_color) = match item.state {
+            DownloadState::Queued => (Bootstrap::Clock, colors.text_disabled),
+            DownloadState::ResolvingUrl => (Bootstrap::Search, colors.info),
+            DownloadState::Downloading => (Bootstrap::CloudArrowDown, colors.info),
+            DownloadState::Paused => (Bootstrap::PauseCircle, colors.text_secondary),
+            DownloadState::Retrying(_) => (Bootstrap::ArrowClockwise, colors.warning),
+            DownloadState::Done => (Bootstrap::CheckCircle, colors.success),
+            DownloadState::Failed(_) => (Bootstrap::XCircle, colors.error),
+        };
+        let size = match item.total {
+            Some(total) => format!("{} / {}", format_size(item.downloaded), format_size(total)),
+            None => format_size(item.downloaded),
+        };
+        let detail = match &item.state {
+            DownloadState::Queued => "Queued".to_string(),
+            DownloadState::ResolvingUrl => "Resolving URL...".to_string(),
+            DownloadState::Downloading => {
+                let mut parts = vec![size];
+                if item.speed > 0.0 {
+                    parts.push(format!("{}/s", format_size(item.speed as u64)));
+                }
+                if let Some(eta) = item.eta() {
+                    let secs = eta.as_secs();
+                    parts.push(format!("{}:{:02} left", secs / 60, secs % 60));
+                }
+                parts.join("  ·  ")
+            }
+            DownloadState::Paused if item.downloaded > 0 => format!("Paused at {}", size),
+            DownloadState::Paused => "Paused".to_string(),
+            DownloadState::Retrying(reason) => format!(
+                "Retrying ({} of {}): {}",
+                item.attempts + 1,
+                MAX_ATTEMPTS,
+                reason
+            ),
+            DownloadState::Done => format_size(item.downloaded),
+            DownloadState::Failed(reason) => reason.clone(),
+        };
+        let detail_color = match item.state {
+            DownloadState::Failed(_) => colors.error,
+            DownloadState::Retrying(_) => colors.warning,
+            _ => colors.text_secondary,
+        };
+
+        // Pause while it is waiting or running, resume or retry once stopped
+        let toggle = match item.state {
+            DownloadState::Done => None,
+            DownloadState::Paused => Some((Bootstrap::PlayFill, Message::ResumeDownload(item.id))),
+            DownloadState::Failed(_) => {
+                Some((Bootstrap::ArrowClockwise, Message::ResumeDownload(item.id)))
+            }
+            _ => Some((Bootstrap::PauseFill, Message::PauseDownload(item.id))),
+        };
+        let controls = toggle.map(|(toggle_icon, message)| {
+            row![
+                icon_button(toggle_icon, message),
+                Space::with_width(4),
+                icon_button(Bootstrap::XLg, Message::CancelDownload(item.id)),
+            ]
+            .align_items(Alignment::Center)
+        });
+
+        list = list.push(
+            row![
+                icon_to_text(icon)
+                    .size(12.0)
+                    .style(iced::theme::Text::Color(icon_color)),
+                Space::with_width(8),
+                text(&item.title)
+                    .size(12)
+                    .style(iced::theme::Text::Color(colors.text_primary))
+                    .shaping(iced::widget::text::Shaping::Advanced)
+                    .width(Length::FillPortion(3)),
+                Space::with_width(8),
+                progress_bar(0.0..=1.0, item.fraction().unwrap_or(0.0))
+                    .height(Length::Fixed(6.0))
+                    .width(Length::FillPortion(2)),
+                Space::with_width(8),
+                text(detail)
+                    .size(11)
+                    .style(iced::theme::Text::Color(detail_color))
+                    .width(Length::FillPortion(3)),
+            ]
+            .spacing(0)
+            .align_items(Alignment::Center)
+            .width(Length::Fill)
+            .push_maybe(controls),
+        );
+    }
+
+    container(scrollable(list).height(Length::Shrink))
+        .width(Length::Fill)
+        .max_height(180)
+        .padding([8, 12])
+        .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+            mode: theme_mode,
+        })))
+        .into()
+}
+
+/// Byte count in B, KB or MB
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MB", b as f64 / (1024.0 * 1024.0)),
+        b if b >= 1024 => format!("{:.0} KB", b as f64 / 1024.0),
+        b => format!("{} B", b),
+    }
+}
+
 fn build_audio_converter(state: &AppState, theme_mode: ThemeMode) -> Element<'_, Message> {
     let colors = get_colors(theme_mode);
     let header = build_app_header(
@@ -2017,3 +4339,266 @@ impl iced::widget::button::StyleSheet for FormatButtonStyle {
         self.hovered(style)
     }
 }
+
+// ============== FOLDER WATCHER ==============
+
+fn build_folder_watcher(state: &AppState, theme_mode: ThemeMode) -> Element<'_, Message> {
+    let colors = get_colors(theme_mode);
+    let header = build_app_header(
+        "Folder Watcher",
+        "Automatically tag new audio files in a folder",
+        theme_mode,
+    );
+
+    let folder_label = state
+        .watch_folder
+        .as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "No folder selected".to_string());
+
+    let rules_panel = container(
+        column![
+            text("Watched Folder")
+                .size(14)
+                .style(iced::theme::Text::Color(colors.text_primary))
+                .width(Length::Fill),
+            Space::with_height(10),
+            container(
+                text(folder_label)
+                    .size(12)
+                    .style(iced::theme::Text::Color(if state.watch_folder.is_some() {
+                        colors.cosmic_accent
+                    } else {
+                        colors.text_disabled
+                    }))
+                    .width(Length::Fill)
+                    .shaping(iced::widget::text::Shaping::Advanced)
+            )
+            .width(Length::Fill)
+            .padding([8, 10])
+            .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+                mode: theme_mode
+            }))),
+            Space::with_height(8),
+            button("Select Folder")
+                .style(iced::theme::Button::Custom(Box::new(
+                    SecondaryButtonStyle { mode: theme_mode }
+                )))
+                .on_press(Message::SelectWatchFolder)
+                .padding([8, 12])
+                .width(Length::Fill),
+            Space::with_height(18),
+            text("Rules")
+                .size(14)
+                .style(iced::theme::Text::Color(colors.text_primary))
+                .width(Length::Fill),
+            Space::with_height(10),
+            build_rule_toggle(
+                "Parse artist and title from file name",
+                state.watch_rules.parse_filename,
+                Message::WatchParseFilenameToggled,
+                theme_mode
+            ),
+            Space::with_height(8),
+            build_rule_toggle(
+                "Use folder image (cover.jpg, folder.jpg) as cover",
+                state.watch_rules.use_folder_cover,
+                Message::WatchFolderCoverToggled,
+                theme_mode
+            ),
+            Space::with_height(12),
+            text("Default Genre")
+                .size(11)
+                .style(iced::theme::Text::Color(colors.text_secondary))
+                .width(Length::Fill),
+            Space::with_height(5),
+            text_input("Leave empty to skip", &state.watch_rules.default_genre)
+                .on_input(Message::WatchDefaultGenreChanged)
+                .width(Length::Fill)
+                .padding(10)
+                .style(iced::theme::TextInput::Custom(Box::new(TextInputStyle {
+                    mode: theme_mode
+                }))),
+            Space::with_height(Length::Fill),
+            button(
+                row![
+                    icon_to_text(if state.watch_active {
+                        Bootstrap::StopCircle
+                    } else {
+                        Bootstrap::PlayCircle
+                    })
+                    .size(14.0)
+                    .style(iced::theme::Text::Color(Color::WHITE)),
+                    Space::with_width(8),
+                    text(if state.watch_active {
+                        "Stop Watching"
+                    } else {
+                        "Start Watching"
+                    })
+                    .size(14),
+                ]
+                .spacing(0)
+                .align_items(Alignment::Center)
+            )
+            .style(iced::theme::Button::Custom(Box::new(PrimaryButtonStyle {
+                mode: theme_mode
+            })))
+            .on_press_maybe(if state.watch_folder.is_some() {
+                Some(Message::ToggleWatching)
+            } else {
+                None
+            })
+            .padding([12, 16])
+            .width(Length::Fill),
+        ]
+        .spacing(0)
+        .width(Length::Fill)
+        .height(Length::Fill),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .padding([12, 14, 12, 14])
+    .style(iced::theme::Container::Custom(Box::new(CardStyle {
+        mode: theme_mode,
+    })));
+
+    let log_list: Element<Message> = if state.watch_log.is_empty() {
+        container(
+            text(if state.watch_active {
+                "Waiting for new files..."
+            } else {
+                "Tagged files will appear here"
+            })
+            .size(12)
+            .style(iced::theme::Text::Color(colors.text_disabled)),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x()
+        .center_y()
+        .into()
+    } else {
+        let mut log_column = Column::new().spacing(4).width(Length::Fill);
+        for entry in &state.watch_log {
+            log_column = log_column.push(
+                text(entry)
+                    .size(12)
+                    .style(iced::theme::Text::Color(if entry.starts_with('✗') {
+                        colors.error
+                    } else {
+                        colors.text_primary
+                    }))
+                    .width(Length::Fill)
+                    .shaping(iced::widget::text::Shaping::Advanced),
+            );
+        }
+        scrollable(container(log_column).width(Length::Fill).padding([8, 10]))
+            .height(Length::Fill)
+            .into()
+    };
+
+    let log_panel = container(
+        column![
+            row![
+                text("Activity")
+                    .size(14)
+                    .style(iced::theme::Text::Color(colors.text_primary)),
+                Space::with_width(Length::Fill),
+                button(text("Clear").size(12))
+                    .style(iced::theme::Button::Custom(Box::new(
+                        SecondaryButtonStyle { mode: theme_mode }
+                    )))
+                    .on_press_maybe(if state.watch_log.is_empty() {
+                        None
+                    } else {
+                        Some(Message::ClearWatchLog)
+                    })
+                    .padding([6, 12]),
+            ]
+            .spacing(0)
+            .align_items(Alignment::Center)
+            .width(Length::Fill),
+            Space::with_height(10),
+            container(log_list)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+                    mode: theme_mode
+                }))),
+            Space::with_height(10),
+            container(
+                text(&state.watch_status)
+                    .size(12)
+                    .style(iced::theme::Text::Color(if state.watch_active {
+                        colors.info
+                    } else {
+                        colors.text_secondary
+                    }))
+                    .width(Length::Fill)
+                    .shaping(iced::widget::text::Shaping::Advanced)
+            )
+            .width(Length::Fill)
+            .padding([10, 12])
+            .style(iced::theme::Container::Custom(Box::new(FileItemStyle {
+                mode: theme_mode
+            }))),
+        ]
+        .spacing(0)
+        .width(Length::Fill)
+        .height(Length::Fill),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .padding([12, 14, 12, 14])
+    .style(iced::theme::Container::Custom(Box::new(CardStyle {
+        mode: theme_mode,
+    })));
+
+    column![
+        header,
+        Space::with_height(8),
+        row![
+            container(rules_panel)
+                .width(Length::FillPortion(2))
+                .height(Length::Fill)
+                .padding([0, 6, 0, 12]),
+            container(log_panel)
+                .width(Length::FillPortion(3))
+                .height(Length::Fill)
+                .padding([0, 12, 0, 6]),
+        ]
+        .spacing(0)
+        .width(Length::Fill)
+        .height(Length::Fill),
+        Space::with_height(12),
+    ]
+    .spacing(0)
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .into()
+}
+
+fn build_rule_toggle(
+    label: &str,
+    enabled: bool,
+    on_toggle: fn(bool) -> Message,
+    theme_mode: ThemeMode,
+) -> Element<'static, Message> {
+    let colors = get_colors(theme_mode);
+    row![
+        checkbox("", enabled)
+            .on_toggle(on_toggle)
+            .style(iced::theme::Checkbox::Custom(Box::new(ToggleStyle {
+                mode: theme_mode
+            }))),
+        Space::with_width(8),
+        text(label.to_string())
+            .size(12)
+            .style(iced::theme::Text::Color(colors.text_primary))
+            .width(Length::Fill),
+    ]
+    .spacing(0)
+    .align_items(Alignment::Center)
+    .width(Length::Fill)
+    .into()
+}